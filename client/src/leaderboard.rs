@@ -0,0 +1,52 @@
+//! Off-chain reader that ranks miners from their statistics accounts.
+//!
+//! Fetch the accounts with `getProgramAccounts` filtered on `dataSize == MINER_SPACE`
+//! and pass the (pubkey, data) pairs in.
+
+use Hihi::state::{MinerState, MINER_SPACE};
+
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use std::cmp::Ordering;
+
+/// Miners of `instance` ordered by tokens earned, then puzzles solved, then
+/// limit breaks, then highest difficulty. Accounts that aren't miner accounts
+/// of the instance are skipped.
+pub fn rank_miners(instance: &Pubkey, accounts: &[(Pubkey, Vec<u8>)]) -> Vec<(Pubkey, MinerState)> {
+    let mut miners: Vec<(Pubkey, MinerState)> = accounts
+        .iter()
+        .filter(|(_, data)| data.len() == MINER_SPACE)
+        .filter_map(|(key, data)| match MinerState::unpack(data) {
+            Ok(miner) if &miner.instance_id == instance => Some((*key, miner)),
+            _ => None,
+        })
+        .collect();
+    miners.sort_by(|(_, a), (_, b)| compare_miners(b, a));
+    miners
+}
+
+fn compare_miners(a: &MinerState, b: &MinerState) -> Ordering {
+    a.tokens_earned
+        .cmp(&b.tokens_earned)
+        .then(a.puzzles_solved.cmp(&b.puzzles_solved))
+        .then(a.limit_breaks.cmp(&b.limit_breaks))
+        .then(a.highest_difficulty.cmp(&b.highest_difficulty))
+}
+
+/// The ranking as CSV, one miner per line.
+pub fn leaderboard_csv(miners: &[(Pubkey, MinerState)]) -> String {
+    let mut out = "rank,wallet,tokens_earned,puzzles_solved,limit_breaks,highest_difficulty,first_slot,last_slot\n".to_owned();
+    for (rank, (_, miner)) in miners.iter().enumerate() {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            rank + 1,
+            miner.wallet_id,
+            miner.tokens_earned,
+            miner.puzzles_solved,
+            miner.limit_breaks,
+            miner.highest_difficulty,
+            miner.first_slot,
+            miner.last_slot
+        ));
+    }
+    out
+}
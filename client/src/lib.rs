@@ -0,0 +1,3 @@
+//! Off-chain readers of Hihi accounts, kept out of the on-chain program.
+
+pub mod leaderboard;
@@ -0,0 +1,78 @@
+use hihi_client::leaderboard::{leaderboard_csv, rank_miners};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use Hihi::state::{MinerState, MINER_SPACE};
+
+fn miner(
+    instance: &Pubkey,
+    tokens_earned: u64,
+    puzzles_solved: u64,
+    limit_breaks: u32,
+) -> MinerState {
+    MinerState {
+        is_initialized: true,
+        instance_id: *instance,
+        wallet_id: Pubkey::new_unique(),
+        bump: 255,
+        puzzles_solved,
+        tokens_earned,
+        limit_breaks,
+        first_slot: 10,
+        last_slot: 20,
+        highest_difficulty: 3,
+    }
+}
+
+fn account(miner: &MinerState) -> (Pubkey, Vec<u8>) {
+    let mut data = vec![0; MINER_SPACE];
+    miner.pack_into_slice(&mut data);
+    (Pubkey::new_unique(), data)
+}
+
+#[test]
+fn miners_are_ranked_by_tokens_then_puzzles_then_limit_breaks() {
+    let instance = Pubkey::new_unique();
+    let first = miner(&instance, 500, 1, 0);
+    let second = miner(&instance, 100, 5, 0);
+    let third = miner(&instance, 100, 4, 2);
+    let fourth = miner(&instance, 100, 4, 1);
+    let other = miner(&Pubkey::new_unique(), 1000, 9, 9);
+
+    let mut accounts: Vec<_> = [&fourth, &second, &other, &first, &third]
+        .iter()
+        .map(|miner| account(miner))
+        .collect();
+    //not a miner account, skipped.
+    accounts.push((Pubkey::new_unique(), vec![1; MINER_SPACE + 1]));
+
+    let ranked = rank_miners(&instance, &accounts);
+    let wallets: Vec<_> = ranked.iter().map(|(_, miner)| miner.wallet_id).collect();
+    assert_eq!(
+        wallets,
+        vec![
+            first.wallet_id,
+            second.wallet_id,
+            third.wallet_id,
+            fourth.wallet_id
+        ]
+    );
+    assert_eq!(ranked[0].0, accounts[3].0);
+}
+
+#[test]
+fn leaderboard_is_one_csv_line_per_miner() {
+    let instance = Pubkey::new_unique();
+    let first = miner(&instance, 500, 1, 0);
+    let second = miner(&instance, 100, 5, 2);
+    let ranked = rank_miners(&instance, &[account(&second), account(&first)]);
+
+    assert_eq!(
+        leaderboard_csv(&ranked),
+        format!(
+            "rank,wallet,tokens_earned,puzzles_solved,limit_breaks,highest_difficulty,first_slot,last_slot\n\
+             1,{},500,1,0,3,10,20\n\
+             2,{},100,5,2,3,10,20\n",
+            first.wallet_id, second.wallet_id
+        )
+    );
+    assert_eq!(leaderboard_csv(&[]).lines().count(), 1);
+}
@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use solana_program::pubkey::Pubkey;
+use Hihi::processor::check_claim;
+
+// The first 64 bytes are the claim and pool keys, the rest is the work.
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 64 {
+        return;
+    }
+    let (keys, work) = data.split_at(64);
+    let claim = Pubkey::new(&keys[..32]);
+    let pool = Pubkey::new(&keys[32..]);
+    let _ = check_claim(&claim, &pool, work);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use Hihi::instruction::HihiInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(ix) = HihiInstruction::unpack(data) {
+        assert_eq!(HihiInstruction::unpack(&ix.pack()).unwrap(), ix);
+    }
+});
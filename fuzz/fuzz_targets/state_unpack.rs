@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use solana_program::program_pack::Pack;
+use Hihi::state::{HihiState, STATE_SPACE};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(state) = HihiState::unpack_from_slice(data) {
+        if state.is_initialized {
+            let mut packed = vec![0; STATE_SPACE];
+            state.pack_into_slice(&mut packed);
+            assert_eq!(HihiState::unpack_from_slice(&packed).unwrap(), state);
+        }
+    }
+});
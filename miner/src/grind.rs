@@ -0,0 +1,198 @@
+//! Claim key grinding. Candidate pubkeys are made a batch at a time, compressed with one
+//! shared field inversion, and each is hashed against every target so a single key
+//! generation is checked against all open puzzles.
+
+use crate::keypair::{expand_seed, ClaimKey, ExpandedKeypair};
+use curve25519_dalek::{constants::ED25519_BASEPOINT_POINT, edwards::EdwardsPoint, scalar::Scalar};
+use sha2::{Digest, Sha256, Sha512};
+use solana_program::pubkey::Pubkey;
+
+/// Offset of the magic bytes in a puzzle: tokens, sha, magic length.
+const MAGIC_OFFSET: usize = 1 + 32 + 1;
+
+/// How candidate keys are made.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// A fresh seed and a full base point multiplication per key, exportable to the CLI.
+    Seed,
+    /// One point addition per key from a random start, not exportable to the CLI.
+    Step,
+}
+
+/// The claim hash prefix a key has to produce.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    pub sha: [u8; 32],
+    pub magic: Vec<u8>,
+}
+
+impl Target {
+    /// Target of a puzzle, `bytes` limits it to the first magic bytes, as for pool shares.
+    pub fn from_work(work: &[u8], bytes: Option<usize>) -> Option<Self> {
+        if work.len() < MAGIC_OFFSET || work.len() - MAGIC_OFFSET < work[33] as usize {
+            return None;
+        }
+        let mut sha = [0; 32];
+        sha.copy_from_slice(&work[1..33]);
+        let mut len = work[33] as usize;
+        if let Some(bytes) = bytes {
+            len = len.min(bytes);
+        }
+        Some(Self {
+            sha,
+            magic: work[MAGIC_OFFSET..MAGIC_OFFSET + len].to_vec(),
+        })
+    }
+
+    /// `sha256(sha || claim || pool)` starts with the magic.
+    pub fn is_solved_by(&self, claim: &[u8; 32], pool: &Pubkey) -> bool {
+        let mut hasher = Sha256::new();
+        hasher.update(self.sha);
+        hasher.update(claim);
+        hasher.update(pool.as_ref());
+        hasher.finalize().starts_with(&self.magic)
+    }
+}
+
+/// A key solving `targets[target]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Found {
+    pub target: usize,
+    pub key: ClaimKey,
+}
+
+//position of a stepped search: `scalar + offset` is the key of `point`.
+struct Stepper {
+    scalar: Scalar,
+    prefix: [u8; 32],
+    point: EdwardsPoint,
+    offset: u64,
+}
+
+impl Stepper {
+    fn new(seed: &[u8; 32]) -> Self {
+        let (scalar, prefix) = expand_seed(seed);
+        Self {
+            scalar,
+            prefix,
+            point: EdwardsPoint::mul_base(&scalar),
+            offset: 0,
+        }
+    }
+}
+
+pub struct Grinder {
+    targets: Vec<Target>,
+    pool: Pubkey,
+    batch: usize,
+    mode: Mode,
+    stepper: Option<Stepper>,
+    /// Candidate keys generated so far.
+    pub tried: u64,
+}
+
+impl Grinder {
+    pub fn new(targets: Vec<Target>, pool: Pubkey, batch: usize, mode: Mode) -> Self {
+        Self {
+            targets,
+            pool,
+            batch: batch.max(1),
+            mode,
+            stepper: None,
+            tried: 0,
+        }
+    }
+
+    /// Starts stepping from `seed` instead of a random one.
+    pub fn with_start(mut self, seed: &[u8; 32]) -> Self {
+        self.stepper = Some(Stepper::new(seed));
+        self
+    }
+
+    pub fn targets(&self) -> &[Target] {
+        &self.targets
+    }
+
+    /// Generates and checks one batch of keys.
+    pub fn next_batch(&mut self) -> Vec<Found> {
+        self.tried += self.batch as u64;
+        match self.mode {
+            Mode::Seed => self.seed_batch(),
+            Mode::Step => self.step_batch(),
+        }
+    }
+
+    fn seed_batch(&mut self) -> Vec<Found> {
+        let mut seeds = vec![[0u8; 32]; self.batch];
+        for seed in seeds.iter_mut() {
+            getrandom::getrandom(seed).expect("no system randomness");
+        }
+        let points: Vec<EdwardsPoint> = seeds
+            .iter()
+            .map(|seed| EdwardsPoint::mul_base(&expand_seed(seed).0))
+            .collect();
+        let mut found = Vec::new();
+        for (i, compressed) in EdwardsPoint::compress_batch_alloc(&points)
+            .iter()
+            .enumerate()
+        {
+            let claim = compressed.to_bytes();
+            for target in self.solved(&claim) {
+                found.push(Found {
+                    target,
+                    key: ClaimKey::Seed {
+                        seed: seeds[i],
+                        pubkey: Pubkey::new_from_array(claim),
+                    },
+                });
+            }
+        }
+        found
+    }
+
+    fn step_batch(&mut self) -> Vec<Found> {
+        if self.stepper.is_none() {
+            let mut seed = [0; 32];
+            getrandom::getrandom(&mut seed).expect("no system randomness");
+            self.stepper = Some(Stepper::new(&seed));
+        }
+        let stepper = self.stepper.as_mut().unwrap();
+        let mut points = Vec::with_capacity(self.batch);
+        for _ in 0..self.batch {
+            points.push(stepper.point);
+            stepper.point += ED25519_BASEPOINT_POINT;
+        }
+        let start = stepper.offset;
+        stepper.offset += self.batch as u64;
+
+        let mut found = Vec::new();
+        for (i, compressed) in EdwardsPoint::compress_batch_alloc(&points)
+            .iter()
+            .enumerate()
+        {
+            let claim = compressed.to_bytes();
+            for target in self.solved(&claim) {
+                let stepper = self.stepper.as_ref().unwrap();
+                let scalar = stepper.scalar + Scalar::from(start + i as u64);
+                //every key gets its own nonce prefix, signing one message with two keys
+                //that share a prefix and differ by a known step would leak the scalar.
+                let mut hasher = Sha512::new();
+                hasher.update(stepper.prefix);
+                hasher.update(scalar.as_bytes());
+                let mut prefix = [0; 32];
+                prefix.copy_from_slice(&hasher.finalize()[..32]);
+                found.push(Found {
+                    target,
+                    key: ClaimKey::Stepped(ExpandedKeypair::new(scalar, prefix)),
+                });
+            }
+        }
+        found
+    }
+
+    fn solved(&self, claim: &[u8; 32]) -> Vec<usize> {
+        (0..self.targets.len())
+            .filter(|&t| self.targets[t].is_solved_by(claim, &self.pool))
+            .collect()
+    }
+}
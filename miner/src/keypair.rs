@@ -0,0 +1,177 @@
+//! Claim keys found by the grinder and how they are exported.
+//!
+//! Keys ground from seeds are ordinary ed25519 keypairs and export to the Solana CLI JSON
+//! format (`[seed || pubkey]`). Stepped keys are `a + i` for a random scalar `a`, there is
+//! no seed that hashes to them, so they can't be written in that format. They export to
+//! `<pubkey>.expanded` as a JSON object of the hex scalar and prefix instead, which the CLI
+//! refuses to load, and sign through `ExpandedKeypair`.
+
+use curve25519_dalek::{edwards::EdwardsPoint, scalar::clamp_integer, scalar::Scalar};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha512};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signature, Signer, SignerError};
+
+pub const EXPANDED_BYTES: usize = 64;
+
+/// An ed25519 secret as the scalar and nonce prefix a seed expands to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpandedKeypair {
+    scalar: Scalar,
+    prefix: [u8; 32],
+    pubkey: Pubkey,
+}
+
+impl ExpandedKeypair {
+    pub fn new(scalar: Scalar, prefix: [u8; 32]) -> Self {
+        let pubkey = Pubkey::new_from_array(EdwardsPoint::mul_base(&scalar).compress().to_bytes());
+        Self {
+            scalar,
+            prefix,
+            pubkey,
+        }
+    }
+
+    /// The standard ed25519 expansion of `seed`.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let (scalar, prefix) = expand_seed(seed);
+        Self::new(scalar, prefix)
+    }
+
+    pub fn to_bytes(&self) -> [u8; EXPANDED_BYTES] {
+        let mut bytes = [0; EXPANDED_BYTES];
+        bytes[..32].copy_from_slice(self.scalar.as_bytes());
+        bytes[32..].copy_from_slice(&self.prefix);
+        bytes
+    }
+
+    /// Rejects input that isn't a canonical scalar followed by a prefix.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != EXPANDED_BYTES {
+            return None;
+        }
+        let mut scalar = [0; 32];
+        scalar.copy_from_slice(&bytes[..32]);
+        let scalar = Option::<Scalar>::from(Scalar::from_canonical_bytes(scalar))?;
+        let mut prefix = [0; 32];
+        prefix.copy_from_slice(&bytes[32..]);
+        Some(Self::new(scalar, prefix))
+    }
+
+    /// `{"pubkey": .., "scalar": "<hex>", "prefix": "<hex>"}`.
+    pub fn to_json(&self) -> String {
+        json!({
+            "pubkey": self.pubkey.to_string(),
+            "scalar": hex::encode(self.scalar.as_bytes()),
+            "prefix": hex::encode(self.prefix),
+        })
+        .to_string()
+    }
+
+    /// Read `to_json` output, rejecting keys whose scalar doesn't match the pubkey.
+    pub fn from_json(json: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(json).ok()?;
+        let mut bytes = hex::decode(value["scalar"].as_str()?).ok()?;
+        bytes.extend(hex::decode(value["prefix"].as_str()?).ok()?);
+        let keypair = Self::from_bytes(&bytes)?;
+        if keypair.pubkey.to_string() != value["pubkey"].as_str()? {
+            return None;
+        }
+        Some(keypair)
+    }
+
+    /// RFC 8032 signature of `message`.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        let r = hash_to_scalar(&[&self.prefix, message]);
+        let big_r = EdwardsPoint::mul_base(&r).compress();
+        let k = hash_to_scalar(&[big_r.as_bytes(), self.pubkey.as_ref(), message]);
+        let s = r + k * self.scalar;
+        let mut signature = [0; 64];
+        signature[..32].copy_from_slice(big_r.as_bytes());
+        signature[32..].copy_from_slice(s.as_bytes());
+        Signature::new(&signature)
+    }
+}
+
+impl Signer for ExpandedKeypair {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        Ok(self.sign(message))
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// A claim key that solves a target.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClaimKey {
+    Seed { seed: [u8; 32], pubkey: Pubkey },
+    Stepped(ExpandedKeypair),
+}
+
+impl ClaimKey {
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            Self::Seed { pubkey, .. } => *pubkey,
+            Self::Stepped(keypair) => keypair.pubkey,
+        }
+    }
+
+    /// The solana keypair, `None` for stepped keys which have no seed.
+    pub fn keypair(&self) -> Option<Keypair> {
+        match self {
+            Self::Seed { seed, .. } => keypair_from_seed(seed).ok(),
+            Self::Stepped(_) => None,
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        match self {
+            Self::Seed { .. } => self.keypair().unwrap().sign_message(message),
+            Self::Stepped(keypair) => keypair.sign(message),
+        }
+    }
+
+    /// Solana CLI JSON keypair for seed keys, `ExpandedKeypair::to_json` for stepped keys.
+    pub fn to_json(&self) -> String {
+        match self {
+            Self::Seed { .. } => {
+                serde_json::to_string(&self.keypair().unwrap().to_bytes().to_vec()).unwrap()
+            }
+            Self::Stepped(keypair) => keypair.to_json(),
+        }
+    }
+
+    /// `<pubkey>.json` for seed keys, `<pubkey>.expanded` for stepped keys.
+    pub fn file_name(&self) -> String {
+        match self {
+            Self::Seed { .. } => format!("{}.json", self.pubkey()),
+            Self::Stepped(_) => format!("{}.expanded", self.pubkey()),
+        }
+    }
+}
+
+/// Scalar and nonce prefix of an ed25519 seed.
+pub fn expand_seed(seed: &[u8; 32]) -> (Scalar, [u8; 32]) {
+    let hash = Sha512::digest(seed);
+    let mut low = [0; 32];
+    low.copy_from_slice(&hash[..32]);
+    let mut prefix = [0; 32];
+    prefix.copy_from_slice(&hash[32..]);
+    (Scalar::from_bytes_mod_order(clamp_integer(low)), prefix)
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut wide = [0; 64];
+    wide.copy_from_slice(&hasher.finalize());
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
@@ -0,0 +1,4 @@
+//! Claim key grinder for the keypair claim format, `sha256(sha || claim || pool)`.
+
+pub mod grind;
+pub mod keypair;
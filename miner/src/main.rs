@@ -0,0 +1,218 @@
+//! Claim key miner.
+//!
+//! With `--pool` it fetches the open puzzles from a pool server, grinds stepped keys to the
+//! pool's share difficulty and submits them signed by `--miner-keypair`. Claim keys stay
+//! here, solutions are claimed by signing the transaction the pool sends back. With `--works` it grinds full solutions for
+//! the hex encoded puzzles in a file (one per line) against `--pool-key` and writes each
+//! key to `--out`; `--step` uses stepped keys, written as `<pubkey>.expanded` since the
+//! Solana CLI can't load them.
+
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    path::PathBuf,
+    process,
+    str::FromStr,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use hihi_miner::{
+    grind::{Found, Grinder, Mode, Target},
+    keypair::ClaimKey,
+};
+use hihi_pool::server::share_message;
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signer};
+
+const USAGE: &str = "usage: hihi-miner --pool <addr> --miner-keypair <path> [--refresh <seconds>]
+       hihi-miner --works <file> --pool-key <pubkey> --out <dir> [--step]
+       common: [--threads <n>] [--batch <keys>]";
+
+struct Config {
+    threads: usize,
+    batch: usize,
+}
+
+fn get(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1).cloned())
+}
+
+fn need(args: &[String], name: &str) -> Result<String, Box<dyn Error>> {
+    Ok(get(args, name).ok_or_else(|| format!("missing {}\n{}", name, USAGE))?)
+}
+
+//grinds on `threads` threads until `deadline`, found keys go to `sender`.
+fn spawn_grinders(
+    config: &Config,
+    targets: &[Target],
+    pool: Pubkey,
+    mode: Mode,
+    deadline: Option<Instant>,
+    sender: mpsc::Sender<Found>,
+) {
+    for _ in 0..config.threads {
+        let mut grinder = Grinder::new(targets.to_vec(), pool, config.batch, mode);
+        let sender = sender.clone();
+        thread::spawn(move || {
+            while deadline.is_none_or(|d| Instant::now() < d) {
+                for found in grinder.next_batch() {
+                    if sender.send(found).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+struct PoolConnection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl PoolConnection {
+    fn request(&mut self, request: Value) -> Result<Value, Box<dyn Error>> {
+        writeln!(self.writer, "{}", request)?;
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        let response: Value = serde_json::from_str(&line)?;
+        if let Some(error) = response["error"].as_str() {
+            return Err(error.into());
+        }
+        Ok(response)
+    }
+}
+
+fn mine_pool(config: &Config, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let miner = read_keypair_file(need(args, "--miner-keypair")?)?;
+    let refresh = Duration::from_secs(get(args, "--refresh").unwrap_or("10".into()).parse()?);
+    let stream = TcpStream::connect(need(args, "--pool")?)?;
+    let mut pool = PoolConnection {
+        reader: BufReader::new(stream.try_clone()?),
+        writer: stream,
+    };
+    loop {
+        let response = pool.request(json!({ "method": "puzzles" }))?;
+        let pool_key = Pubkey::from_str(response["pool"].as_str().ok_or("missing pool")?)?;
+        let share_difficulty = response["share_difficulty"].as_u64().unwrap_or(1) as usize;
+        let mut works = Vec::new();
+        for puzzle in response["puzzles"].as_array().ok_or("missing puzzles")? {
+            works.push(hex::decode(puzzle["work"].as_str().ok_or("missing work")?)?);
+        }
+        let targets: Vec<Target> = works
+            .iter()
+            .filter_map(|work| Target::from_work(work, Some(share_difficulty)))
+            .collect();
+        if targets.is_empty() {
+            thread::sleep(refresh);
+            continue;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        spawn_grinders(
+            config,
+            &targets,
+            pool_key,
+            Mode::Step,
+            Some(Instant::now() + refresh),
+            sender,
+        );
+        for found in receiver {
+            let claim = found.key.pubkey();
+            let work = &works[found.target];
+            let result = pool.request(json!({
+                "method": "submit",
+                "miner": miner.pubkey().to_string(),
+                "work": hex::encode(work),
+                "claim": claim.to_string(),
+                "signature": miner.sign_message(&share_message(&pool_key, work, &claim)).to_string(),
+            }));
+            let result = match result {
+                Ok(response) if response["result"] == "solution" => {
+                    let message = base64::decode(response["message"].as_str().unwrap_or(""))?;
+                    pool.request(json!({
+                        "method": "claim",
+                        "claim": claim.to_string(),
+                        "signature": found.key.sign(&message).to_string(),
+                    }))
+                }
+                other => other,
+            };
+            match result {
+                Ok(response) => println!("{} {}", claim, response),
+                Err(e) => eprintln!("{} rejected: {}", claim, e),
+            }
+        }
+    }
+}
+
+fn mine_solo(config: &Config, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let pool_key = Pubkey::from_str(&need(args, "--pool-key")?)?;
+    let out = PathBuf::from(need(args, "--out")?);
+    let mode = if args.iter().any(|a| a == "--step") {
+        Mode::Step
+    } else {
+        Mode::Seed
+    };
+    let mut targets = Vec::new();
+    for line in fs::read_to_string(need(args, "--works")?)?.lines() {
+        let work = hex::decode(line.trim())?;
+        targets.push(Target::from_work(&work, None).ok_or("invalid puzzle")?);
+    }
+    fs::create_dir_all(&out)?;
+
+    let (sender, receiver) = mpsc::channel();
+    spawn_grinders(config, &targets, pool_key, mode, None, sender);
+    let mut solved = vec![false; targets.len()];
+    for found in receiver {
+        if solved[found.target] {
+            continue;
+        }
+        solved[found.target] = true;
+        let path = out.join(found.key.file_name());
+        fs::write(&path, found.key.to_json())?;
+        let kind = match found.key {
+            ClaimKey::Seed { .. } => "keypair",
+            ClaimKey::Stepped(_) => "expanded key",
+        };
+        println!(
+            "puzzle {} solved, {} {}",
+            found.target,
+            kind,
+            path.display()
+        );
+        if solved.iter().all(|s| *s) {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = Config {
+        threads: get(&args, "--threads")
+            .and_then(|t| t.parse().ok())
+            .unwrap_or(4),
+        batch: get(&args, "--batch")
+            .and_then(|b| b.parse().ok())
+            .unwrap_or(256),
+    };
+    let result = if get(&args, "--pool").is_some() {
+        mine_pool(&config, &args)
+    } else {
+        mine_solo(&config, &args)
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,135 @@
+use hihi_miner::{
+    grind::{Found, Grinder, Mode, Target},
+    keypair::{ClaimKey, ExpandedKeypair},
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair, Signer};
+use Hihi::processor::check_claim;
+
+fn work(sha: u8, magic: &[u8]) -> Vec<u8> {
+    let mut work = vec![1];
+    work.extend_from_slice(&[sha; 32]);
+    work.push(magic.len() as u8);
+    work.extend_from_slice(magic);
+    work.resize(57, 0);
+    work
+}
+
+fn grind_until(grinder: &mut Grinder, targets: usize) -> Vec<Found> {
+    let mut found: Vec<Found> = Vec::new();
+    while (0..targets).any(|t| found.iter().all(|f| f.target != t)) {
+        found.extend(grinder.next_batch());
+    }
+    found
+}
+
+#[test]
+fn found_keys_solve_every_target() {
+    let works = [work(1, &[7]), work(2, &[200])];
+    let pool = Pubkey::new_from_array([9; 32]);
+    for mode in [Mode::Seed, Mode::Step].iter() {
+        let targets = works
+            .iter()
+            .map(|w| Target::from_work(w, None).unwrap())
+            .collect();
+        let mut grinder = Grinder::new(targets, pool, 64, *mode).with_start(&[3; 32]);
+        for found in grind_until(&mut grinder, works.len()) {
+            let claim = found.key.pubkey();
+            assert!(check_claim(&claim, &pool, &works[found.target]).is_ok());
+        }
+    }
+}
+
+#[test]
+fn share_targets_only_use_the_first_magic_bytes() {
+    let work = work(4, &[1, 2, 3]);
+    let target = Target::from_work(&work, Some(1)).unwrap();
+    assert_eq!(target.magic, vec![1]);
+    assert_eq!(
+        Target::from_work(&work, Some(8)).unwrap().magic,
+        vec![1, 2, 3]
+    );
+    assert_eq!(Target::from_work(&work[..35], None), None);
+}
+
+#[test]
+fn seed_keys_export_to_the_cli_format() {
+    let pool = Pubkey::new_from_array([9; 32]);
+    let targets = vec![Target::from_work(&work(5, &[42]), None).unwrap()];
+    let mut grinder = Grinder::new(targets, pool, 64, Mode::Seed);
+    let found = grind_until(&mut grinder, 1).remove(0);
+
+    assert_eq!(
+        found.key.file_name(),
+        format!("{}.json", found.key.pubkey())
+    );
+    let json = found.key.to_json();
+    let keypair = read_keypair(&mut json.as_bytes()).unwrap();
+    assert_eq!(keypair.pubkey(), found.key.pubkey());
+    let signature = keypair.sign_message(b"claim");
+    assert!(signature.verify(found.key.pubkey().as_ref(), b"claim"));
+}
+
+#[test]
+fn stepped_keys_sign_for_their_pubkey() {
+    let pool = Pubkey::new_from_array([9; 32]);
+    let targets = vec![Target::from_work(&work(6, &[99]), None).unwrap()];
+    let mut grinder = Grinder::new(targets, pool, 64, Mode::Step).with_start(&[8; 32]);
+    let keypair = match grind_until(&mut grinder, 1).remove(0).key {
+        ClaimKey::Stepped(keypair) => keypair,
+        key => panic!("expected a stepped key, got {:?}", key),
+    };
+    let signature = keypair.sign_message(b"claim");
+    assert!(signature.verify(keypair.pubkey().as_ref(), b"claim"));
+    assert!(!signature.verify(keypair.pubkey().as_ref(), b"other"));
+    assert_eq!(
+        ExpandedKeypair::from_bytes(&keypair.to_bytes()),
+        Some(keypair.clone())
+    );
+    assert_eq!(
+        ClaimKey::Stepped(keypair).keypair().map(|k| k.pubkey()),
+        None
+    );
+}
+
+#[test]
+fn stepped_keys_export_apart_from_cli_keypairs() {
+    let pool = Pubkey::new_from_array([9; 32]);
+    let targets = vec![Target::from_work(&work(6, &[99]), None).unwrap()];
+    let mut grinder = Grinder::new(targets, pool, 64, Mode::Step).with_start(&[8; 32]);
+    let key = grind_until(&mut grinder, 1).remove(0).key;
+    assert_eq!(key.file_name(), format!("{}.expanded", key.pubkey()));
+
+    let json = key.to_json();
+    assert!(read_keypair(&mut json.as_bytes()).is_err());
+    let keypair = ExpandedKeypair::from_json(&json).unwrap();
+    assert_eq!(ClaimKey::Stepped(keypair), key);
+
+    //a scalar that doesn't belong to the pubkey is refused.
+    let forged = json.replace(
+        &key.pubkey().to_string(),
+        &ExpandedKeypair::from_seed(&[1; 32]).pubkey().to_string(),
+    );
+    assert_eq!(ExpandedKeypair::from_json(&forged), None);
+}
+
+#[test]
+fn expanded_seed_matches_the_solana_keypair() {
+    let seed = [11; 32];
+    let keypair = solana_sdk::signature::keypair_from_seed(&seed).unwrap();
+    let expanded = ExpandedKeypair::from_seed(&seed);
+    assert_eq!(expanded.pubkey(), keypair.pubkey());
+    assert_eq!(expanded.sign_message(b"m"), keypair.sign_message(b"m"));
+}
+
+#[test]
+fn claim_keys_sign_pool_transactions() {
+    let pool = Pubkey::new_from_array([9; 32]);
+    let targets = vec![Target::from_work(&work(7, &[13]), None).unwrap()];
+    for mode in [Mode::Seed, Mode::Step].iter() {
+        let mut grinder = Grinder::new(targets.clone(), pool, 64, *mode).with_start(&[4; 32]);
+        let key = grind_until(&mut grinder, 1).remove(0).key;
+        let signature = key.sign(b"message");
+        assert!(signature.verify(key.pubkey().as_ref(), b"message"));
+    }
+}
@@ -0,0 +1,74 @@
+//! Share ledger and payout split.
+
+use std::collections::BTreeMap;
+
+use solana_program::pubkey::Pubkey;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShareLedger {
+    shares: BTreeMap<Pubkey, u64>,
+    total: u64,
+    /// Payout rounds closed so far, the shares belong to this one.
+    round: u64,
+}
+
+impl ShareLedger {
+    pub fn credit(&mut self, miner: &Pubkey, shares: u64) {
+        *self.shares.entry(*miner).or_insert(0) += shares;
+        self.total += shares;
+    }
+
+    pub fn shares_of(&self, miner: &Pubkey) -> u64 {
+        self.shares.get(miner).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    pub fn miners(&self) -> impl Iterator<Item = (&Pubkey, &u64)> {
+        self.shares.iter()
+    }
+
+    /// Split `amount` by shares. Every unit is paid out: what flooring leaves over goes
+    /// to the largest remainders, ties to the lower key.
+    pub fn split(&self, amount: u64) -> Vec<(Pubkey, u64)> {
+        if self.total == 0 {
+            return Vec::new();
+        }
+        let mut paid = 0;
+        let mut out = Vec::with_capacity(self.shares.len());
+        let mut remainders = Vec::with_capacity(self.shares.len());
+        for (i, (miner, shares)) in self.shares.iter().enumerate() {
+            let exact = amount as u128 * *shares as u128;
+            let part = (exact / self.total as u128) as u64;
+            paid += part;
+            out.push((*miner, part));
+            remainders.push((exact % self.total as u128, i));
+        }
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (_, i) in remainders.into_iter().take((amount - paid) as usize) {
+            out[i].1 += 1;
+        }
+        out
+    }
+
+    /// Start a new round, after a payout.
+    pub fn reset(&mut self) {
+        self.shares.clear();
+        self.total = 0;
+    }
+
+    /// Split `amount` by the shares of this round and start the next, so no share is paid
+    /// twice.
+    pub fn close_round(&mut self, amount: u64) -> Vec<(Pubkey, u64)> {
+        let split = self.split(amount);
+        self.reset();
+        self.round += 1;
+        split
+    }
+}
@@ -0,0 +1,12 @@
+//! Mining pool for Hihi puzzles.
+//!
+//! Miners grind claim keys against the open puzzles and submit every key whose claim hash
+//! matches a shorter, pool wide prefix of the puzzle magic. Those partial solutions are
+//! credited as shares in a ledger, full solutions are claimed on chain with the pool key
+//! and the mined tokens are split by shares.
+
+pub mod ledger;
+pub mod puzzles;
+pub mod rpc;
+pub mod server;
+pub mod share;
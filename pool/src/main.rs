@@ -0,0 +1,326 @@
+//! Pool server: miners connect over TCP and exchange JSON lines. The connection is neither
+//! encrypted nor authenticated, so the server only listens on loopback addresses; remote
+//! miners reach it through an authenticated tunnel such as `ssh -L`. Signatures are base58.
+//!
+//! `{"method":"puzzles"}` lists the open puzzles, the share difficulty and the payout round.
+//! `{"method":"submit","miner":"<pubkey>","work":"<hex>","claim":"<pubkey>","signature":".."}`
+//! grades a claim key, signed by the miner over `share_message`. For a full solution the
+//! reply carries the base64 claim transaction message, already signed by the pool.
+//! `{"method":"claim","claim":"<pubkey>","signature":".."}` adds the claim key's signature of
+//! that message and sends the transaction.
+//! `{"method":"payouts","round":<n>,"amount":<tokens>,"signature":".."}` splits an amount by
+//! the shares of the round, signed by the pool key over `payout_message`, and opens the next.
+//! `hihi-pool --pool-keypair <path> --payout <tokens>` sends it.
+
+use std::{
+    env,
+    error::Error,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    process,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use hihi_pool::{
+    puzzles::{claim_instruction, open_puzzles, page_ids, OpenPuzzle},
+    rpc::RpcClient,
+    server::{payout_message, Outcome, Pool},
+};
+use serde_json::{json, Value};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{read_keypair_file, Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use Hihi::state::{HihiState, PageState};
+
+const USAGE: &str = "usage: hihi-pool --rpc <url> --program <id> --instance <id> \
+--pool-keypair <path> --to-token <token account> [--share-difficulty <bytes>] \
+[--listen <addr>] [--refresh <seconds>]
+       hihi-pool --pool-keypair <path> --payout <tokens> [--listen <addr>]";
+
+struct Config {
+    rpc: String,
+    program_id: Pubkey,
+    instance_id: Pubkey,
+    pool_keypair: Keypair,
+    to_token: Pubkey,
+    share_difficulty: u8,
+    listen: SocketAddr,
+    refresh: u64,
+}
+
+fn get(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1).cloned())
+}
+
+fn need(args: &[String], name: &str) -> Result<String, Box<dyn Error>> {
+    Ok(get(args, name).ok_or_else(|| format!("missing {}\n{}", name, USAGE))?)
+}
+
+//the protocol has no transport security, keep it off the network.
+fn listen_addr(args: &[String]) -> Result<SocketAddr, Box<dyn Error>> {
+    let listen: SocketAddr = get(args, "--listen")
+        .unwrap_or("127.0.0.1:7878".into())
+        .parse()?;
+    if !listen.ip().is_loopback() {
+        return Err("--listen must be a loopback address, tunnel remote miners to it".into());
+    }
+    Ok(listen)
+}
+
+fn parse_args(args: &[String]) -> Result<Config, Box<dyn Error>> {
+    Ok(Config {
+        rpc: need(args, "--rpc")?,
+        program_id: Pubkey::from_str(&need(args, "--program")?)?,
+        instance_id: Pubkey::from_str(&need(args, "--instance")?)?,
+        pool_keypair: read_keypair_file(need(args, "--pool-keypair")?)?,
+        to_token: Pubkey::from_str(&need(args, "--to-token")?)?,
+        share_difficulty: get(args, "--share-difficulty")
+            .unwrap_or("1".into())
+            .parse()?,
+        listen: listen_addr(args)?,
+        refresh: get(args, "--refresh").unwrap_or("10".into()).parse()?,
+    })
+}
+
+struct Shared {
+    config: Config,
+    rpc: RpcClient,
+    pool: Mutex<Pool>,
+    instance: Mutex<Option<HihiState>>,
+}
+
+fn fetch_puzzles(shared: &Shared) -> Result<(HihiState, Vec<OpenPuzzle>), Box<dyn Error>> {
+    let config = &shared.config;
+    let data = shared
+        .rpc
+        .get_account_data(&config.instance_id)?
+        .ok_or("instance account not found")?;
+    let instance = HihiState::unpack(&data)?;
+    let mut pages = Vec::new();
+    for page_id in page_ids(&config.program_id, &config.instance_id, &instance) {
+        if let Some(data) = shared.rpc.get_account_data(&page_id)? {
+            pages.push((page_id, PageState::unpack(&data)?));
+        }
+    }
+    let puzzles = open_puzzles(&instance, &pages);
+    Ok((instance, puzzles))
+}
+
+fn refresh(shared: &Shared) {
+    match fetch_puzzles(shared) {
+        Ok((instance, puzzles)) => {
+            shared.pool.lock().unwrap().set_puzzles(puzzles);
+            *shared.instance.lock().unwrap() = Some(instance);
+        }
+        Err(e) => eprintln!("refresh failed: {}", e),
+    }
+}
+
+//the claim transaction signed by the pool, the claim key signs it on the miner.
+fn solution_transaction(
+    shared: &Shared,
+    puzzle: &OpenPuzzle,
+    claim_key: &Pubkey,
+) -> Result<Transaction, Box<dyn Error>> {
+    let config = &shared.config;
+    let instance = shared
+        .instance
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("instance not loaded")?;
+    let ix = claim_instruction(
+        &config.program_id,
+        &config.instance_id,
+        &instance,
+        puzzle,
+        claim_key,
+        &config.pool_keypair.pubkey(),
+        &config.to_token,
+    )?;
+    let blockhash = shared.rpc.get_recent_blockhash()?;
+    let mut transaction = Transaction::new_with_payer(&[ix], Some(&config.pool_keypair.pubkey()));
+    transaction.partial_sign(&[&config.pool_keypair], blockhash);
+    Ok(transaction)
+}
+
+fn handle(shared: &Shared, request: &Value) -> Result<Value, Box<dyn Error>> {
+    match request["method"].as_str() {
+        Some("puzzles") => {
+            let pool = shared.pool.lock().unwrap();
+            let puzzles: Vec<Value> = pool
+                .puzzles()
+                .iter()
+                .map(|p| json!({ "work": hex::encode(&p.work), "difficulty": p.work[33] }))
+                .collect();
+            Ok(json!({
+                "pool": pool.pool_key.to_string(),
+                "share_difficulty": pool.share_difficulty,
+                "round": pool.ledger.round(),
+                "puzzles": puzzles,
+            }))
+        }
+        Some("submit") => {
+            let miner = Pubkey::from_str(request["miner"].as_str().ok_or("missing miner")?)?;
+            let work = hex::decode(request["work"].as_str().ok_or("missing work")?)?;
+            let claim_key = Pubkey::from_str(request["claim"].as_str().ok_or("missing claim")?)?;
+            let signature =
+                Signature::from_str(request["signature"].as_str().ok_or("missing signature")?)?;
+
+            let outcome = shared
+                .pool
+                .lock()
+                .unwrap()
+                .submit(&miner, &work, &claim_key, &signature)?;
+            match outcome {
+                Outcome::Share => Ok(json!({ "result": "share" })),
+                Outcome::Solution(puzzle) => {
+                    let transaction = solution_transaction(shared, &puzzle, &claim_key)?;
+                    let message = base64::encode(transaction.message_data());
+                    shared
+                        .pool
+                        .lock()
+                        .unwrap()
+                        .hold_solution(&claim_key, transaction);
+                    Ok(json!({ "result": "solution", "message": message }))
+                }
+            }
+        }
+        Some("claim") => {
+            let claim_key = Pubkey::from_str(request["claim"].as_str().ok_or("missing claim")?)?;
+            let signature =
+                Signature::from_str(request["signature"].as_str().ok_or("missing signature")?)?;
+            let transaction = shared
+                .pool
+                .lock()
+                .unwrap()
+                .sign_solution(&claim_key, &signature)?;
+            let signature = shared.rpc.send_transaction(&transaction)?;
+            Ok(json!({ "result": "claimed", "signature": signature }))
+        }
+        Some("payouts") => {
+            let round = request["round"].as_u64().ok_or("missing round")?;
+            let amount = request["amount"].as_u64().ok_or("missing amount")?;
+            let signature =
+                Signature::from_str(request["signature"].as_str().ok_or("missing signature")?)?;
+            let mut pool = shared.pool.lock().unwrap();
+            let total_shares = pool.ledger.total();
+            let payouts: Vec<Value> = pool
+                .payout(round, amount, &signature)?
+                .iter()
+                .map(|(miner, amount)| json!({ "miner": miner.to_string(), "amount": amount }))
+                .collect();
+            Ok(json!({ "round": round, "total_shares": total_shares, "payouts": payouts }))
+        }
+        _ => Err("unknown method".into()),
+    }
+}
+
+fn serve(shared: Arc<Shared>, stream: TcpStream) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let response = match serde_json::from_str::<Value>(&line?) {
+            Ok(request) => match handle(&shared, &request) {
+                Ok(response) => response,
+                Err(e) => json!({ "error": e.to_string() }),
+            },
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+        writeln!(writer, "{}", response)?;
+    }
+    Ok(())
+}
+
+fn request(
+    writer: &mut TcpStream,
+    reader: &mut BufReader<TcpStream>,
+    request: Value,
+) -> Result<Value, Box<dyn Error>> {
+    writeln!(writer, "{}", request)?;
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let response: Value = serde_json::from_str(&line)?;
+    if let Some(error) = response["error"].as_str() {
+        return Err(error.into());
+    }
+    Ok(response)
+}
+
+//pays out the open round of a running pool.
+fn payout(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let pool_keypair = read_keypair_file(need(args, "--pool-keypair")?)?;
+    let amount: u64 = need(args, "--payout")?.parse()?;
+    let mut writer = TcpStream::connect(listen_addr(args)?)?;
+    let mut reader = BufReader::new(writer.try_clone()?);
+    let puzzles = request(&mut writer, &mut reader, json!({ "method": "puzzles" }))?;
+    let round = puzzles["round"].as_u64().ok_or("missing round")?;
+    let message = payout_message(&pool_keypair.pubkey(), round, amount);
+    let response = request(
+        &mut writer,
+        &mut reader,
+        json!({
+            "method": "payouts",
+            "round": round,
+            "amount": amount,
+            "signature": pool_keypair.sign_message(&message).to_string(),
+        }),
+    )?;
+    println!("{}", response);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if get(&args, "--payout").is_some() {
+        if let Err(e) = payout(&args) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    let config = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let listener = TcpListener::bind(config.listen).unwrap_or_else(|e| {
+        eprintln!("can't listen on {}: {}", config.listen, e);
+        process::exit(1);
+    });
+    let shared = Arc::new(Shared {
+        rpc: RpcClient::new(&config.rpc),
+        pool: Mutex::new(Pool::new(
+            config.pool_keypair.pubkey(),
+            config.share_difficulty,
+        )),
+        instance: Mutex::new(None),
+        config,
+    });
+
+    refresh(&shared);
+    let refresher = shared.clone();
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(refresher.config.refresh));
+        refresh(&refresher);
+    });
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve(shared, stream) {
+                        eprintln!("connection closed: {}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("accept failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Open puzzles of an instance and the instructions that claim them.
+
+use std::convert::TryInto;
+
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use Hihi::{
+    instruction::{claim, claim_puzzle, WORK_BYTES},
+    processor::{open_batch_puzzles, Processor},
+    state::{HihiState, PageState},
+};
+
+/// Where an open puzzle is stored, which decides how it is claimed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Heap,
+    Page(Pubkey),
+    Batch { seed: [u8; 32], index: u8 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpenPuzzle {
+    /// The puzzle in its stored form: tokens, sha, magic length and magic.
+    pub work: Vec<u8>,
+    pub source: Source,
+}
+
+/// Every open puzzle of the instance heap, its puzzle batches and the given pages.
+pub fn open_puzzles(instance: &HihiState, pages: &[(Pubkey, PageState)]) -> Vec<OpenPuzzle> {
+    let mut out = Vec::new();
+    for work in instance.work.iter() {
+        out.push(OpenPuzzle {
+            work: work.clone(),
+            source: Source::Heap,
+        });
+    }
+    for batch in instance.batches.iter() {
+        for (index, work) in open_batch_puzzles(batch) {
+            out.push(OpenPuzzle {
+                work,
+                source: Source::Batch {
+                    seed: batch.seed,
+                    index,
+                },
+            });
+        }
+    }
+    for (page_id, page) in pages {
+        for work in page.work.iter() {
+            out.push(OpenPuzzle {
+                work: work.clone(),
+                source: Source::Page(*page_id),
+            });
+        }
+    }
+    out
+}
+
+/// Page accounts of an instance, `Processor::page_id` for every created page.
+pub fn page_ids(program_id: &Pubkey, instance_id: &Pubkey, instance: &HihiState) -> Vec<Pubkey> {
+    (0..instance.page_count)
+        .map(|index| Processor::page_id(program_id, instance_id, index).0)
+        .collect()
+}
+
+/// The instruction claiming `puzzle` with `claim_key` for `pool_key`, minting to `to_token`.
+pub fn claim_instruction(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    instance: &HihiState,
+    puzzle: &OpenPuzzle,
+    claim_key: &Pubkey,
+    pool_key: &Pubkey,
+    to_token: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let authority_id = Processor::authority_id(program_id, instance_id, instance.nonce)?;
+    match &puzzle.source {
+        Source::Batch { seed, index } => claim_puzzle(
+            program_id,
+            instance_id,
+            &spl_token::id(),
+            &instance.token_mint_id,
+            &authority_id,
+            claim_key,
+            pool_key,
+            to_token,
+            None,
+            *seed,
+            *index,
+        ),
+        source => {
+            let work: [u8; WORK_BYTES] = puzzle
+                .work
+                .as_slice()
+                .try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+            let page_id = match source {
+                Source::Page(page_id) => Some(page_id),
+                _ => None,
+            };
+            claim(
+                program_id,
+                instance_id,
+                &spl_token::id(),
+                &instance.token_mint_id,
+                &authority_id,
+                claim_key,
+                pool_key,
+                to_token,
+                None,
+                page_id,
+                work,
+            )
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! The few JSON-RPC calls the pool needs.
+
+use serde_json::{json, Value};
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use solana_sdk::transaction::Transaction;
+use std::{error::Error, str::FromStr};
+
+pub type RpcResult<T> = Result<T, Box<dyn Error>>;
+
+pub struct RpcClient {
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+        }
+    }
+
+    fn call(&self, method: &str, params: Value) -> RpcResult<Value> {
+        let response: Value = ureq::post(&self.url)
+            .send_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))?
+            .into_json()?;
+        if let Some(error) = response.get("error") {
+            return Err(format!("{} failed: {}", method, error).into());
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Data of an account, `None` if it doesn't exist.
+    pub fn get_account_data(&self, pubkey: &Pubkey) -> RpcResult<Option<Vec<u8>>> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([pubkey.to_string(), { "encoding": "base64" }]),
+        )?;
+        match result["value"]["data"][0].as_str() {
+            Some(data) => Ok(Some(base64::decode(data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_recent_blockhash(&self) -> RpcResult<Hash> {
+        let result = self.call("getRecentBlockhash", json!([]))?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or("getRecentBlockhash returned no blockhash")?;
+        Ok(Hash::from_str(blockhash)?)
+    }
+
+    /// Send a signed transaction, returns its signature.
+    pub fn send_transaction(&self, transaction: &Transaction) -> RpcResult<String> {
+        let data = base64::encode(bincode::serialize(transaction)?);
+        let result = self.call("sendTransaction", json!([data, { "encoding": "base64" }]))?;
+        Ok(result.as_str().unwrap_or_default().to_owned())
+    }
+}
@@ -0,0 +1,195 @@
+//! Pool state: the open puzzles, submitted shares, solutions waiting for the claim key's
+//! signature and the share ledger.
+//!
+//! Shares are signed by the miner wallet they are credited to, payouts by the pool key.
+//! Claim keys never leave the miner: a solution's claim transaction is signed by the pool
+//! and handed back for the claim key to sign.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{signature::Signature, transaction::Transaction};
+
+use crate::{
+    ledger::ShareLedger,
+    puzzles::OpenPuzzle,
+    share::{grade, Grade},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PoolError {
+    /// The work is not one of the open puzzles.
+    UnknownPuzzle,
+    /// This claim key was already submitted for the puzzle.
+    DuplicateShare,
+    /// The claim hash doesn't reach the share difficulty.
+    BelowTarget,
+    /// The signature doesn't match the miner, claim or pool key.
+    InvalidSignature,
+    /// No solution of this claim key is waiting for its signature.
+    UnknownSolution,
+    /// The payout is for another round than the open one.
+    WrongRound,
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PoolError::UnknownPuzzle => f.write_str("Unknown or closed puzzle"),
+            PoolError::DuplicateShare => f.write_str("Share was already submitted"),
+            PoolError::BelowTarget => f.write_str("Claim hash is below the share target"),
+            PoolError::InvalidSignature => f.write_str("Invalid signature"),
+            PoolError::UnknownSolution => f.write_str("No solution waits for this claim key"),
+            PoolError::WrongRound => f.write_str("Payout is for another round"),
+        }
+    }
+}
+
+impl std::error::Error for PoolError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// A share was credited.
+    Share,
+    /// A share was credited and the puzzle is solved, claim it on chain.
+    Solution(OpenPuzzle),
+}
+
+/// What a miner signs with its wallet to have the share of `claim_key` credited to it.
+pub fn share_message(pool_key: &Pubkey, work: &[u8], claim_key: &Pubkey) -> Vec<u8> {
+    let mut message = b"hihi-pool share".to_vec();
+    message.extend_from_slice(pool_key.as_ref());
+    message.extend_from_slice(claim_key.as_ref());
+    message.extend_from_slice(work);
+    message
+}
+
+/// What the pool key signs to pay `amount` out to the shares of `round`.
+pub fn payout_message(pool_key: &Pubkey, round: u64, amount: u64) -> Vec<u8> {
+    let mut message = b"hihi-pool payout".to_vec();
+    message.extend_from_slice(pool_key.as_ref());
+    message.extend_from_slice(&round.to_le_bytes());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message
+}
+
+pub struct Pool {
+    pub pool_key: Pubkey,
+    /// Magic bytes a share has to match.
+    pub share_difficulty: u8,
+    pub ledger: ShareLedger,
+    puzzles: Vec<OpenPuzzle>,
+    seen: HashSet<(Vec<u8>, Pubkey)>,
+    /// Claim transactions signed by the pool, by claim key.
+    solutions: HashMap<Pubkey, Transaction>,
+}
+
+impl Pool {
+    pub fn new(pool_key: Pubkey, share_difficulty: u8) -> Self {
+        Self {
+            pool_key,
+            share_difficulty,
+            ledger: ShareLedger::default(),
+            puzzles: Vec::new(),
+            seen: HashSet::new(),
+            solutions: HashMap::new(),
+        }
+    }
+
+    pub fn puzzles(&self) -> &[OpenPuzzle] {
+        &self.puzzles
+    }
+
+    /// Replace the open puzzles, shares of closed puzzles are forgotten.
+    pub fn set_puzzles(&mut self, puzzles: Vec<OpenPuzzle>) {
+        self.seen
+            .retain(|(work, _)| puzzles.iter().any(|p| &p.work == work));
+        self.puzzles = puzzles;
+    }
+
+    /// Grade a claim key submitted by `miner` for the puzzle `work` and credit the share,
+    /// `signature` is the miner's of `share_message`.
+    pub fn submit(
+        &mut self,
+        miner: &Pubkey,
+        work: &[u8],
+        claim_key: &Pubkey,
+        signature: &Signature,
+    ) -> Result<Outcome, PoolError> {
+        let message = share_message(&self.pool_key, work, claim_key);
+        if !signature.verify(miner.as_ref(), &message) {
+            return Err(PoolError::InvalidSignature);
+        }
+        let position = self
+            .puzzles
+            .iter()
+            .position(|p| p.work == work)
+            .ok_or(PoolError::UnknownPuzzle)?;
+        if self.seen.contains(&(work.to_vec(), *claim_key)) {
+            return Err(PoolError::DuplicateShare);
+        }
+
+        let grade = grade(work, claim_key, &self.pool_key, self.share_difficulty);
+        if grade == Grade::Rejected {
+            return Err(PoolError::BelowTarget);
+        }
+
+        self.seen.insert((work.to_vec(), *claim_key));
+        self.ledger.credit(miner, 1);
+
+        if grade == Grade::Solution {
+            let puzzle = self.puzzles.swap_remove(position);
+            return Ok(Outcome::Solution(puzzle));
+        }
+        Ok(Outcome::Share)
+    }
+
+    /// Keep the claim transaction of a solution until the claim key signs it.
+    pub fn hold_solution(&mut self, claim_key: &Pubkey, transaction: Transaction) {
+        self.solutions.insert(*claim_key, transaction);
+    }
+
+    /// Add the claim key's signature to its held transaction, which is then fully signed.
+    pub fn sign_solution(
+        &mut self,
+        claim_key: &Pubkey,
+        signature: &Signature,
+    ) -> Result<Transaction, PoolError> {
+        let transaction = self
+            .solutions
+            .get(claim_key)
+            .ok_or(PoolError::UnknownSolution)?;
+        let signers = transaction.message.header.num_required_signatures as usize;
+        let position = transaction.message.account_keys[..signers]
+            .iter()
+            .position(|key| key == claim_key)
+            .ok_or(PoolError::UnknownSolution)?;
+        if !signature.verify(claim_key.as_ref(), &transaction.message_data()) {
+            return Err(PoolError::InvalidSignature);
+        }
+        let mut transaction = self.solutions.remove(claim_key).unwrap();
+        transaction.signatures[position] = *signature;
+        Ok(transaction)
+    }
+
+    /// Split `amount` by the shares of `round` and open the next round, `signature` is the
+    /// pool key's of `payout_message`.
+    pub fn payout(
+        &mut self,
+        round: u64,
+        amount: u64,
+        signature: &Signature,
+    ) -> Result<Vec<(Pubkey, u64)>, PoolError> {
+        let message = payout_message(&self.pool_key, round, amount);
+        if !signature.verify(self.pool_key.as_ref(), &message) {
+            return Err(PoolError::InvalidSignature);
+        }
+        if round != self.ledger.round() {
+            return Err(PoolError::WrongRound);
+        }
+        Ok(self.ledger.close_round(amount))
+    }
+}
@@ -0,0 +1,41 @@
+//! Grading submitted claim keys with the program's claim hash.
+
+use solana_program::pubkey::Pubkey;
+use Hihi::processor::{check_claim, claim_hash};
+
+/// Offset of the magic bytes in a puzzle: tokens, sha, magic length.
+const MAGIC_OFFSET: usize = 1 + 32 + 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Grade {
+    /// Matches less of the magic than the share difficulty.
+    Rejected,
+    /// Matches the share difficulty but not the whole magic.
+    Share,
+    /// Solves the puzzle, `check_claim` accepts it.
+    Solution,
+}
+
+/// Leading magic bytes of `work` matched by the claim hash of `claim` and `pool`.
+pub fn matched_magic(work: &[u8], claim: &Pubkey, pool: &Pubkey) -> usize {
+    if work.len() <= MAGIC_OFFSET {
+        return 0;
+    }
+    let hash = claim_hash(&work[1..MAGIC_OFFSET - 1], claim, pool);
+    hash.iter()
+        .zip(&work[MAGIC_OFFSET..])
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Grade a claim key against a puzzle, `share_difficulty` is the number of magic bytes a
+/// share has to match.
+pub fn grade(work: &[u8], claim: &Pubkey, pool: &Pubkey, share_difficulty: u8) -> Grade {
+    if check_claim(claim, pool, work).is_ok() {
+        return Grade::Solution;
+    }
+    if matched_magic(work, claim, pool) >= share_difficulty as usize {
+        return Grade::Share;
+    }
+    Grade::Rejected
+}
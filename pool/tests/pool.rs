@@ -0,0 +1,231 @@
+use hihi_pool::{
+    ledger::ShareLedger,
+    puzzles::{OpenPuzzle, Source},
+    server::{payout_message, share_message, Outcome, Pool, PoolError},
+    share::{grade, matched_magic, Grade},
+};
+use solana_program::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_sdk::{
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+fn puzzle(mag_len: u8) -> Vec<u8> {
+    let mut work = vec![100];
+    work.extend_from_slice(&[7; 32]);
+    work.push(mag_len);
+    work.extend_from_slice(&[33, 232]);
+    work.extend_from_slice(&[0; 21]);
+    work
+}
+
+/// Grind claim keys until one matches exactly `bytes` magic bytes.
+fn find_key(work: &[u8], pool: &Pubkey, bytes: usize) -> Pubkey {
+    loop {
+        let key = Pubkey::new_unique();
+        if matched_magic(work, &key, pool) == bytes {
+            return key;
+        }
+    }
+}
+
+#[test]
+fn shares_and_solutions_are_graded() {
+    let pool_key = Pubkey::new_unique();
+    let work = puzzle(2);
+    let share = find_key(&work, &pool_key, 1);
+    let solution = find_key(&work, &pool_key, 2);
+    assert_eq!(grade(&work, &share, &pool_key, 1), Grade::Share);
+    assert_eq!(grade(&work, &share, &pool_key, 2), Grade::Rejected);
+    assert_eq!(grade(&work, &solution, &pool_key, 1), Grade::Solution);
+    assert_eq!(grade(&work, &solution, &pool_key, 3), Grade::Solution);
+}
+
+fn pool_with(pool_key: &Pubkey, work: &[u8]) -> Pool {
+    let mut pool = Pool::new(*pool_key, 1);
+    pool.set_puzzles(vec![OpenPuzzle {
+        work: work.to_vec(),
+        source: Source::Heap,
+    }]);
+    pool
+}
+
+/// Submit `key` for `work`, signed by `miner`.
+fn submit(
+    pool: &mut Pool,
+    miner: &Keypair,
+    work: &[u8],
+    key: &Pubkey,
+) -> Result<Outcome, PoolError> {
+    let signature = miner.sign_message(&share_message(&pool.pool_key, work, key));
+    pool.submit(&miner.pubkey(), work, key, &signature)
+}
+
+#[test]
+fn pool_credits_shares_once() {
+    let pool_key = Pubkey::new_unique();
+    let miner = Keypair::new();
+    let work = puzzle(2);
+    let mut pool = pool_with(&pool_key, &work);
+
+    let key = find_key(&work, &pool_key, 1);
+    assert_eq!(submit(&mut pool, &miner, &work, &key), Ok(Outcome::Share));
+    assert_eq!(
+        submit(&mut pool, &miner, &work, &key),
+        Err(PoolError::DuplicateShare)
+    );
+    assert_eq!(
+        submit(&mut pool, &miner, &puzzle(4), &key),
+        Err(PoolError::UnknownPuzzle)
+    );
+    assert_eq!(pool.ledger.shares_of(&miner.pubkey()), 1);
+
+    let solution = find_key(&work, &pool_key, 2);
+    match submit(&mut pool, &miner, &work, &solution) {
+        Ok(Outcome::Solution(p)) => assert_eq!(p.work, work),
+        other => panic!("expected a solution, got {:?}", other),
+    }
+    assert!(pool.puzzles().is_empty());
+    assert_eq!(pool.ledger.shares_of(&miner.pubkey()), 2);
+}
+
+#[test]
+fn shares_are_credited_to_the_signing_miner() {
+    let pool_key = Pubkey::new_unique();
+    let (miner, thief) = (Keypair::new(), Keypair::new());
+    let work = puzzle(2);
+    let mut pool = pool_with(&pool_key, &work);
+    let key = find_key(&work, &pool_key, 1);
+
+    //a share signed by one wallet can't be credited to another.
+    let signature = thief.sign_message(&share_message(&pool_key, &work, &key));
+    assert_eq!(
+        pool.submit(&miner.pubkey(), &work, &key, &signature),
+        Err(PoolError::InvalidSignature)
+    );
+    //nor the signature moved to another claim key.
+    let other = find_key(&work, &pool_key, 1);
+    let signature = miner.sign_message(&share_message(&pool_key, &work, &other));
+    assert_eq!(
+        pool.submit(&miner.pubkey(), &work, &key, &signature),
+        Err(PoolError::InvalidSignature)
+    );
+    assert_eq!(pool.ledger.total(), 0);
+
+    assert_eq!(submit(&mut pool, &miner, &work, &key), Ok(Outcome::Share));
+    assert_eq!(pool.ledger.shares_of(&miner.pubkey()), 1);
+}
+
+#[test]
+fn solutions_wait_for_the_claim_key_signature() {
+    let pool_keypair = Keypair::new();
+    let claim = Keypair::new();
+    let mut pool = Pool::new(pool_keypair.pubkey(), 1);
+    let ix = Instruction {
+        program_id: Pubkey::new_unique(),
+        accounts: vec![
+            AccountMeta::new_readonly(claim.pubkey(), true),
+            AccountMeta::new(pool_keypair.pubkey(), true),
+        ],
+        data: Vec::new(),
+    };
+    let mut transaction = Transaction::new_with_payer(&[ix], Some(&pool_keypair.pubkey()));
+    transaction.partial_sign(&[&pool_keypair], Hash::new_unique());
+    assert!(!transaction.is_signed());
+    pool.hold_solution(&claim.pubkey(), transaction.clone());
+
+    let message = transaction.message_data();
+    assert_eq!(
+        pool.sign_solution(&claim.pubkey(), &Keypair::new().sign_message(&message)),
+        Err(PoolError::InvalidSignature)
+    );
+    let signed = pool
+        .sign_solution(&claim.pubkey(), &claim.sign_message(&message))
+        .unwrap();
+    assert!(signed.verify().is_ok());
+    assert_eq!(
+        pool.sign_solution(&claim.pubkey(), &claim.sign_message(&message)),
+        Err(PoolError::UnknownSolution)
+    );
+}
+
+#[test]
+fn payouts_pay_each_share_once() {
+    let pool_keypair = Keypair::new();
+    let pool_key = pool_keypair.pubkey();
+    let miner = Keypair::new();
+    let work = puzzle(3);
+    let mut pool = pool_with(&pool_key, &work);
+    submit(&mut pool, &miner, &work, &find_key(&work, &pool_key, 1)).unwrap();
+    submit(&mut pool, &miner, &work, &find_key(&work, &pool_key, 1)).unwrap();
+
+    let sign = |round, amount| pool_keypair.sign_message(&payout_message(&pool_key, round, amount));
+    assert_eq!(
+        pool.payout(
+            0,
+            100,
+            &miner.sign_message(&payout_message(&pool_key, 0, 100))
+        ),
+        Err(PoolError::InvalidSignature)
+    );
+    assert_eq!(
+        pool.payout(0, 100, &sign(0, 99)),
+        Err(PoolError::InvalidSignature)
+    );
+    assert_eq!(
+        pool.payout(0, 100, &sign(0, 100)),
+        Ok(vec![(miner.pubkey(), 100)])
+    );
+    assert_eq!(pool.ledger.round(), 1);
+    assert_eq!(pool.ledger.total(), 0);
+
+    //a replayed payout finds the round closed.
+    assert_eq!(
+        pool.payout(0, 100, &sign(0, 100)),
+        Err(PoolError::WrongRound)
+    );
+    let other = Keypair::new();
+    submit(&mut pool, &other, &work, &find_key(&work, &pool_key, 1)).unwrap();
+    assert_eq!(
+        pool.payout(1, 50, &sign(1, 50)),
+        Ok(vec![(other.pubkey(), 50)])
+    );
+    assert_eq!(pool.payout(2, 50, &sign(2, 50)), Ok(Vec::new()));
+    assert_eq!(
+        pool.payout(2, 0, &Signature::default()),
+        Err(PoolError::InvalidSignature)
+    );
+}
+
+#[test]
+fn split_pays_out_everything_by_shares() {
+    let miners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut ledger = ShareLedger::default();
+    ledger.credit(&miners[0], 1);
+    ledger.credit(&miners[1], 1);
+    ledger.credit(&miners[2], 2);
+
+    let split = ledger.split(1001);
+    assert_eq!(split.iter().map(|(_, a)| a).sum::<u64>(), 1001);
+    for (miner, amount) in split {
+        let expected = 1001 * ledger.shares_of(&miner) / ledger.total();
+        assert!(amount == expected || amount == expected + 1);
+    }
+    assert!(ShareLedger::default().split(10).is_empty());
+}
+
+#[test]
+fn closing_a_round_resets_the_ledger() {
+    let miner = Pubkey::new_unique();
+    let mut ledger = ShareLedger::default();
+    ledger.credit(&miner, 3);
+    assert_eq!(ledger.close_round(10), vec![(miner, 10)]);
+    assert_eq!((ledger.round(), ledger.total()), (1, 0));
+    assert_eq!(ledger.shares_of(&miner), 0);
+    assert!(ledger.close_round(10).is_empty());
+    assert_eq!(ledger.round(), 2);
+}
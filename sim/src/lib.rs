@@ -0,0 +1,294 @@
+//! Offline simulation of an instance. Breaches and limit breaks go through the program's
+//! own pricing, reward and puzzle heap functions, only the accounts and transfers are
+//! left out.
+
+use solana_program::{
+    clock::Clock,
+    hash::hash,
+    native_token::{lamports_to_sol, sol_to_lamports},
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use Hihi::{
+    processor::{
+        breach_base_tokens, breaches_cost, buy_breaches, calculate_tokens, create_limit_break,
+        current_price, record_limit_break, roll_epoch, update_window, Processor, BREACH_WINDOW,
+        LB_DIFF, LB_TOKEN_COUNT, MAX_BREACHES, MAX_DIFF, START_DIFF,
+    },
+    state::{HihiState, STATE_SPACE},
+};
+
+/// Milliseconds per slot, for hashrates and timestamps.
+pub const SLOT_MS: u64 = 400;
+
+/// A stretch of the scenario with constant activity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phase {
+    pub slots: u64,
+    /// Breach transactions per breach window.
+    pub breach_rate: f64,
+    /// Breaches each transaction pays for, at most `MAX_BREACHES`.
+    pub breaches_per_tx: u64,
+    /// Hashes per second of all miners together.
+    pub hashrate: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    pub epoch_slots: u64,
+    /// Part of the hashrate on the limit break while puzzles are open.
+    pub limit_break_share: f64,
+    /// Emit a row every this many breach windows.
+    pub sample_windows: u64,
+    pub phases: Vec<Phase>,
+}
+
+/// One line of the CSV time series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub slot: u64,
+    pub epoch: u64,
+    pub breach_price: u64,
+    pub breach_count: i32,
+    pub limit_count: u32,
+    /// Tokens minted to breachers, puzzle and limit break solvers, in base units.
+    pub supply_minted: u64,
+    /// Lamports held back for miners, `HihiState::lamports`.
+    pub reserve: u64,
+    /// Lamports released from the reserve so far.
+    pub released: u64,
+    pub difficulty: u8,
+    pub heap: usize,
+    pub batch_puzzles: u32,
+    pub work_cached: u64,
+}
+
+impl Row {
+    pub const HEADER: &'static str = "slot,epoch,breach_price_sol,breach_count,limit_count,\
+supply_minted,reserve_sol,released_sol,difficulty,heap,batch_puzzles,work_cached";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.slot,
+            self.epoch,
+            lamports_to_sol(self.breach_price),
+            self.breach_count,
+            self.limit_count,
+            lamports_to_sol(self.supply_minted),
+            lamports_to_sol(self.reserve),
+            lamports_to_sol(self.released),
+            self.difficulty,
+            self.heap,
+            self.batch_puzzles,
+            self.work_cached
+        )
+    }
+}
+
+pub struct Simulation {
+    pub instance: HihiState,
+    instance_id: Pubkey,
+    epoch_slots: u64,
+    limit_break_share: f64,
+    clock: Clock,
+    supply_minted: u64,
+    released: u64,
+    breach_carry: f64,
+    puzzle_hashes: f64,
+    limit_break_hashes: f64,
+}
+
+impl Simulation {
+    /// A freshly initialized instance at slot 0.
+    pub fn new(epoch_slots: u64, limit_break_share: f64) -> Self {
+        let mut instance = HihiState::unpack_unchecked(&[0; STATE_SPACE]).unwrap();
+        let instance_id = Pubkey::new_from_array([1; 32]);
+        let clock = Clock::default();
+        instance.token_mint_id = Pubkey::new_from_array([2; 32]);
+        instance.current_slot = clock.slot;
+        instance.current_epoch = clock.epoch;
+        instance.breach_price = current_price(&instance);
+        instance.difficulty = START_DIFF;
+        instance.limit_break = create_limit_break(
+            &clock,
+            &instance,
+            &instance_id,
+            &recent_hash(&clock),
+            LB_TOKEN_COUNT,
+            instance.difficulty + LB_DIFF,
+        );
+        instance.is_initialized = true;
+        Self {
+            instance,
+            instance_id,
+            epoch_slots: epoch_slots.max(1),
+            limit_break_share,
+            clock,
+            supply_minted: 0,
+            released: 0,
+            breach_carry: 0.0,
+            puzzle_hashes: 0.0,
+            limit_break_hashes: 0.0,
+        }
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.instance.difficulty + LB_DIFF > MAX_DIFF
+    }
+
+    /// Advance one breach window: breaches arrive, then miners spend the window's hashes.
+    pub fn step(&mut self, phase: &Phase) {
+        self.clock.slot += BREACH_WINDOW as u64;
+        self.clock.epoch = self.clock.slot / self.epoch_slots;
+        self.clock.unix_timestamp = (self.clock.slot * SLOT_MS / 1000) as i64;
+
+        self.breach_carry += phase.breach_rate;
+        while self.breach_carry >= 1.0 {
+            self.breach_carry -= 1.0;
+            self.breach(phase.breaches_per_tx.clamp(1, MAX_BREACHES));
+        }
+
+        let hashes = phase.hashrate * (BREACH_WINDOW as u64 * SLOT_MS) as f64 / 1000.0;
+        self.limit_break_hashes += hashes * self.limit_break_share;
+        self.puzzle_hashes += hashes * (1.0 - self.limit_break_share);
+        self.solve_puzzles();
+        //with nothing left to solve the rest goes to the limit break.
+        if self.next_puzzle_difficulty().is_none() {
+            self.limit_break_hashes += self.puzzle_hashes;
+            self.puzzle_hashes = 0.0;
+        }
+        self.limit_breaks();
+    }
+
+    //`process_breach` without the transfers.
+    fn breach(&mut self, breaches: u64) {
+        if self.is_game_over() {
+            return;
+        }
+        let instance = &mut self.instance;
+        let lamports = breaches_cost(instance, self.clock.slot, breaches);
+        let base_tokens = breach_base_tokens(instance, self.clock.epoch);
+        instance.lamports += (lamports / 4) * 3;
+        update_window(instance, &self.clock);
+        let bought = buy_breaches(instance, self.clock.slot, lamports);
+        self.supply_minted += sol_to_lamports((base_tokens as u64 * bought) as f64);
+        Processor::add_breaches(
+            instance,
+            &mut [],
+            &self.clock,
+            &self.instance_id,
+            &recent_hash(&self.clock),
+            bought,
+            lamports,
+            base_tokens,
+        )
+        .unwrap();
+    }
+
+    fn next_puzzle_difficulty(&self) -> Option<u8> {
+        match (self.instance.work.last(), self.instance.batches.first()) {
+            (Some(work), _) => Some(work[33]),
+            (None, Some(batch)) => Some(batch.difficulty),
+            (None, None) => None,
+        }
+    }
+
+    //heap puzzles first, then batches.
+    fn solve_puzzles(&mut self) {
+        while let Some(difficulty) = self.next_puzzle_difficulty() {
+            let cost = puzzle_cost(difficulty);
+            if self.puzzle_hashes < cost {
+                return;
+            }
+            self.puzzle_hashes -= cost;
+            let work = match self.instance.work.pop() {
+                Some(work) => work,
+                None => {
+                    let batch = &self.instance.batches[0];
+                    let seed = batch.seed;
+                    let index = (0..batch.count).find(|i| !batch.is_claimed(*i)).unwrap();
+                    Processor::take_puzzle(&mut self.instance, &seed, index).unwrap()
+                }
+            };
+            self.supply_minted += sol_to_lamports(work[0] as f64);
+        }
+    }
+
+    //the limit break branch of `process_claim_and_breaks`.
+    fn limit_breaks(&mut self) {
+        while !self.is_game_over()
+            && self.limit_break_hashes >= puzzle_cost(self.instance.limit_break[33])
+        {
+            let instance = &mut self.instance;
+            let count = LB_TOKEN_COUNT / calculate_tokens(instance.breach_count);
+            update_window(instance, &self.clock);
+            if let Some(released) = roll_epoch(instance, self.clock.epoch) {
+                self.released += released;
+            }
+            if record_limit_break(instance, count).is_err() {
+                //exhausted for this epoch, the hashes wait for the next one.
+                return;
+            }
+            self.limit_break_hashes -= puzzle_cost(instance.limit_break[33]);
+            self.supply_minted += sol_to_lamports(instance.limit_break[0] as f64);
+            if instance.difficulty + LB_DIFF <= MAX_DIFF {
+                instance.limit_break = create_limit_break(
+                    &self.clock,
+                    instance,
+                    &self.instance_id,
+                    &recent_hash(&self.clock),
+                    LB_TOKEN_COUNT,
+                    instance.difficulty + LB_DIFF,
+                );
+            }
+        }
+    }
+
+    pub fn row(&self) -> Row {
+        Row {
+            slot: self.clock.slot,
+            epoch: self.clock.epoch,
+            breach_price: self.instance.breach_price,
+            breach_count: self.instance.breach_count,
+            limit_count: self.instance.limit_count,
+            supply_minted: self.supply_minted,
+            reserve: self.instance.lamports,
+            released: self.released,
+            difficulty: self.instance.difficulty,
+            heap: self.instance.work.len(),
+            batch_puzzles: self.instance.batches.iter().map(|b| b.open_count()).sum(),
+            work_cached: self.instance.work_cached,
+        }
+    }
+}
+
+/// Expected hashes to match a `magic_len` byte magic.
+pub fn puzzle_cost(magic_len: u8) -> f64 {
+    256f64.powi(magic_len as i32)
+}
+
+fn recent_hash(clock: &Clock) -> [u8; 32] {
+    hash(&clock.slot.to_le_bytes()).to_bytes()
+}
+
+/// Runs every phase in order, a row per `sample_windows` windows and one at the end.
+pub fn run(scenario: &Scenario) -> Vec<Row> {
+    let mut sim = Simulation::new(scenario.epoch_slots, scenario.limit_break_share);
+    let sample = scenario.sample_windows.max(1);
+    let mut rows = vec![sim.row()];
+    let mut windows = 0;
+    for phase in scenario.phases.iter() {
+        for _ in 0..phase.slots / BREACH_WINDOW as u64 {
+            sim.step(phase);
+            windows += 1;
+            if windows % sample == 0 {
+                rows.push(sim.row());
+            }
+        }
+    }
+    if windows % sample != 0 {
+        rows.push(sim.row());
+    }
+    rows
+}
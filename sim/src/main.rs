@@ -0,0 +1,62 @@
+//! Prints a CSV time series of a simulated instance.
+//!
+//! Each `--phase <slots>:<breach txs per window>:<breaches per tx>:<hashes per second>`
+//! runs in order, e.g. a quiet start, a rush of breaches and a large miner joining:
+//!
+//! hihi-sim --phase 432000:1:1:1e6 --phase 432000:20:5:1e6 --phase 864000:2:1:1e10
+
+use std::{env, error::Error, process};
+
+use hihi_sim::{run, Phase, Row, Scenario};
+
+const USAGE: &str = "usage: hihi-sim --phase <slots>:<breach txs per window>:<breaches per tx>:\
+<hashes per second> [--phase ...] [--epoch-slots <slots>] [--limit-break-share <0-1>] \
+[--sample <windows>]";
+
+fn parse_phase(phase: &str) -> Result<Phase, Box<dyn Error>> {
+    let parts: Vec<&str> = phase.split(':').collect();
+    if parts.len() != 4 {
+        return Err(format!("invalid phase {}\n{}", phase, USAGE).into());
+    }
+    Ok(Phase {
+        slots: parts[0].parse()?,
+        breach_rate: parts[1].parse()?,
+        breaches_per_tx: parts[2].parse()?,
+        hashrate: parts[3].parse()?,
+    })
+}
+
+fn parse_args() -> Result<Scenario, Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let get = |name: &str| -> Option<String> {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1).cloned())
+    };
+    let mut phases = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--phase" {
+            phases.push(parse_phase(args.get(i + 1).ok_or(USAGE)?)?);
+        }
+    }
+    if phases.is_empty() {
+        return Err(USAGE.into());
+    }
+    Ok(Scenario {
+        epoch_slots: get("--epoch-slots").unwrap_or("432000".into()).parse()?,
+        limit_break_share: get("--limit-break-share").unwrap_or("0.5".into()).parse()?,
+        sample_windows: get("--sample").unwrap_or("100".into()).parse()?,
+        phases,
+    })
+}
+
+fn main() {
+    let scenario = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    println!("{}", Row::HEADER);
+    for row in run(&scenario) {
+        println!("{}", row.to_csv());
+    }
+}
@@ -0,0 +1,70 @@
+use hihi_sim::{run, Phase, Row, Scenario, Simulation};
+use Hihi::processor::{calculate_price, START_PRICE};
+
+fn scenario(phases: Vec<Phase>) -> Scenario {
+    Scenario {
+        epoch_slots: 10_000,
+        limit_break_share: 0.5,
+        sample_windows: 10,
+        phases,
+    }
+}
+
+fn phase(slots: u64, breach_rate: f64, hashrate: f64) -> Phase {
+    Phase {
+        slots,
+        breach_rate,
+        breaches_per_tx: 1,
+        hashrate,
+    }
+}
+
+#[test]
+fn breaches_without_miners_fill_the_heap_and_raise_the_price() {
+    let rows = run(&scenario(vec![phase(200_000, 3.0, 0.0)]));
+    let last = rows.last().unwrap();
+
+    //split_float can round an exact payment down, the remainder carries to the next one.
+    assert!(last.breach_count > 5990 && last.breach_count <= 6000);
+    assert_eq!(last.limit_count, 0);
+    assert!(last.breach_price > calculate_price(5000, START_PRICE));
+    assert!(rows
+        .windows(2)
+        .all(|w| w[0].breach_price <= w[1].breach_price));
+    assert_eq!(
+        last.heap + last.batch_puzzles as usize + last.work_cached as usize,
+        last.breach_count as usize
+    );
+    assert!(last.work_cached > 0);
+    assert!(last.reserve > 0);
+    assert_eq!(last.released, 0);
+}
+
+#[test]
+fn miners_drain_puzzles_and_limit_breaks_raise_difficulty() {
+    let rows = run(&scenario(vec![phase(50_000, 1.0, 1e10)]));
+    let last = rows.last().unwrap();
+
+    assert_eq!(last.heap, 0);
+    assert_eq!(last.batch_puzzles, 0);
+    assert!(last.limit_count > 0);
+    assert!(last.difficulty > rows[0].difficulty);
+    assert!(last.released > 0);
+    //puzzle, breach and limit break tokens are all counted.
+    assert!(last.supply_minted > rows[rows.len() - 2].supply_minted);
+}
+
+#[test]
+fn rows_are_sampled_and_csv_matches_the_header() {
+    let rows = run(&scenario(vec![
+        phase(2_500, 1.0, 0.0),
+        phase(1_000, 0.0, 0.0),
+    ]));
+    //start, every 10 windows of 35, and the end.
+    assert_eq!(rows.len(), 1 + 3 + 1);
+    let columns = Row::HEADER.split(',').count();
+    assert!(rows
+        .iter()
+        .all(|r| r.to_csv().split(',').count() == columns));
+    assert_eq!(rows[0], Simulation::new(10_000, 0.5).row());
+}
@@ -0,0 +1,1073 @@
+//! Account lists of the instructions. Each `parse` takes the accounts in instruction order and
+//! checks ownership, signers, writable accounts and program addresses in one place, returning
+//! the first failed check as a `HihiError`.
+
+use crate::{
+    error::HihiError,
+    processor::{next_optional_account, Processor},
+    state::{
+        HihiState, MinerState, PageState, ReferrerState, VestingState, MINER_SEED, PAGE_SEED,
+        REFERRER_SEED, VESTING_SEED,
+    },
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+/// The only key allowed to initialize instances.
+const INITIALIZER_ID: [u8; 32] = [
+    80, 97, 223, 1, 83, 109, 8, 147, 151, 40, 159, 3, 204, 231, 107, 20, 85, 34, 21, 236, 209, 141,
+    60, 7, 160, 185, 70, 160, 206, 226, 231, 158,
+];
+
+pub fn signer(info: &AccountInfo, error: HihiError) -> ProgramResult {
+    if info.is_signer == false {
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+pub fn writable(info: &AccountInfo, error: HihiError) -> ProgramResult {
+    if info.is_writable == false {
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// A signer paying for transfers or new accounts.
+pub fn payer(info: &AccountInfo) -> ProgramResult {
+    signer(info, HihiError::PayerNotSigner)?;
+    writable(info, HihiError::PayerNotWritable)
+}
+
+pub fn system_program(info: &AccountInfo) -> ProgramResult {
+    if info.key != &system_program::id() {
+        return Err(HihiError::InvalidSystemProgram.into());
+    }
+    Ok(())
+}
+
+pub fn token_program(info: &AccountInfo) -> ProgramResult {
+    if info.key != &spl_token::id() {
+        return Err(HihiError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+pub fn slot_hashes(info: &AccountInfo) -> ProgramResult {
+    if info.key != &sysvar::slot_hashes::id() {
+        return Err(HihiError::InvalidSlotHashes.into());
+    }
+    Ok(())
+}
+
+/// The mint of the instance.
+pub fn token_mint(instance: &HihiState, info: &AccountInfo) -> ProgramResult {
+    if info.key != &instance.token_mint_id {
+        return Err(HihiError::InvalidTokenMint.into());
+    }
+    Ok(())
+}
+
+/// An initialized instance owned by the program, writable when `write` is set.
+pub fn instance(
+    program_id: &Pubkey,
+    info: &AccountInfo,
+    write: bool,
+) -> Result<HihiState, ProgramError> {
+    if info.owner != program_id {
+        return Err(HihiError::InstanceNotOwnedByProgram.into());
+    }
+    if write {
+        writable(info, HihiError::InstanceNotWritable)?;
+    }
+    let instance = HihiState::unpack_unchecked(&info.data.borrow())?;
+    if instance.is_initialized == false {
+        return Err(HihiError::NotInitialized.into());
+    }
+    Ok(instance)
+}
+
+/// The authority of the instance, `Processor::authority_id` of its nonce.
+pub fn authority(
+    program_id: &Pubkey,
+    instance_info: &AccountInfo,
+    instance: &HihiState,
+    info: &AccountInfo,
+) -> ProgramResult {
+    if &Processor::authority_id(program_id, instance_info.key, instance.nonce)? != info.key {
+        return Err(HihiError::InvalidAuthority.into());
+    }
+    Ok(())
+}
+
+/// Both admins of the instance signed.
+pub fn admins(
+    instance: &HihiState,
+    admin_one_info: &AccountInfo,
+    admin_two_info: &AccountInfo,
+) -> ProgramResult {
+    signer(admin_one_info, HihiError::AdminOneNotSigner)?;
+    signer(admin_two_info, HihiError::AdminTwoNotSigner)?;
+    if admin_one_info.key != &instance.admin_one_id {
+        return Err(HihiError::InvalidAdminOne.into());
+    }
+    if admin_two_info.key != &instance.admin_two_id {
+        return Err(HihiError::InvalidAdminTwo.into());
+    }
+    Ok(())
+}
+
+/// The withdraw key of the instance signed.
+pub fn withdraw_key(instance: &HihiState, info: &AccountInfo) -> ProgramResult {
+    signer(info, HihiError::WithdrawNotSigner)?;
+    if info.key != &instance.withdraw_id {
+        return Err(HihiError::InvalidWithdrawKey.into());
+    }
+    Ok(())
+}
+
+/// The initializer and the keys of a new instance all signed.
+pub fn initializer_and_admins(
+    initializer_info: &AccountInfo,
+    admin_one_info: &AccountInfo,
+    admin_two_info: &AccountInfo,
+    withdraw_info: &AccountInfo,
+) -> ProgramResult {
+    signer(initializer_info, HihiError::InitializerNotSigner)?;
+    if initializer_info.key != &Pubkey::new(&INITIALIZER_ID) {
+        return Err(HihiError::InvalidInitializer.into());
+    }
+    signer(admin_one_info, HihiError::AdminOneNotSigner)?;
+    signer(admin_two_info, HihiError::AdminTwoNotSigner)?;
+    signer(withdraw_info, HihiError::WithdrawNotSigner)
+}
+
+/// The `[seed, instance, wallet]` program address of a wallet account with its stored bump.
+pub fn wallet_address(
+    program_id: &Pubkey,
+    info: &AccountInfo,
+    seed: &[u8],
+    instance_id: &Pubkey,
+    wallet_id: &Pubkey,
+    bump: u8,
+    error: HihiError,
+) -> ProgramResult {
+    let id = Pubkey::create_program_address(
+        &[
+            seed,
+            &instance_id.to_bytes(),
+            &wallet_id.to_bytes(),
+            &[bump],
+        ],
+        program_id,
+    )
+    .or(Err(error.clone()))?;
+    if &id != info.key {
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// The writable vesting account of the instance and `wallet_id`.
+pub fn vesting(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    wallet_id: &Pubkey,
+    info: &AccountInfo,
+) -> Result<VestingState, ProgramError> {
+    if info.owner != program_id {
+        return Err(HihiError::InvalidVestingAccount.into());
+    }
+    writable(info, HihiError::InvalidVestingAccount)?;
+    let vesting = VestingState::unpack(&info.data.borrow())?;
+    if &vesting.instance_id != instance_id || &vesting.wallet_id != wallet_id {
+        return Err(HihiError::InvalidVestingAccount.into());
+    }
+    wallet_address(
+        program_id,
+        info,
+        VESTING_SEED,
+        instance_id,
+        wallet_id,
+        vesting.bump,
+        HihiError::InvalidVestingAccount,
+    )?;
+    Ok(vesting)
+}
+
+/// The writable miner statistics account of the instance and `wallet_id`.
+pub fn miner(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    wallet_id: &Pubkey,
+    info: &AccountInfo,
+) -> Result<MinerState, ProgramError> {
+    if info.owner != program_id {
+        return Err(HihiError::InvalidMinerAccount.into());
+    }
+    writable(info, HihiError::InvalidMinerAccount)?;
+    let miner = MinerState::unpack(&info.data.borrow())?;
+    if &miner.instance_id != instance_id || &miner.wallet_id != wallet_id {
+        return Err(HihiError::InvalidMinerAccount.into());
+    }
+    wallet_address(
+        program_id,
+        info,
+        MINER_SEED,
+        instance_id,
+        wallet_id,
+        miner.bump,
+        HihiError::InvalidMinerAccount,
+    )?;
+    Ok(miner)
+}
+
+/// The writable referral account of the instance, at the address of the wallet it stores.
+pub fn referrer(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    info: &AccountInfo,
+) -> Result<ReferrerState, ProgramError> {
+    if info.owner != program_id {
+        return Err(HihiError::InvalidReferrerAccount.into());
+    }
+    writable(info, HihiError::InvalidReferrerAccount)?;
+    let referrer = ReferrerState::unpack(&info.data.borrow())?;
+    if &referrer.instance_id != instance_id {
+        return Err(HihiError::InvalidReferrerAccount.into());
+    }
+    wallet_address(
+        program_id,
+        info,
+        REFERRER_SEED,
+        instance_id,
+        &referrer.wallet_id,
+        referrer.bump,
+        HihiError::InvalidReferrerAccount,
+    )?;
+    Ok(referrer)
+}
+
+/// A writable work page of the instance at the address of its index.
+pub fn page(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    info: &AccountInfo,
+) -> Result<PageState, ProgramError> {
+    if info.owner != program_id {
+        return Err(HihiError::InvalidPageAccount.into());
+    }
+    writable(info, HihiError::InvalidPageAccount)?;
+    let page = PageState::unpack(&info.data.borrow())?;
+    if &page.instance_id != instance_id {
+        return Err(HihiError::InvalidPageAccount.into());
+    }
+    let id = Pubkey::create_program_address(
+        &[
+            PAGE_SEED,
+            &instance_id.to_bytes(),
+            &page.index.to_le_bytes(),
+            &[page.bump],
+        ],
+        program_id,
+    )
+    .or(Err(HihiError::InvalidPageAccount))?;
+    if &id != info.key {
+        return Err(HihiError::InvalidPageAccount.into());
+    }
+    Ok(page)
+}
+
+/// Work pages of the instance, each passed once.
+pub fn pages<'a, 'b>(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    infos: &[&'a AccountInfo<'b>],
+) -> Result<Vec<(&'a AccountInfo<'b>, PageState)>, ProgramError> {
+    let mut pages = Vec::with_capacity(infos.len());
+    for (i, info) in infos.iter().enumerate() {
+        if infos[..i].iter().any(|p| p.key == info.key) {
+            return Err(HihiError::InvalidPageAccount.into());
+        }
+        pages.push((*info, page(program_id, instance_id, info)?));
+    }
+    Ok(pages)
+}
+
+/// Owner of a token account, the wallet of the miner and vesting accounts it is paid for.
+pub fn token_owner(info: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    Ok(spl_token::state::Account::unpack_unchecked(&info.data.borrow())?.owner)
+}
+
+/// The registry address, returns its bump seed.
+pub fn registry(program_id: &Pubkey, info: &AccountInfo) -> Result<u8, ProgramError> {
+    let (id, bump) = Processor::registry_id(program_id);
+    if &id != info.key || info.is_writable == false {
+        return Err(HihiError::InvalidRegistry.into());
+    }
+    Ok(bump)
+}
+
+pub struct InitializeAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub token_mint: &'a AccountInfo<'b>,
+    pub admin_one: &'a AccountInfo<'b>,
+    pub admin_two: &'a AccountInfo<'b>,
+    pub withdraw: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+    pub slot_hashes: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeAccounts<'a, 'b> {
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance = next_account_info(account_info_iter)?;
+        let initializer = next_account_info(account_info_iter)?;
+        let token_mint = next_account_info(account_info_iter)?;
+        let admin_one = next_account_info(account_info_iter)?;
+        let admin_two = next_account_info(account_info_iter)?;
+        let withdraw = next_account_info(account_info_iter)?;
+        let rent = next_account_info(account_info_iter)?;
+        let slot_hashes_info = next_account_info(account_info_iter)?;
+
+        if instance.owner != program_id {
+            return Err(HihiError::InstanceNotOwnedByProgram.into());
+        }
+        signer(instance, HihiError::InstanceNotSigner)?;
+        initializer_and_admins(initializer, admin_one, admin_two, withdraw)?;
+        slot_hashes(slot_hashes_info)?;
+
+        Ok(Self {
+            instance,
+            token_mint,
+            admin_one,
+            admin_two,
+            withdraw,
+            rent,
+            slot_hashes: slot_hashes_info,
+        })
+    }
+}
+
+pub struct InitializeCanonicalAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub initializer: &'a AccountInfo<'b>,
+    pub token_mint: &'a AccountInfo<'b>,
+    pub admin_one: &'a AccountInfo<'b>,
+    pub admin_two: &'a AccountInfo<'b>,
+    pub withdraw: &'a AccountInfo<'b>,
+    pub registry: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub slot_hashes: &'a AccountInfo<'b>,
+    pub instance_bump: u8,
+    pub registry_bump: u8,
+}
+
+impl<'a, 'b> InitializeCanonicalAccounts<'a, 'b> {
+    /// The instance must be the address of its mint and the mint authority the instance
+    /// authority for `nonce`.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        nonce: u8,
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance = next_account_info(account_info_iter)?;
+        let initializer = next_account_info(account_info_iter)?;
+        let token_mint = next_account_info(account_info_iter)?;
+        let admin_one = next_account_info(account_info_iter)?;
+        let admin_two = next_account_info(account_info_iter)?;
+        let withdraw = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let slot_hashes_info = next_account_info(account_info_iter)?;
+
+        initializer_and_admins(initializer, admin_one, admin_two, withdraw)?;
+        writable(initializer, HihiError::PayerNotWritable)?;
+        system_program(system_program_info)?;
+        slot_hashes(slot_hashes_info)?;
+
+        let (id, instance_bump) = Processor::instance_id(program_id, token_mint.key);
+        if &id != instance.key {
+            return Err(HihiError::InvalidInstanceAddress.into());
+        }
+
+        if token_mint.owner != &spl_token::id() {
+            return Err(HihiError::InvalidTokenMint.into());
+        }
+        let mint = spl_token::state::Mint::unpack(&token_mint.data.borrow())?;
+        let authority_id = Processor::authority_id(program_id, instance.key, nonce)?;
+        if mint.mint_authority != COption::Some(authority_id) {
+            return Err(HihiError::InvalidMintAuthority.into());
+        }
+
+        let registry_bump = registry(program_id, registry_info)?;
+
+        Ok(Self {
+            instance,
+            initializer,
+            token_mint,
+            admin_one,
+            admin_two,
+            withdraw,
+            registry: registry_info,
+            system_program: system_program_info,
+            slot_hashes: slot_hashes_info,
+            instance_bump,
+            registry_bump,
+        })
+    }
+}
+
+pub struct RegisterAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub registry: &'a AccountInfo<'b>,
+    pub payer: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub registry_bump: u8,
+}
+
+impl<'a, 'b> RegisterAccounts<'a, 'b> {
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let admin_one = next_account_info(account_info_iter)?;
+        let admin_two = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, false)?;
+        admins(&state, admin_one, admin_two)?;
+        payer(payer_info)?;
+        system_program(system_program_info)?;
+        let registry_bump = registry(program_id, registry_info)?;
+
+        Ok(Self {
+            instance: instance_info,
+            registry: registry_info,
+            payer: payer_info,
+            system_program: system_program_info,
+            registry_bump,
+        })
+    }
+}
+
+pub struct BreachAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_mint: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub from: &'a AccountInfo<'b>,
+    pub to_token: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub slot_hashes: &'a AccountInfo<'b>,
+    /// The referral account of the instance, paid to `referrer_token`.
+    pub referrer: Option<(&'a AccountInfo<'b>, ReferrerState)>,
+    pub referrer_token: Option<&'a AccountInfo<'b>>,
+    pub pages: Vec<(&'a AccountInfo<'b>, PageState)>,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> BreachAccounts<'a, 'b> {
+    /// A referrer needs its token account.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let from = next_account_info(account_info_iter)?;
+        let to_token = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let slot_hashes_info = next_account_info(account_info_iter)?;
+        let referrer_info = next_optional_account(account_info_iter, program_id);
+        let referrer_token = next_optional_account(account_info_iter, program_id);
+        let page_infos: Vec<&AccountInfo> = account_info_iter.collect();
+
+        let state = instance(program_id, instance_info, true)?;
+        payer(from)?;
+        system_program(system_program_info)?;
+        slot_hashes(slot_hashes_info)?;
+        authority(program_id, instance_info, &state, authority_info)?;
+        token_program(token_program_info)?;
+        token_mint(&state, token_mint_info)?;
+
+        let referrer = match referrer_info {
+            Some(info) => {
+                let referrer_state = referrer(program_id, instance_info.key, info)?;
+                if referrer_token.is_none() {
+                    return Err(HihiError::InvalidReferrerAccount.into());
+                }
+                Some((info, referrer_state))
+            }
+            None => None,
+        };
+        let pages = pages(program_id, instance_info.key, &page_infos)?;
+
+        Ok(Self {
+            instance: instance_info,
+            token_program: token_program_info,
+            token_mint: token_mint_info,
+            authority: authority_info,
+            from,
+            to_token,
+            system_program: system_program_info,
+            slot_hashes: slot_hashes_info,
+            referrer,
+            referrer_token,
+            pages,
+            state,
+        })
+    }
+}
+
+pub struct BreachWithTokensAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub from_token: &'a AccountInfo<'b>,
+    pub slot_hashes: &'a AccountInfo<'b>,
+    pub pages: Vec<(&'a AccountInfo<'b>, PageState)>,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> BreachWithTokensAccounts<'a, 'b> {
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let from_token = next_account_info(account_info_iter)?;
+        let slot_hashes_info = next_account_info(account_info_iter)?;
+        let page_infos: Vec<&AccountInfo> = account_info_iter.collect();
+
+        let state = instance(program_id, instance_info, true)?;
+        signer(owner, HihiError::PayerNotSigner)?;
+        slot_hashes(slot_hashes_info)?;
+        token_program(token_program_info)?;
+        token_mint(&state, token_mint_info)?;
+        let pages = pages(program_id, instance_info.key, &page_infos)?;
+
+        Ok(Self {
+            instance: instance_info,
+            token_program: token_program_info,
+            token_mint: token_mint_info,
+            owner,
+            from_token,
+            slot_hashes: slot_hashes_info,
+            pages,
+            state,
+        })
+    }
+}
+
+/// Which solution a claim carries, it decides the accounts after the shared ones.
+pub enum ClaimKind {
+    /// A stored puzzle, then an optional miner and page account.
+    Work,
+    /// A batch puzzle, then an optional miner account.
+    Puzzle,
+    /// The limit break, then the lamport receiver, system program, slot hashes and optional
+    /// miner and vesting accounts. The vesting account is needed once rewards vest.
+    LimitBreak,
+}
+
+pub struct ClaimAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_mint: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub claim: &'a AccountInfo<'b>,
+    pub pool: &'a AccountInfo<'b>,
+    pub to_token: &'a AccountInfo<'b>,
+    /// Statistics account of the `to_token` owner.
+    pub miner: Option<(&'a AccountInfo<'b>, MinerState)>,
+    pub page: Option<(&'a AccountInfo<'b>, PageState)>,
+    pub to_lamports: Option<&'a AccountInfo<'b>>,
+    pub system_program: Option<&'a AccountInfo<'b>>,
+    pub slot_hashes: Option<&'a AccountInfo<'b>>,
+    /// Vesting account of the `to_token` owner.
+    pub vesting: Option<(&'a AccountInfo<'b>, VestingState)>,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> ClaimAccounts<'a, 'b> {
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        kind: ClaimKind,
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let claim = next_account_info(account_info_iter)?;
+        let pool = next_account_info(account_info_iter)?;
+        let to_token = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, true)?;
+        signer(claim, HihiError::ClaimKeyNotSigner)?;
+        signer(pool, HihiError::PoolKeyNotSigner)?;
+        authority(program_id, instance_info, &state, authority_info)?;
+        token_program(token_program_info)?;
+        token_mint(&state, token_mint_info)?;
+
+        let mut accounts = Self {
+            instance: instance_info,
+            token_program: token_program_info,
+            token_mint: token_mint_info,
+            authority: authority_info,
+            claim,
+            pool,
+            to_token,
+            miner: None,
+            page: None,
+            to_lamports: None,
+            system_program: None,
+            slot_hashes: None,
+            vesting: None,
+            state,
+        };
+
+        let (miner_info, page_info, vesting_info) = match kind {
+            ClaimKind::Work => {
+                let miner_info = next_optional_account(account_info_iter, program_id);
+                let page_info = next_optional_account(account_info_iter, program_id);
+                (miner_info, page_info, None)
+            }
+            ClaimKind::Puzzle => (
+                next_optional_account(account_info_iter, program_id),
+                None,
+                None,
+            ),
+            ClaimKind::LimitBreak => {
+                let to_lamports = next_account_info(account_info_iter)?;
+                let system_program_info = next_account_info(account_info_iter)?;
+                let slot_hashes_info = next_account_info(account_info_iter)?;
+                system_program(system_program_info)?;
+                slot_hashes(slot_hashes_info)?;
+                accounts.to_lamports = Some(to_lamports);
+                accounts.system_program = Some(system_program_info);
+                accounts.slot_hashes = Some(slot_hashes_info);
+                let miner_info = next_optional_account(account_info_iter, program_id);
+                let vesting_info = next_optional_account(account_info_iter, program_id);
+                if accounts.state.vesting_epochs > 0 && vesting_info.is_none() {
+                    return Err(HihiError::InvalidVestingAccount.into());
+                }
+                (miner_info, None, vesting_info)
+            }
+        };
+
+        if let Some(info) = miner_info {
+            let wallet_id = token_owner(to_token)?;
+            accounts.miner = Some((
+                info,
+                miner(program_id, instance_info.key, &wallet_id, info)?,
+            ));
+        }
+        if let Some(info) = page_info {
+            accounts.page = Some((info, page(program_id, instance_info.key, info)?));
+        }
+        if let Some(info) = vesting_info {
+            let wallet_id = token_owner(to_token)?;
+            accounts.vesting = Some((
+                info,
+                vesting(program_id, instance_info.key, &wallet_id, info)?,
+            ));
+        }
+        Ok(accounts)
+    }
+}
+
+/// Accounts creating a miner, referrer or vesting account of a wallet.
+pub struct WalletAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub new: &'a AccountInfo<'b>,
+    pub wallet: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub bump: u8,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> WalletAccounts<'a, 'b> {
+    /// The new account must be the address of `[seed, instance, wallet]`. Vesting accounts are
+    /// counted in the instance, which must be writable for them.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        seed: &[u8],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let new = next_account_info(account_info_iter)?;
+        let wallet = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, seed == VESTING_SEED)?;
+        payer(wallet)?;
+        system_program(system_program_info)?;
+
+        let (id, bump) = Pubkey::find_program_address(
+            &[seed, &instance_info.key.to_bytes(), &wallet.key.to_bytes()],
+            program_id,
+        );
+        if &id != new.key {
+            return Err(HihiError::InvalidProgramAddress.into());
+        }
+
+        Ok(Self {
+            instance: instance_info,
+            new,
+            wallet,
+            system_program: system_program_info,
+            bump,
+            state,
+        })
+    }
+}
+
+pub struct CreatePageAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub page: &'a AccountInfo<'b>,
+    pub payer: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub bump: u8,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> CreatePageAccounts<'a, 'b> {
+    /// The page must be the address of the next page index of the instance.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let page = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, true)?;
+        payer(payer_info)?;
+        system_program(system_program_info)?;
+
+        let (id, bump) = Processor::page_id(program_id, instance_info.key, state.page_count);
+        if &id != page.key {
+            return Err(HihiError::InvalidPageAccount.into());
+        }
+
+        Ok(Self {
+            instance: instance_info,
+            page,
+            payer: payer_info,
+            system_program: system_program_info,
+            bump,
+            state,
+        })
+    }
+}
+
+pub struct ClaimVestedAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_mint: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub vesting: &'a AccountInfo<'b>,
+    pub to_token: &'a AccountInfo<'b>,
+    pub state: HihiState,
+    pub vesting_state: VestingState,
+}
+
+impl<'a, 'b> ClaimVestedAccounts<'a, 'b> {
+    /// The vesting account must belong to the instance and the signing wallet.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let vesting = next_account_info(account_info_iter)?;
+        let wallet = next_account_info(account_info_iter)?;
+        let to_token = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, false)?;
+        signer(wallet, HihiError::PayerNotSigner)?;
+        authority(program_id, instance_info, &state, authority_info)?;
+        token_program(token_program_info)?;
+        token_mint(&state, token_mint_info)?;
+        let vesting_state = self::vesting(program_id, instance_info.key, wallet.key, vesting)?;
+
+        Ok(Self {
+            instance: instance_info,
+            token_program: token_program_info,
+            token_mint: token_mint_info,
+            authority: authority_info,
+            vesting,
+            to_token,
+            state,
+            vesting_state,
+        })
+    }
+}
+
+pub struct ConfigureAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> ConfigureAccounts<'a, 'b> {
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let admin_one = next_account_info(account_info_iter)?;
+        let admin_two = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, true)?;
+        admins(&state, admin_one, admin_two)?;
+
+        Ok(Self {
+            instance: instance_info,
+            state,
+        })
+    }
+}
+
+pub struct ChangeKeysAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub new_admin_one: &'a AccountInfo<'b>,
+    pub new_admin_two: &'a AccountInfo<'b>,
+    pub new_withdraw: &'a AccountInfo<'b>,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> ChangeKeysAccounts<'a, 'b> {
+    /// The current admins and withdraw key and their replacements all sign.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let admin_one = next_account_info(account_info_iter)?;
+        let admin_two = next_account_info(account_info_iter)?;
+        let withdraw = next_account_info(account_info_iter)?;
+        let new_admin_one = next_account_info(account_info_iter)?;
+        let new_admin_two = next_account_info(account_info_iter)?;
+        let new_withdraw = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, true)?;
+        admins(&state, admin_one, admin_two)?;
+        withdraw_key(&state, withdraw)?;
+        signer(new_admin_one, HihiError::NewAdminOneNotSigner)?;
+        signer(new_admin_two, HihiError::NewAdminTwoNotSigner)?;
+        signer(new_withdraw, HihiError::NewWithdrawNotSigner)?;
+
+        Ok(Self {
+            instance: instance_info,
+            new_admin_one,
+            new_admin_two,
+            new_withdraw,
+            state,
+        })
+    }
+}
+
+pub struct WithdrawAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub withdraw: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> WithdrawAccounts<'a, 'b> {
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let withdraw = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        let state = instance(program_id, instance_info, false)?;
+        withdraw_key(&state, withdraw)?;
+        authority(program_id, instance_info, &state, authority_info)?;
+        system_program(system_program_info)?;
+
+        Ok(Self {
+            instance: instance_info,
+            authority: authority_info,
+            withdraw,
+            system_program: system_program_info,
+            state,
+        })
+    }
+}
+
+pub struct CloseAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub token_mint: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub new_mint_authority: &'a AccountInfo<'b>,
+    pub withdraw: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    /// The registry the instance is dropped from, if it was ever created.
+    pub registry: &'a AccountInfo<'b>,
+    pub pages: Vec<(&'a AccountInfo<'b>, PageState)>,
+    /// Vesting accounts with the token account of their wallet.
+    pub vestings: Vec<(&'a AccountInfo<'b>, &'a AccountInfo<'b>, VestingState)>,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> CloseAccounts<'a, 'b> {
+    /// Every page of the instance is passed first, then the pair of every vesting account of
+    /// the instance and its token account, each once.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let admin_one = next_account_info(account_info_iter)?;
+        let admin_two = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let new_mint_authority = next_account_info(account_info_iter)?;
+        let withdraw = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let registry_info = next_account_info(account_info_iter)?;
+        let rest: Vec<&AccountInfo> = account_info_iter.collect();
+
+        let state = instance(program_id, instance_info, true)?;
+        admins(&state, admin_one, admin_two)?;
+        if withdraw.key != &state.withdraw_id {
+            return Err(HihiError::InvalidWithdrawKey.into());
+        }
+        authority(program_id, instance_info, &state, authority_info)?;
+        token_program(token_program_info)?;
+        token_mint(&state, token_mint_info)?;
+        system_program(system_program_info)?;
+        registry(program_id, registry_info)?;
+
+        if rest.len() < state.page_count as usize {
+            return Err(HihiError::InvalidPageAccount.into());
+        }
+        let (page_infos, vesting_infos) = rest.split_at(state.page_count as usize);
+        let pages = pages(program_id, instance_info.key, page_infos)?;
+
+        if vesting_infos.len() % 2 != 0 {
+            return Err(HihiError::InvalidVestingAccount.into());
+        }
+        let mut vestings = Vec::with_capacity(vesting_infos.len() / 2);
+        for (i, pair) in vesting_infos.chunks_exact(2).enumerate() {
+            if vesting_infos[..2 * i]
+                .chunks_exact(2)
+                .any(|p| p[0].key == pair[0].key)
+            {
+                return Err(HihiError::InvalidVestingAccount.into());
+            }
+            let wallet_id = token_owner(pair[1])?;
+            let vesting_state = vesting(program_id, instance_info.key, &wallet_id, pair[0])?;
+            vestings.push((pair[0], pair[1], vesting_state));
+        }
+        if vestings.len() != state.vesting_count as usize {
+            return Err(HihiError::VestingsNotSettled.into());
+        }
+
+        Ok(Self {
+            instance: instance_info,
+            token_program: token_program_info,
+            token_mint: token_mint_info,
+            authority: authority_info,
+            new_mint_authority,
+            withdraw,
+            system_program: system_program_info,
+            registry: registry_info,
+            pages,
+            vestings,
+            state,
+        })
+    }
+}
+
+pub struct MigrateAccounts<'a, 'b> {
+    pub instance: &'a AccountInfo<'b>,
+    pub payer: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    /// Page 0 of the instance, needed when the heap holds more than `HEAP_COUNT` puzzles.
+    pub page: Option<&'a AccountInfo<'b>>,
+    pub page_bump: u8,
+    pub state: HihiState,
+}
+
+impl<'a, 'b> MigrateAccounts<'a, 'b> {
+    /// The instance must still be in the first release layout.
+    pub fn parse(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let instance_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let page = next_optional_account(account_info_iter, program_id);
+
+        if instance_info.owner != program_id {
+            return Err(HihiError::InstanceNotOwnedByProgram.into());
+        }
+        writable(instance_info, HihiError::InstanceNotWritable)?;
+        let state = HihiState::unpack_legacy(&instance_info.data.borrow())?;
+        payer(payer_info)?;
+        system_program(system_program_info)?;
+
+        let (id, page_bump) = Processor::page_id(program_id, instance_info.key, 0);
+        if let Some(page) = page {
+            if &id != page.key {
+                return Err(HihiError::InvalidPageAccount.into());
+            }
+        }
+
+        Ok(Self {
+            instance: instance_info,
+            payer: payer_info,
+            system_program: system_program_info,
+            page,
+            page_bump,
+            state,
+        })
+    }
+}
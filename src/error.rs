@@ -4,28 +4,122 @@ use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use solana_program::{
     decode_error::DecodeError,
+    instruction::InstructionError,
     msg,
     program_error::{PrintProgramError, ProgramError},
 };
 use std::fmt;
 use thiserror::Error;
 
+/// Every variant has a fixed code, returned on chain as `ProgramError::Custom(code)`.
+/// New variants must be appended with the next free code, never renumbered.
 #[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
 pub enum HihiError {
-    InvalidInstruction,
-    InvalidOwner,
-    InvalidProgramAddress,
-    InvalidTokenMint,
-    InvalidTokenAddress,
-    WorkLimitExceeded,
-    InvalidClaimHash,
-    IncorrectClaimSolution,
-    DeserializationFailure,
-    NotInitialized,
-    AlreadyInitialized,
-    NotRentExempt,
-    InsufficientFundsForTransaction,
-    UnknownError,
+    InvalidInstruction = 0,
+    InvalidOwner = 1,
+    InvalidProgramAddress = 2,
+    InvalidTokenMint = 3,
+    InvalidTokenAddress = 4,
+    WorkLimitExceeded = 5,
+    InvalidClaimHash = 6,
+    IncorrectClaimSolution = 7,
+    DeserializationFailure = 8,
+    NotInitialized = 9,
+    AlreadyInitialized = 10,
+    NotRentExempt = 11,
+    InsufficientFundsForTransaction = 12,
+    UnknownError = 13,
+    InstanceNotOwnedByProgram = 14,
+    InstanceNotWritable = 15,
+    InstanceNotSigner = 16,
+    InitializerNotSigner = 17,
+    InvalidInitializer = 18,
+    AdminOneNotSigner = 19,
+    AdminTwoNotSigner = 20,
+    WithdrawNotSigner = 21,
+    InvalidAdminOne = 22,
+    InvalidAdminTwo = 23,
+    InvalidWithdrawKey = 24,
+    NewAdminOneNotSigner = 25,
+    NewAdminTwoNotSigner = 26,
+    NewWithdrawNotSigner = 27,
+    PayerNotSigner = 28,
+    PayerNotWritable = 29,
+    InvalidAuthority = 30,
+    InvalidTokenProgram = 31,
+    InvalidSystemProgram = 32,
+    ClaimKeyNotSigner = 33,
+    PoolKeyNotSigner = 34,
+    BreachTooSmall = 35,
+    BreachTooLarge = 36,
+    BreachCountOverflow = 37,
+    GameOver = 38,
+    LimitBreaksExhausted = 39,
+    InvalidWorkCount = 40,
+    InvalidMagicLength = 41,
+    InvalidMinerAccount = 42,
+    InvalidReferrerAccount = 43,
+    SelfReferral = 44,
+    InvalidReferralShare = 45,
+    InvalidVestingAccount = 46,
+    NothingVested = 47,
+    InvalidPageAccount = 48,
+    InvalidBatchCount = 49,
+    InvalidPuzzleIndex = 50,
+    PuzzleAlreadyClaimed = 51,
+    InvalidSlotHashes = 52,
+    SupplyExhausted = 53,
+    InvalidMaxSupply = 54,
+    InvalidPriceCurve = 55,
+    InvalidSurge = 56,
+    InvalidDecay = 57,
+    InstanceStillActive = 58,
+    InvalidInstanceAddress = 59,
+    InvalidMintAuthority = 60,
+    InvalidRegistry = 61,
+    RegistryFull = 62,
+    AlreadyRegistered = 63,
+    TokenPriceOverflow = 64,
+    LegacyState = 65,
+    VestingsNotSettled = 66,
+    VestingCountOverflow = 67,
+    InvalidAccountType = 68,
+}
+
+impl HihiError {
+    /// The stable numeric code sent back as `ProgramError::Custom`.
+    pub fn code(&self) -> u32 {
+        self.clone() as u32
+    }
+
+    /// Look up the error for a `ProgramError::Custom` code.
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::from_u32(code)
+    }
+
+    /// Decode a failed program result into a `HihiError`, if it is one.
+    pub fn decode(error: &ProgramError) -> Option<Self> {
+        match error {
+            ProgramError::Custom(code) => Self::from_code(*code),
+            _ => None,
+        }
+    }
+
+    /// Decode the instruction error of a failed transaction into a `HihiError`, if it is one.
+    pub fn decode_instruction_error(error: &InstructionError) -> Option<Self> {
+        match error {
+            InstructionError::Custom(code) => Self::from_code(*code),
+            _ => None,
+        }
+    }
+}
+
+/// Readable explanation of a failed instruction, for clients and logs.
+pub fn explain_instruction_error(error: &InstructionError) -> String {
+    match HihiError::decode_instruction_error(error) {
+        Some(e) => format!("HihiError {}: {}", e.code(), e),
+        None => error.to_string(),
+    }
 }
 
 impl From<HihiError> for ProgramError {
@@ -58,6 +152,87 @@ impl fmt::Display for HihiError {
             HihiError::InsufficientFundsForTransaction => {
                 f.write_str("Not enough funds to process transaction")
             }
+            HihiError::InstanceNotOwnedByProgram => {
+                f.write_str("Instance account is not owned by the program")
+            }
+            HihiError::InstanceNotWritable => f.write_str("Instance account must be writable"),
+            HihiError::InstanceNotSigner => f.write_str("Instance account must sign"),
+            HihiError::InitializerNotSigner => f.write_str("Initializer must sign"),
+            HihiError::InvalidInitializer => f.write_str("Initializer key is not allowed"),
+            HihiError::AdminOneNotSigner => f.write_str("Admin one must sign"),
+            HihiError::AdminTwoNotSigner => f.write_str("Admin two must sign"),
+            HihiError::WithdrawNotSigner => f.write_str("Withdraw key must sign"),
+            HihiError::InvalidAdminOne => f.write_str("Admin one does not match the instance"),
+            HihiError::InvalidAdminTwo => f.write_str("Admin two does not match the instance"),
+            HihiError::InvalidWithdrawKey => {
+                f.write_str("Withdraw key does not match the instance")
+            }
+            HihiError::NewAdminOneNotSigner => f.write_str("New admin one must sign"),
+            HihiError::NewAdminTwoNotSigner => f.write_str("New admin two must sign"),
+            HihiError::NewWithdrawNotSigner => f.write_str("New withdraw key must sign"),
+            HihiError::PayerNotSigner => f.write_str("Paying account must sign"),
+            HihiError::PayerNotWritable => f.write_str("Paying account must be writable"),
+            HihiError::InvalidAuthority => {
+                f.write_str("Authority is not the instance program address")
+            }
+            HihiError::InvalidTokenProgram => f.write_str("Token program is not spl-token"),
+            HihiError::InvalidSystemProgram => f.write_str("Invalid system program"),
+            HihiError::ClaimKeyNotSigner => f.write_str("Claim key must sign"),
+            HihiError::PoolKeyNotSigner => f.write_str("Pool key must sign"),
+            HihiError::BreachTooSmall => f.write_str("Breach amount is below the minimum"),
+            HihiError::BreachTooLarge => {
+                f.write_str("Breach amount is above ten times the breach price")
+            }
+            HihiError::BreachCountOverflow => f.write_str("Breach count overflow"),
+            HihiError::GameOver => f.write_str("Maximum difficulty reached, the game is over"),
+            HihiError::LimitBreaksExhausted => f.write_str("No limit breaks left for this epoch"),
+            HihiError::InvalidWorkCount => f.write_str("Stored work count exceeds the heap size"),
+            HihiError::InvalidMagicLength => f.write_str("Work magic length is out of bounds"),
+            HihiError::InvalidMinerAccount => {
+                f.write_str("Miner account does not belong to this instance and wallet")
+            }
+            HihiError::InvalidReferrerAccount => {
+                f.write_str("Referrer accounts do not belong to this instance and wallet")
+            }
+            HihiError::SelfReferral => f.write_str("Breaching wallet can not refer itself"),
+            HihiError::InvalidReferralShare => {
+                f.write_str("Referral share can not exceed 10000 basis points")
+            }
+            HihiError::InvalidVestingAccount => f.write_str("Invalid vesting account"),
+            HihiError::NothingVested => f.write_str("No vested tokens to claim"),
+            HihiError::InvalidPageAccount => f.write_str("Invalid work page account"),
+            HihiError::InvalidBatchCount => f.write_str("Puzzle batch count exceeds the maximum"),
+            HihiError::InvalidPuzzleIndex => f.write_str("Puzzle index is outside its batch"),
+            HihiError::PuzzleAlreadyClaimed => f.write_str("Puzzle was already claimed"),
+            HihiError::InvalidSlotHashes => f.write_str("Invalid SlotHashes sysvar account"),
+            HihiError::SupplyExhausted => f.write_str("Max supply reached, nothing left to mint"),
+            HihiError::InvalidMaxSupply => {
+                f.write_str("Max supply can only be lowered and not below the minted supply")
+            }
+            HihiError::InvalidPriceCurve => f.write_str("Price curve exponent is out of bounds"),
+            HihiError::InvalidSurge => {
+                f.write_str("Surge can not exceed 10000 basis points per breach")
+            }
+            HihiError::InvalidDecay => {
+                f.write_str("Decay needs a floor and both can not exceed 10000 basis points")
+            }
+            HihiError::InstanceStillActive => {
+                f.write_str("Instance can only close once the game is over or no puzzle is left")
+            }
+            HihiError::InvalidInstanceAddress => {
+                f.write_str("Instance is not the program address of its mint")
+            }
+            HihiError::InvalidMintAuthority => {
+                f.write_str("Mint authority must be the instance authority")
+            }
+            HihiError::InvalidRegistry => f.write_str("Invalid registry account"),
+            HihiError::RegistryFull => f.write_str("Registry can not list more instances"),
+            HihiError::AlreadyRegistered => f.write_str("Instance is already registered"),
+            HihiError::TokenPriceOverflow => f.write_str("Token price of the breaches overflows"),
+            HihiError::LegacyState => f.write_str("Instance uses the old layout, migrate it first"),
+            HihiError::VestingsNotSettled => f.write_str("Every vesting account must be settled"),
+            HihiError::VestingCountOverflow => f.write_str("Vesting count overflow"),
+            HihiError::InvalidAccountType => f.write_str("Account holds another kind of state"),
         }
     }
 }
@@ -84,6 +259,79 @@ impl PrintProgramError for HihiError {
             HihiError::InsufficientFundsForTransaction => {
                 msg!("Not enough funds to process transaction")
             }
+            HihiError::InstanceNotOwnedByProgram => {
+                msg!("Instance account is not owned by the program")
+            }
+            HihiError::InstanceNotWritable => msg!("Instance account must be writable"),
+            HihiError::InstanceNotSigner => msg!("Instance account must sign"),
+            HihiError::InitializerNotSigner => msg!("Initializer must sign"),
+            HihiError::InvalidInitializer => msg!("Initializer key is not allowed"),
+            HihiError::AdminOneNotSigner => msg!("Admin one must sign"),
+            HihiError::AdminTwoNotSigner => msg!("Admin two must sign"),
+            HihiError::WithdrawNotSigner => msg!("Withdraw key must sign"),
+            HihiError::InvalidAdminOne => msg!("Admin one does not match the instance"),
+            HihiError::InvalidAdminTwo => msg!("Admin two does not match the instance"),
+            HihiError::InvalidWithdrawKey => msg!("Withdraw key does not match the instance"),
+            HihiError::NewAdminOneNotSigner => msg!("New admin one must sign"),
+            HihiError::NewAdminTwoNotSigner => msg!("New admin two must sign"),
+            HihiError::NewWithdrawNotSigner => msg!("New withdraw key must sign"),
+            HihiError::PayerNotSigner => msg!("Paying account must sign"),
+            HihiError::PayerNotWritable => msg!("Paying account must be writable"),
+            HihiError::InvalidAuthority => msg!("Authority is not the instance program address"),
+            HihiError::InvalidTokenProgram => msg!("Token program is not spl-token"),
+            HihiError::InvalidSystemProgram => msg!("Invalid system program"),
+            HihiError::ClaimKeyNotSigner => msg!("Claim key must sign"),
+            HihiError::PoolKeyNotSigner => msg!("Pool key must sign"),
+            HihiError::BreachTooSmall => msg!("Breach amount is below the minimum"),
+            HihiError::BreachTooLarge => msg!("Breach amount is above ten times the breach price"),
+            HihiError::BreachCountOverflow => msg!("Breach count overflow"),
+            HihiError::GameOver => msg!("Maximum difficulty reached, the game is over"),
+            HihiError::LimitBreaksExhausted => msg!("No limit breaks left for this epoch"),
+            HihiError::InvalidWorkCount => msg!("Stored work count exceeds the heap size"),
+            HihiError::InvalidMagicLength => msg!("Work magic length is out of bounds"),
+            HihiError::InvalidMinerAccount => {
+                msg!("Miner account does not belong to this instance and wallet")
+            }
+            HihiError::InvalidReferrerAccount => {
+                msg!("Referrer accounts do not belong to this instance and wallet")
+            }
+            HihiError::SelfReferral => msg!("Breaching wallet can not refer itself"),
+            HihiError::InvalidReferralShare => {
+                msg!("Referral share can not exceed 10000 basis points")
+            }
+            HihiError::InvalidVestingAccount => msg!("Invalid vesting account"),
+            HihiError::NothingVested => msg!("No vested tokens to claim"),
+            HihiError::InvalidPageAccount => msg!("Invalid work page account"),
+            HihiError::InvalidBatchCount => msg!("Puzzle batch count exceeds the maximum"),
+            HihiError::InvalidPuzzleIndex => msg!("Puzzle index is outside its batch"),
+            HihiError::PuzzleAlreadyClaimed => msg!("Puzzle was already claimed"),
+            HihiError::InvalidSlotHashes => msg!("Invalid SlotHashes sysvar account"),
+            HihiError::SupplyExhausted => msg!("Max supply reached, nothing left to mint"),
+            HihiError::InvalidMaxSupply => {
+                msg!("Max supply can only be lowered and not below the minted supply")
+            }
+            HihiError::InvalidPriceCurve => msg!("Price curve exponent is out of bounds"),
+            HihiError::InvalidSurge => msg!("Surge can not exceed 10000 basis points per breach"),
+            HihiError::InvalidDecay => {
+                msg!("Decay needs a floor and both can not exceed 10000 basis points")
+            }
+            HihiError::InstanceStillActive => {
+                msg!("Instance can only close once the game is over or no puzzle is left")
+            }
+            HihiError::InvalidInstanceAddress => {
+                msg!("Instance is not the program address of its mint")
+            }
+            HihiError::InvalidMintAuthority => {
+                msg!("Mint authority must be the instance authority")
+            }
+            HihiError::InvalidRegistry => msg!("Invalid registry account"),
+            HihiError::RegistryFull => msg!("Registry can not list more instances"),
+            HihiError::AlreadyRegistered => msg!("Instance is already registered"),
+            HihiError::TokenPriceOverflow => msg!("Token price of the breaches overflows"),
+            HihiError::LegacyState => msg!("Instance uses the old layout, migrate it first"),
+            HihiError::VestingsNotSettled => msg!("Every vesting account must be settled"),
+            HihiError::VestingCountOverflow => msg!("Vesting count overflow"),
+            HihiError::InvalidAccountType => msg!("Account holds another kind of state"),
         }
     }
 }
@@ -0,0 +1,654 @@
+//! JSON IDL of the program interface, generated from the instruction builders,
+//! the state layout and the error enum so it can't drift from the code.
+
+use crate::{
+    error::HihiError,
+    instruction::{self, Configure, HihiInstruction, V2_TAG_OFFSET, WORK_BYTES},
+    state::{
+        BATCH_BYTES, BATCH_LAYOUT, MINER_LAYOUT, MINER_SPACE, PAGE_LAYOUT, PAGE_SPACE,
+        REFERRER_LAYOUT, REFERRER_SPACE, REGISTRY_LAYOUT, REGISTRY_SPACE, STATE_LAYOUT,
+        STATE_SPACE, VESTING_LAYOUT, VESTING_SPACE,
+    },
+};
+
+use solana_program::{instruction::Instruction, pubkey::Pubkey, system_program, sysvar};
+
+/// Stand-in key for the n-th named account passed to a builder.
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n + 1; 32])
+}
+
+struct IdlInstruction {
+    name: &'static str,
+    args: &'static [(&'static str, &'static str)],
+    account_names: &'static [&'static str],
+    optional: &'static [&'static str],
+    instruction: Instruction,
+}
+
+fn instructions() -> Vec<IdlInstruction> {
+    let program_id = Pubkey::new_from_array([255; 32]);
+    vec![
+        IdlInstruction {
+            name: "initialize",
+            args: &[("nonce", "u8")],
+            account_names: &[
+                "instance",
+                "initializer",
+                "tokenMint",
+                "adminOne",
+                "adminTwo",
+                "withdraw",
+            ],
+            optional: &[],
+            instruction: instruction::initialize(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "breach",
+            args: &[("lamports", "u64")],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "toToken",
+                "from",
+                "referrer",
+                "referrerToken",
+                "pages",
+            ],
+            optional: &["referrer", "referrerToken", "pages"],
+            instruction: instruction::breach(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                Some((&key(6), &key(7))),
+                &[key(8)],
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "limitBreak",
+            args: &[],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "toToken",
+                "toLamports",
+                "claimKey",
+                "poolKey",
+                "miner",
+                "vesting",
+            ],
+            optional: &["miner", "vesting"],
+            instruction: instruction::limit_break(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                &key(7),
+                Some(&key(8)),
+                Some(&key(9)),
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "claim",
+            args: &[("work", "[u8; 57]")],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "claimKey",
+                "poolKey",
+                "toToken",
+                "miner",
+                "page",
+            ],
+            optional: &["miner", "page"],
+            instruction: instruction::claim(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                Some(&key(7)),
+                Some(&key(8)),
+                [0; WORK_BYTES],
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "withdraw",
+            args: &[],
+            account_names: &["instance", "authority", "withdraw"],
+            optional: &[],
+            instruction: instruction::withdraw(&program_id, &key(0), &key(1), &key(2)).unwrap(),
+        },
+        IdlInstruction {
+            name: "changeKeys",
+            args: &[],
+            account_names: &[
+                "instance",
+                "adminOne",
+                "adminTwo",
+                "withdraw",
+                "newAdminOne",
+                "newAdminTwo",
+                "newWithdraw",
+            ],
+            optional: &[],
+            instruction: instruction::change_keys(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "createMiner",
+            args: &[],
+            account_names: &["instance", "miner", "wallet"],
+            optional: &[],
+            instruction: instruction::create_miner(&program_id, &key(0), &key(1), &key(2)).unwrap(),
+        },
+        IdlInstruction {
+            name: "createReferrer",
+            args: &[],
+            account_names: &["instance", "referrer", "wallet"],
+            optional: &[],
+            instruction: instruction::create_referrer(&program_id, &key(0), &key(1), &key(2))
+                .unwrap(),
+        },
+        IdlInstruction {
+            name: "configure",
+            args: &[
+                ("referral_share", "u16"),
+                ("vesting_epochs", "u16"),
+                ("max_supply", "u64"),
+                ("price_scale", "u32"),
+                ("price_exponent", "u32"),
+                ("surge_bps", "u16"),
+                ("decay_bps", "u16"),
+                ("decay_floor_bps", "u16"),
+            ],
+            account_names: &["instance", "adminOne", "adminTwo"],
+            optional: &[],
+            instruction: instruction::configure(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                Configure {
+                    referral_share: 0,
+                    vesting_epochs: 0,
+                    max_supply: 0,
+                    price_scale: 0,
+                    price_exponent: 0,
+                    surge_bps: 0,
+                    decay_bps: 0,
+                    decay_floor_bps: 0,
+                },
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "breachWithTokens",
+            args: &[("breaches", "u64")],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "owner",
+                "fromToken",
+                "pages",
+            ],
+            optional: &["pages"],
+            instruction: instruction::breach_with_tokens(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &[key(5)],
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "createVesting",
+            args: &[],
+            account_names: &["instance", "vesting", "wallet"],
+            optional: &[],
+            instruction: instruction::create_vesting(&program_id, &key(0), &key(1), &key(2))
+                .unwrap(),
+        },
+        IdlInstruction {
+            name: "claimVested",
+            args: &[],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "vesting",
+                "wallet",
+                "toToken",
+            ],
+            optional: &[],
+            instruction: instruction::claim_vested(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "createPage",
+            args: &[],
+            account_names: &["instance", "page", "payer"],
+            optional: &[],
+            instruction: instruction::create_page(&program_id, &key(0), &key(1), &key(2)).unwrap(),
+        },
+        IdlInstruction {
+            name: "claimPuzzle",
+            args: &[("seed", "[u8; 32]"), ("index", "u8")],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "claimKey",
+                "poolKey",
+                "toToken",
+                "miner",
+            ],
+            optional: &["miner"],
+            instruction: instruction::claim_puzzle(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                Some(&key(7)),
+                [0; 32],
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "claimWithNonce",
+            args: &[("work", "[u8; 57]"), ("nonce", "u64")],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "claimKey",
+                "poolKey",
+                "toToken",
+                "miner",
+                "page",
+            ],
+            optional: &["miner", "page"],
+            instruction: instruction::claim_with_nonce(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                Some(&key(7)),
+                Some(&key(8)),
+                [0; WORK_BYTES],
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "claimPuzzleWithNonce",
+            args: &[("seed", "[u8; 32]"), ("index", "u8"), ("nonce", "u64")],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "claimKey",
+                "poolKey",
+                "toToken",
+                "miner",
+            ],
+            optional: &["miner"],
+            instruction: instruction::claim_puzzle_with_nonce(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                Some(&key(7)),
+                [0; 32],
+                0,
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "limitBreakWithNonce",
+            args: &[("nonce", "u64")],
+            account_names: &[
+                "instance",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "toToken",
+                "toLamports",
+                "claimKey",
+                "poolKey",
+                "miner",
+                "vesting",
+            ],
+            optional: &["miner", "vesting"],
+            instruction: instruction::limit_break_with_nonce(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                &key(7),
+                Some(&key(8)),
+                Some(&key(9)),
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "initializeWithHalving",
+            args: &[("nonce", "u8"), ("halving_epochs", "u64")],
+            account_names: &[
+                "instance",
+                "initializer",
+                "tokenMint",
+                "adminOne",
+                "adminTwo",
+                "withdraw",
+            ],
+            optional: &[],
+            instruction: instruction::initialize_with_halving(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                0,
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "close",
+            args: &[],
+            account_names: &[
+                "instance",
+                "adminOne",
+                "adminTwo",
+                "tokenProgram",
+                "tokenMint",
+                "authority",
+                "newMintAuthority",
+                "withdraw",
+                "registry",
+                "pages",
+                "vesting",
+                "toToken",
+            ],
+            optional: &["pages", "vesting", "toToken"],
+            instruction: instruction::close(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                &key(7),
+                &key(8),
+                &[key(9)],
+                &[(key(10), key(11))],
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "initializeCanonical",
+            args: &[("nonce", "u8"), ("halving_epochs", "u64")],
+            account_names: &[
+                "instance",
+                "initializer",
+                "tokenMint",
+                "adminOne",
+                "adminTwo",
+                "withdraw",
+                "registry",
+            ],
+            optional: &[],
+            instruction: instruction::initialize_canonical(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+                &key(5),
+                &key(6),
+                0,
+                0,
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "register",
+            args: &[],
+            account_names: &["instance", "adminOne", "adminTwo", "registry", "payer"],
+            optional: &[],
+            instruction: instruction::register(
+                &program_id,
+                &key(0),
+                &key(1),
+                &key(2),
+                &key(3),
+                &key(4),
+            )
+            .unwrap(),
+        },
+        IdlInstruction {
+            name: "migrate",
+            args: &[],
+            account_names: &["instance", "payer", "page"],
+            optional: &["page"],
+            instruction: instruction::migrate(&program_id, &key(0), &key(1), Some(&key(2)))
+                .unwrap(),
+        },
+    ]
+}
+
+fn account_name(names: &[&'static str], pubkey: &Pubkey) -> &'static str {
+    if pubkey == &system_program::id() {
+        return "systemProgram";
+    }
+    if pubkey == &sysvar::rent::id() {
+        return "rent";
+    }
+    if pubkey == &sysvar::slot_hashes::id() {
+        return "slotHashes";
+    }
+    for (i, name) in names.iter().enumerate() {
+        if pubkey == &key(i as u8) {
+            return name;
+        }
+    }
+    "unknown"
+}
+
+fn join(items: Vec<String>, indent: &str) -> String {
+    if items.is_empty() {
+        return "[]".to_owned();
+    }
+    let inner = format!(",\n{}  ", indent);
+    format!("[\n{}  {}\n{}]", indent, items.join(&inner), indent)
+}
+
+fn instructions_json() -> String {
+    let mut out = Vec::<String>::new();
+    for ix in instructions() {
+        let accounts = ix
+            .instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let name = account_name(ix.account_names, &meta.pubkey);
+                format!(
+                    "{{ \"name\": \"{}\", \"isMut\": {}, \"isSigner\": {}, \"isOptional\": {} }}",
+                    name,
+                    meta.is_writable,
+                    meta.is_signer,
+                    ix.optional.contains(&name)
+                )
+            })
+            .collect();
+        let args = ix
+            .args
+            .iter()
+            .map(|(name, ty)| format!("{{ \"name\": \"{}\", \"type\": \"{}\" }}", name, ty))
+            .collect();
+        let legacy_tag = match HihiInstruction::unpack(&ix.instruction.data)
+            .unwrap()
+            .pack_legacy()
+        {
+            Some(data) => data[0].to_string(),
+            None => "null".to_owned(),
+        };
+        out.push(format!(
+            "{{\n      \"name\": \"{}\",\n      \"tag\": {},\n      \"legacyTag\": {},\n      \"accounts\": {},\n      \"args\": {}\n    }}",
+            ix.name,
+            ix.instruction.data[0],
+            legacy_tag,
+            join(accounts, "      "),
+            join(args, "      ")
+        ));
+    }
+    join(out, "  ")
+}
+
+fn account_json(name: &str, space: usize, layout: &[(&str, &str, usize)]) -> String {
+    let mut offset = 0;
+    let mut fields = Vec::<String>::new();
+    for (name, ty, size) in layout.iter() {
+        fields.push(format!(
+            "{{ \"name\": \"{}\", \"type\": \"{}\", \"offset\": {}, \"size\": {} }}",
+            name, ty, offset, size
+        ));
+        offset += size;
+    }
+    format!(
+        "{{\n      \"name\": \"{}\",\n      \"size\": {},\n      \"fields\": {}\n    }}",
+        name,
+        space,
+        join(fields, "      ")
+    )
+}
+
+fn accounts_json() -> String {
+    join(
+        vec![
+            account_json("HihiState", STATE_SPACE, &STATE_LAYOUT),
+            account_json("MinerState", MINER_SPACE, &MINER_LAYOUT),
+            account_json("ReferrerState", REFERRER_SPACE, &REFERRER_LAYOUT),
+            account_json("VestingState", VESTING_SPACE, &VESTING_LAYOUT),
+            account_json("PageState", PAGE_SPACE, &PAGE_LAYOUT),
+            account_json("RegistryState", REGISTRY_SPACE, &REGISTRY_LAYOUT),
+        ],
+        "  ",
+    )
+}
+
+fn types_json() -> String {
+    join(
+        vec![account_json("PuzzleBatch", BATCH_BYTES, &BATCH_LAYOUT)],
+        "  ",
+    )
+}
+
+fn errors_json() -> String {
+    let mut errors = Vec::<String>::new();
+    let mut code = 0;
+    while let Some(e) = HihiError::from_code(code) {
+        errors.push(format!(
+            "{{ \"code\": {}, \"name\": \"{:?}\", \"msg\": \"{}\" }}",
+            code, e, e
+        ));
+        code += 1;
+    }
+    join(errors, "  ")
+}
+
+/// The program IDL as pretty printed JSON.
+pub fn generate() -> String {
+    format!(
+        "{{\n  \"version\": \"{}\",\n  \"name\": \"hihi\",\n  \"encoding\": \"borsh\",\n  \"v2TagOffset\": {},\n  \"instructions\": {},\n  \"accounts\": {},\n  \"types\": {},\n  \"errors\": {}\n}}\n",
+        env!("CARGO_PKG_VERSION"),
+        V2_TAG_OFFSET,
+        instructions_json(),
+        accounts_json(),
+        types_json(),
+        errors_json()
+    )
+}
@@ -3,32 +3,122 @@ use std::convert::TryFrom;
 use std::convert::TryInto;
 
 use arrayref::array_ref;
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
     sysvar,
 };
+use std::io;
 use std::mem::size_of;
 
 pub const WORK_BYTES: usize = 57;
 
-#[derive(Clone, Debug, PartialEq)]
+/// Tags below this are the hand packed legacy instructions (0-5), tags from here up are
+/// borsh encoded: `V2_TAG_OFFSET + variant index` followed by the borsh fields.
+pub const V2_TAG_OFFSET: u8 = 16;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub struct Initialize {
     pub nonce: u8,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// `Initialize` with rewards halving every `halving_epochs` epochs instead of the breach
+/// count tiers.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct InitializeWithHalving {
+    pub nonce: u8,
+    pub halving_epochs: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub struct Breach {
     pub lamports: u64,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct BreachWithTokens {
+    pub breaches: u64,
+}
+
+/// Admin settings of an instance.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Configure {
+    /// Basis points of the breach tokens minted to the referrer.
+    pub referral_share: u16,
+    /// Epochs limit break rewards vest over, 0 mints them right away.
+    pub vesting_epochs: u16,
+    /// Token base units the program mints at most, 0 while uncapped.
+    pub max_supply: u64,
+    /// Breach price curve, see `processor::curve_price`. A 0 scale keeps the tiered growth.
+    pub price_scale: u32,
+    pub price_exponent: u32,
+    /// Basis points each breach in a window adds to the price, 0 keeps it fixed.
+    pub surge_bps: u16,
+    /// Basis points the price drops per slot without breaches, 0 keeps it fixed.
+    pub decay_bps: u16,
+    /// Lowest decayed price in basis points of the breach price.
+    pub decay_floor_bps: u16,
+}
+
+/// Claims puzzle `index` of the puzzle batch with `seed`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ClaimPuzzle {
+    pub seed: [u8; 32],
+    pub index: u8,
+}
+
+/// `Claim` of a solution hashed with a nonce, see `processor::nonce_claim_hash`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ClaimWithNonce {
+    pub claim: Claim,
+    pub nonce: u64,
+}
+
+/// `ClaimPuzzle` of a solution hashed with a nonce.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ClaimPuzzleWithNonce {
+    pub seed: [u8; 32],
+    pub index: u8,
+    pub nonce: u64,
+}
+
+/// `LimitBreak` of a solution hashed with a nonce.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct LimitBreakWithNonce {
+    pub nonce: u64,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Claim {
     pub work: [u8; WORK_BYTES],
 }
 
-#[derive(Debug, PartialEq)]
+//borsh only implements a few fixed array sizes, so the work bytes are written raw.
+impl BorshSerialize for Claim {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.work)
+    }
+}
+
+impl BorshDeserialize for Claim {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        if buf.len() < WORK_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unexpected length of input",
+            ));
+        }
+        let (work, rest) = buf.split_at(WORK_BYTES);
+        *buf = rest;
+        Ok(Self {
+            work: <[u8; WORK_BYTES]>::try_from(work).unwrap(),
+        })
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 /// All custom program instructions
 pub enum HihiInstruction {
     Initialize(Initialize),
@@ -37,15 +127,34 @@ pub enum HihiInstruction {
     Claim(Claim),
     Withdraw,
     ChangeKeys,
+    CreateMiner,
+    CreateReferrer,
+    Configure(Configure),
+    BreachWithTokens(BreachWithTokens),
+    CreateVesting,
+    ClaimVested,
+    CreatePage,
+    ClaimPuzzle(ClaimPuzzle),
+    ClaimWithNonce(ClaimWithNonce),
+    ClaimPuzzleWithNonce(ClaimPuzzleWithNonce),
+    LimitBreakWithNonce(LimitBreakWithNonce),
+    InitializeWithHalving(InitializeWithHalving),
+    Close,
+    InitializeCanonical(InitializeWithHalving),
+    Register,
+    Migrate,
 }
 
 impl HihiInstruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
         let (&tag, rest) = input.split_first().ok_or(HihiError::InvalidInstruction)?;
+        if tag >= V2_TAG_OFFSET {
+            return Self::unpack_v2(input);
+        }
         match tag {
             0 => {
-                let (nonce, _rest) = rest.split_at(1);
-                Ok(Self::Initialize(Initialize { nonce: nonce[0] }))
+                let (&nonce, _rest) = rest.split_first().ok_or(HihiError::InvalidInstruction)?;
+                Ok(Self::Initialize(Initialize { nonce }))
             }
             1 => {
                 let (lamports, _rest) = Self::unpack_u64(rest)?;
@@ -62,6 +171,13 @@ impl HihiInstruction {
         }
     }
 
+    //borsh rejects unknown variants, short input and trailing bytes.
+    fn unpack_v2(input: &[u8]) -> Result<Self, ProgramError> {
+        let mut data = input.to_vec();
+        data[0] -= V2_TAG_OFFSET;
+        Self::try_from_slice(&data).or(Err(HihiError::InvalidInstruction.into()))
+    }
+
     fn unpack_work(input: &[u8]) -> Result<([u8; WORK_BYTES], &[u8]), ProgramError> {
         if input.len() >= WORK_BYTES {
             let (work, rest) = input.split_at(WORK_BYTES);
@@ -88,7 +204,15 @@ impl HihiInstruction {
         }
     }
 
+    /// Packs into the borsh encoded v2 format.
     pub fn pack(&self) -> Vec<u8> {
+        let mut buf = self.try_to_vec().unwrap();
+        buf[0] += V2_TAG_OFFSET;
+        buf
+    }
+
+    /// Packs into the legacy format, `None` for instructions added after v2.
+    pub fn pack_legacy(&self) -> Option<Vec<u8>> {
         let mut buf = Vec::<u8>::with_capacity(size_of::<Self>());
         match &*self {
             Self::Initialize(Initialize { nonce }) => {
@@ -112,13 +236,22 @@ impl HihiInstruction {
             Self::ChangeKeys => {
                 buf.push(5);
             }
+            _ => return None,
         }
-        buf
+        Some(buf)
     }
 }
 
 //Instructions
 
+/// Absent optional accounts are passed as the program id.
+fn optional_account(program_id: &Pubkey, account_id: Option<&Pubkey>) -> AccountMeta {
+    match account_id {
+        Some(id) => AccountMeta::new(*id, false),
+        None => AccountMeta::new_readonly(*program_id, false),
+    }
+}
+
 /// Creates an 'initialize' instruction.
 pub fn initialize(
     program_id: &Pubkey,
@@ -140,6 +273,7 @@ pub fn initialize(
         AccountMeta::new_readonly(*admin_two_id, true),
         AccountMeta::new_readonly(*withdraw_id, true),
         AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
     ];
 
     Ok(Instruction {
@@ -159,11 +293,13 @@ pub fn breach(
     to_token: &Pubkey,
     from_id: &Pubkey,
     //to_lamps: &Pubkey,
+    referrer: Option<(&Pubkey, &Pubkey)>,
+    pages: &[Pubkey],
     lamports: u64,
 ) -> Result<Instruction, ProgramError> {
     let data = HihiInstruction::Breach(Breach { lamports }).pack();
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*instance_id, false),
         AccountMeta::new_readonly(*token_program_id, false),
         AccountMeta::new(*token_mint_id, false),
@@ -171,7 +307,11 @@ pub fn breach(
         AccountMeta::new(*from_id, true),
         AccountMeta::new(*to_token, false),
         AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        optional_account(program_id, referrer.map(|r| r.0)),
+        optional_account(program_id, referrer.map(|r| r.1)),
     ];
+    accounts.extend(pages.iter().map(|page| AccountMeta::new(*page, false)));
 
     Ok(Instruction {
         program_id: *program_id,
@@ -190,6 +330,8 @@ pub fn limit_break(
     to_lamports: &Pubkey,
     claim_key: &Pubkey,
     pool_key: &Pubkey,
+    miner_id: Option<&Pubkey>,
+    vesting_id: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
     let data = HihiInstruction::LimitBreak.pack();
 
@@ -203,6 +345,9 @@ pub fn limit_break(
         AccountMeta::new(*to_token, false),
         AccountMeta::new(*to_lamports, false),
         AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        optional_account(program_id, miner_id),
+        optional_account(program_id, vesting_id),
     ];
 
     Ok(Instruction {
@@ -221,6 +366,8 @@ pub fn claim(
     claim_pubkey: &Pubkey,
     pool_pubkey: &Pubkey,
     to_pubkey: &Pubkey,
+    miner_id: Option<&Pubkey>,
+    page_id: Option<&Pubkey>,
     work: [u8; WORK_BYTES],
 ) -> Result<Instruction, ProgramError> {
     let data = HihiInstruction::Claim(Claim { work }).pack();
@@ -233,6 +380,8 @@ pub fn claim(
         AccountMeta::new_readonly(*claim_pubkey, true),
         AccountMeta::new_readonly(*pool_pubkey, true),
         AccountMeta::new(*to_pubkey, false),
+        optional_account(program_id, miner_id),
+        optional_account(program_id, page_id),
     ];
 
     Ok(Instruction {
@@ -248,7 +397,7 @@ pub fn withdraw(
     authority_id: &Pubkey,
     withdraw_id: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = HihiInstruction::ChangeKeys.pack();
+    let data = HihiInstruction::Withdraw.pack();
 
     let accounts = vec![
         AccountMeta::new_readonly(*instance_id, false),
@@ -292,3 +441,478 @@ pub fn change_keys(
         data,
     })
 }
+
+/// Creates the statistics account of a miner's wallet, see `Processor::miner_id`.
+pub fn create_miner(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    miner_id: &Pubkey,
+    wallet_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::CreateMiner.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*instance_id, false),
+        AccountMeta::new(*miner_id, false),
+        AccountMeta::new(*wallet_id, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates the referral account of a wallet, see `Processor::referrer_id`.
+pub fn create_referrer(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    referrer_id: &Pubkey,
+    wallet_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::CreateReferrer.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*instance_id, false),
+        AccountMeta::new(*referrer_id, false),
+        AccountMeta::new(*wallet_id, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+pub fn configure(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    admin_one_key: &Pubkey,
+    admin_two_key: &Pubkey,
+    config: Configure,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::Configure(config).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new_readonly(*admin_one_key, true),
+        AccountMeta::new_readonly(*admin_two_key, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Pays for `breaches` breaches by burning HIHI from `from_token`, owned by `owner_id`.
+/// New puzzles that don't fit the instance heap go to `pages`.
+pub fn breach_with_tokens(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    token_program_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    owner_id: &Pubkey,
+    from_token: &Pubkey,
+    pages: &[Pubkey],
+    breaches: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::BreachWithTokens(BreachWithTokens { breaches }).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*token_mint_id, false),
+        AccountMeta::new_readonly(*owner_id, true),
+        AccountMeta::new(*from_token, false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+    ];
+    accounts.extend(pages.iter().map(|page| AccountMeta::new(*page, false)));
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates the vesting account of a wallet, see `Processor::vesting_id`.
+pub fn create_vesting(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    vesting_id: &Pubkey,
+    wallet_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::CreateVesting.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new(*vesting_id, false),
+        AccountMeta::new(*wallet_id, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Mints the vested limit break rewards of `wallet_id` to `to_token`.
+pub fn claim_vested(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    token_program_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    authority_id: &Pubkey,
+    vesting_id: &Pubkey,
+    wallet_id: &Pubkey,
+    to_token: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::ClaimVested.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*instance_id, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*token_mint_id, false),
+        AccountMeta::new_readonly(*authority_id, false),
+        AccountMeta::new(*vesting_id, false),
+        AccountMeta::new_readonly(*wallet_id, true),
+        AccountMeta::new(*to_token, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates the next work page of an instance, see `Processor::page_id`.
+pub fn create_page(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    page_id: &Pubkey,
+    payer_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::CreatePage.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new(*page_id, false),
+        AccountMeta::new(*payer_id, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Claims puzzle `index` of the puzzle batch with `seed`, see `processor::open_batch_puzzles`.
+pub fn claim_puzzle(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    token_program_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    authority_id: &Pubkey,
+    claim_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    to_pubkey: &Pubkey,
+    miner_id: Option<&Pubkey>,
+    seed: [u8; 32],
+    index: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::ClaimPuzzle(ClaimPuzzle { seed, index }).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*token_mint_id, false),
+        AccountMeta::new_readonly(*authority_id, false),
+        AccountMeta::new_readonly(*claim_pubkey, true),
+        AccountMeta::new_readonly(*pool_pubkey, true),
+        AccountMeta::new(*to_pubkey, false),
+        optional_account(program_id, miner_id),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// `limit_break` with the solution hashed as `sha || claim || pool || nonce`, so the claim
+/// key can be the miner's wallet.
+pub fn limit_break_with_nonce(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    token_program_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    authority_id: &Pubkey,
+    to_token: &Pubkey,
+    to_lamports: &Pubkey,
+    claim_key: &Pubkey,
+    pool_key: &Pubkey,
+    miner_id: Option<&Pubkey>,
+    vesting_id: Option<&Pubkey>,
+    nonce: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = limit_break(
+        program_id,
+        instance_id,
+        token_program_id,
+        token_mint_id,
+        authority_id,
+        to_token,
+        to_lamports,
+        claim_key,
+        pool_key,
+        miner_id,
+        vesting_id,
+    )?;
+    instruction.data = HihiInstruction::LimitBreakWithNonce(LimitBreakWithNonce { nonce }).pack();
+    Ok(instruction)
+}
+
+/// `claim` with the solution hashed as `sha || claim || pool || nonce`.
+pub fn claim_with_nonce(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    token_program_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    authority_id: &Pubkey,
+    claim_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    to_pubkey: &Pubkey,
+    miner_id: Option<&Pubkey>,
+    page_id: Option<&Pubkey>,
+    work: [u8; WORK_BYTES],
+    nonce: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = claim(
+        program_id,
+        instance_id,
+        token_program_id,
+        token_mint_id,
+        authority_id,
+        claim_pubkey,
+        pool_pubkey,
+        to_pubkey,
+        miner_id,
+        page_id,
+        work,
+    )?;
+    instruction.data = HihiInstruction::ClaimWithNonce(ClaimWithNonce {
+        claim: Claim { work },
+        nonce,
+    })
+    .pack();
+    Ok(instruction)
+}
+
+/// `claim_puzzle` with the solution hashed as `sha || claim || pool || nonce`.
+pub fn claim_puzzle_with_nonce(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    token_program_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    authority_id: &Pubkey,
+    claim_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    to_pubkey: &Pubkey,
+    miner_id: Option<&Pubkey>,
+    seed: [u8; 32],
+    index: u8,
+    nonce: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = claim_puzzle(
+        program_id,
+        instance_id,
+        token_program_id,
+        token_mint_id,
+        authority_id,
+        claim_pubkey,
+        pool_pubkey,
+        to_pubkey,
+        miner_id,
+        seed,
+        index,
+    )?;
+    instruction.data =
+        HihiInstruction::ClaimPuzzleWithNonce(ClaimPuzzleWithNonce { seed, index, nonce }).pack();
+    Ok(instruction)
+}
+
+/// `initialize` with the halving emission schedule.
+pub fn initialize_with_halving(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    initializer_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    admin_one_id: &Pubkey,
+    admin_two_id: &Pubkey,
+    withdraw_id: &Pubkey,
+    nonce: u8,
+    halving_epochs: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = initialize(
+        program_id,
+        instance_id,
+        initializer_id,
+        token_mint_id,
+        admin_one_id,
+        admin_two_id,
+        withdraw_id,
+        nonce,
+    )?;
+    instruction.data = HihiInstruction::InitializeWithHalving(InitializeWithHalving {
+        nonce,
+        halving_epochs,
+    })
+    .pack();
+    Ok(instruction)
+}
+
+/// Closes a finished instance, see `Processor::process_close`. All `pages` of the instance
+/// must be passed, `vestings` are the pairs of every vesting account and its token account.
+pub fn close(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    admin_one_key: &Pubkey,
+    admin_two_key: &Pubkey,
+    token_program_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    authority_id: &Pubkey,
+    new_mint_authority_id: &Pubkey,
+    withdraw_id: &Pubkey,
+    registry_id: &Pubkey,
+    pages: &[Pubkey],
+    vestings: &[(Pubkey, Pubkey)],
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::Close.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new_readonly(*admin_one_key, true),
+        AccountMeta::new_readonly(*admin_two_key, true),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*token_mint_id, false),
+        AccountMeta::new(*authority_id, false),
+        AccountMeta::new_readonly(*new_mint_authority_id, false),
+        AccountMeta::new(*withdraw_id, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new(*registry_id, false),
+    ];
+    accounts.extend(pages.iter().map(|page| AccountMeta::new(*page, false)));
+    for (vesting, to_token) in vestings {
+        accounts.push(AccountMeta::new(*vesting, false));
+        accounts.push(AccountMeta::new(*to_token, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeCanonical` instruction, `instance_id` is `Processor::instance_id` of
+/// the mint and `registry_id` is `Processor::registry_id`.
+pub fn initialize_canonical(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    initializer_id: &Pubkey,
+    token_mint_id: &Pubkey,
+    admin_one_id: &Pubkey,
+    admin_two_id: &Pubkey,
+    withdraw_id: &Pubkey,
+    registry_id: &Pubkey,
+    nonce: u8,
+    halving_epochs: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::InitializeCanonical(InitializeWithHalving {
+        nonce,
+        halving_epochs,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new(*initializer_id, true),
+        AccountMeta::new_readonly(*token_mint_id, false),
+        AccountMeta::new_readonly(*admin_one_id, true),
+        AccountMeta::new_readonly(*admin_two_id, true),
+        AccountMeta::new_readonly(*withdraw_id, true),
+        AccountMeta::new(*registry_id, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Register` instruction listing an existing instance in the registry.
+pub fn register(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    admin_one_key: &Pubkey,
+    admin_two_key: &Pubkey,
+    registry_id: &Pubkey,
+    payer_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::Register.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*instance_id, false),
+        AccountMeta::new_readonly(*admin_one_key, true),
+        AccountMeta::new_readonly(*admin_two_key, true),
+        AccountMeta::new(*registry_id, false),
+        AccountMeta::new(*payer_id, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `Migrate` instruction moving an instance of the first release to the current
+/// layout. `page_id`, page 0 of the instance, is needed when its heap holds more than
+/// `HEAP_COUNT` puzzles.
+pub fn migrate(
+    program_id: &Pubkey,
+    instance_id: &Pubkey,
+    payer_id: &Pubkey,
+    page_id: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = HihiInstruction::Migrate.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*instance_id, false),
+        AccountMeta::new(*payer_id, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        optional_account(program_id, page_id),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
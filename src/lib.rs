@@ -1,5 +1,7 @@
 pub mod state;
 pub mod error;
+pub mod accounts;
+pub mod idl;
 pub mod instruction;
 pub mod processor;
 pub use solana_program;
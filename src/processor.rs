@@ -1,30 +1,71 @@
 use crate::{
+    accounts::{
+        BreachAccounts, BreachWithTokensAccounts, ChangeKeysAccounts, ClaimAccounts, ClaimKind,
+        ClaimVestedAccounts, CloseAccounts, ConfigureAccounts, CreatePageAccounts,
+        InitializeAccounts, InitializeCanonicalAccounts, MigrateAccounts, RegisterAccounts,
+        WalletAccounts, WithdrawAccounts,
+    },
     error::HihiError,
-    instruction::{Breach, Claim, HihiInstruction, Initialize},
-    state::HihiState,
+    instruction::{
+        Breach, BreachWithTokens, Claim, ClaimPuzzle, ClaimPuzzleWithNonce, ClaimWithNonce,
+        Configure, HihiInstruction, Initialize, InitializeWithHalving, LimitBreakWithNonce,
+    },
+    state::{
+        HihiState, MinerState, PageState, PuzzleBatch, ReferrerState, RegistryState, VestingState,
+        HEAP_COUNT, INSTANCE_SEED, MAX_BATCH_PUZZLES, MINER_SEED, PAGE_SEED, REFERRER_SEED,
+        REGISTRY_SEED, SEED_BYTES, VESTING_SEED, WORK_BYTES,
+    },
 };
 
+use arrayref::array_ref;
+use std::cmp::min;
+
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::AccountInfo,
     entrypoint::ProgramResult,
     hash::hash,
+    instruction::Instruction,
     msg,
     native_token::{lamports_to_sol, sol_to_lamports},
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_memory::sol_memset,
     program_pack::Pack,
     pubkey::Pubkey,
-    sysvar::{clock::Clock, rent::Rent, Sysvar},
+    system_instruction,
+    sysvar::{self, clock::Clock, rent::Rent, Sysvar},
 };
 
-const LB_DIFF: u8 = 3;
-const MAX_DIFF: u8 = 23;
-const START_DIFF: u8 = 2;
-const BREACH_WINDOW: u16 = 100;
-const START_PRICE: u64 = 150000000;
-const LB_TOKEN_COUNT: u8 = 200;
-const LB_DIFF_INCREASE: u8 = 5;
-const LB_MAX_PER_EPOCH: u8 = 23;
+pub const LB_DIFF: u8 = 3;
+pub const MAX_DIFF: u8 = 23;
+pub const START_DIFF: u8 = 2;
+pub const BREACH_WINDOW: u16 = 100;
+pub const START_PRICE: u64 = 150000000;
+/// `curve_price` fitted to the tiers of `calculate_price`, within 2% at 1k, 10k and 100k
+/// breaches.
+pub const CURVE_SCALE: u32 = 86;
+pub const CURVE_EXPONENT: u32 = 865_245;
+/// Steepest allowed curve, `(1 + count / scale) ^ 4`.
+pub const MAX_CURVE_EXPONENT: u32 = 4_000_000;
+pub const LB_TOKEN_COUNT: u8 = 200;
+pub const LB_DIFF_INCREASE: u8 = 5;
+pub const LB_MAX_PER_EPOCH: u8 = 23;
+/// Most breaches a single transaction can pay for, a lamport breach is capped at ten times the price.
+pub const MAX_BREACHES: u64 = 10;
+/// Token base units burned per lamport of breach price.
+pub const TOKEN_BREACH_MULTIPLIER: u64 = 1000;
+/// Referral shares are in basis points of the breach tokens.
+pub const MAX_REFERRAL_SHARE: u16 = 10000;
+/// Surge is in basis points of the breach price per breach, at most doubling it each breach.
+pub const MAX_SURGE_BPS: u16 = 10000;
+/// Decay and its floor are in basis points of the breach price.
+pub const MAX_DECAY_BPS: u16 = 10000;
+
+/// What a claim solves: a stored puzzle, a puzzle of a batch or the limit break.
+enum Solution {
+    Work([u8; 57]),
+    Puzzle(ClaimPuzzle),
+}
 
 pub struct Processor {}
 impl Processor {
@@ -37,6 +78,72 @@ impl Processor {
             .or(Err(HihiError::InvalidProgramAddress))
     }
 
+    /// Canonical instance of a mint.
+    pub fn instance_id(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[INSTANCE_SEED, &token_mint.to_bytes()], program_id)
+    }
+
+    /// The registry listing every instance.
+    pub fn registry_id(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[REGISTRY_SEED], program_id)
+    }
+
+    /// Statistics account of a miner's wallet.
+    pub fn miner_id(program_id: &Pubkey, instance: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[MINER_SEED, &instance.to_bytes(), &wallet.to_bytes()],
+            program_id,
+        )
+    }
+
+    /// Referral account of a wallet.
+    pub fn referrer_id(program_id: &Pubkey, instance: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[REFERRER_SEED, &instance.to_bytes(), &wallet.to_bytes()],
+            program_id,
+        )
+    }
+
+    /// Vesting account of a wallet's limit break rewards.
+    pub fn vesting_id(program_id: &Pubkey, instance: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VESTING_SEED, &instance.to_bytes(), &wallet.to_bytes()],
+            program_id,
+        )
+    }
+
+    /// Work page `index` of an instance.
+    pub fn page_id(program_id: &Pubkey, instance: &Pubkey, index: u32) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[PAGE_SEED, &instance.to_bytes(), &index.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Issue a spl_token `SetAuthority` instruction handing the mint to `new_authority`.
+    pub fn token_set_mint_authority<'a>(
+        instance: &Pubkey,
+        token_program: AccountInfo<'a>,
+        mint: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        new_authority: &Pubkey,
+        nonce: u8,
+    ) -> Result<(), ProgramError> {
+        let instance_bytes = instance.to_bytes();
+        let authority_signature_seeds = [&instance_bytes[..32], &[nonce]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = spl_token::instruction::set_authority(
+            &spl_token::id(),
+            mint.key,
+            Some(new_authority),
+            spl_token::instruction::AuthorityType::MintTokens,
+            authority.key,
+            &[],
+        )?;
+
+        invoke_signed(&ix, &[mint, authority, token_program], signers)
+    }
+
     /// Issue a spl_token `MintTo` instruction.
     pub fn token_mint_to<'a>(
         instance: &Pubkey,
@@ -66,45 +173,45 @@ impl Processor {
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         nonce: &u8,
+        halving_epochs: u64,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let instance_info = next_account_info(account_info_iter)?;
-        let initializer_info = next_account_info(account_info_iter)?;
-        let token_mint_info = next_account_info(account_info_iter)?;
-        let admin_one_info = next_account_info(account_info_iter)?;
-        let admin_two_info = next_account_info(account_info_iter)?;
-        let withdraw_info = next_account_info(account_info_iter)?;
-
-        let initializer_id = Pubkey::new(&[
-            80, 97, 223, 1, 83, 109, 8, 147, 151, 40, 159, 3, 204, 231, 107, 20, 85, 34, 21, 236,
-            209, 141, 60, 7, 160, 185, 70, 160, 206, 226, 231, 158,
-        ]);
-
-        if instance_info.owner != program_id
-            || instance_info.is_signer == false
-            || initializer_info.is_signer == false
-            || initializer_info.key != &initializer_id
-            || admin_one_info.is_signer == false
-            || admin_two_info.is_signer == false
-            || withdraw_info.is_signer == false
-        {
-            return Err(HihiError::InvalidOwner.into());
-        }
-
-        let rent_info = next_account_info(account_info_iter)?;
-
-        let instance_data_len = instance_info.data_len();
-        let mut instance = HihiState::unpack_unchecked(&instance_info.data.borrow_mut())?;
+        let accounts = InitializeAccounts::parse(program_id, accounts)?;
 
-        if instance.is_initialized {
-            return Err(HihiError::AlreadyInitialized.into());
+        let rent = Rent::from_account_info(accounts.rent)?;
+
+        if !rent.is_exempt(accounts.instance.lamports(), accounts.instance.data_len()) {
+            return Err(HihiError::NotRentExempt.into());
         }
 
-        let rent = Rent::from_account_info(rent_info)?;
+        Self::start_instance(
+            accounts.instance,
+            accounts.token_mint,
+            accounts.admin_one,
+            accounts.admin_two,
+            accounts.withdraw,
+            accounts.slot_hashes,
+            *nonce,
+            halving_epochs,
+        )
+    }
+
+    /// Initialize a program owned instance account and its first limit break.
+    fn start_instance(
+        instance_info: &AccountInfo,
+        token_mint_info: &AccountInfo,
+        admin_one_info: &AccountInfo,
+        admin_two_info: &AccountInfo,
+        withdraw_info: &AccountInfo,
+        slot_hashes_info: &AccountInfo,
+        nonce: u8,
+        halving_epochs: u64,
+    ) -> ProgramResult {
+        let mut instance = HihiState::unpack_unchecked(&instance_info.data.borrow())?;
 
-        if !rent.is_exempt(instance_info.lamports(), instance_data_len) {
-            return Err(HihiError::NotRentExempt.into());
+        if instance.is_initialized {
+            return Err(HihiError::AlreadyInitialized.into());
         }
+
         instance.admin_one_id = *admin_one_info.key;
         instance.admin_two_id = *admin_two_info.key;
         instance.withdraw_id = *withdraw_info.key;
@@ -112,10 +219,15 @@ impl Processor {
         instance.token_mint_id = *token_mint_info.key;
 
         let clock = Clock::get()?;
+        let recent_hash = recent_slot_hash(slot_hashes_info)?;
 
         instance.current_slot = clock.slot;
         instance.current_epoch = clock.epoch;
-        instance.breach_price = calculate_price(instance.breach_count, START_PRICE);
+        instance.breach_price = current_price(&instance);
+
+        instance.halving_epochs = halving_epochs;
+        instance.start_epoch = clock.epoch;
+        instance.last_breach_slot = clock.slot;
 
         instance.difficulty = START_DIFF;
 
@@ -123,65 +235,139 @@ impl Processor {
             &clock,
             &instance,
             instance_info.key,
+            &recent_hash,
             LB_TOKEN_COUNT,
             instance.difficulty + LB_DIFF,
         );
 
-        instance.nonce = *nonce;
+        instance.nonce = nonce;
 
         instance.is_initialized = true;
         HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
         Ok(())
     }
 
-    pub fn process_breach(
+    /// Create and initialize the canonical instance of a mint. The mint authority must already
+    /// be the instance authority, so only the mint owner can take the address.
+    pub fn process_initialize_canonical(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
-        lamports: u64,
+        nonce: u8,
+        halving_epochs: u64,
     ) -> ProgramResult {
-        if lamports < 10000 {
-            return Err(HihiError::InsufficientFundsForTransaction.into());
-        }
+        let accounts = InitializeCanonicalAccounts::parse(program_id, accounts, nonce)?;
+
+        create_program_account(
+            program_id,
+            accounts.initializer,
+            accounts.instance,
+            accounts.system_program,
+            &[
+                INSTANCE_SEED,
+                &accounts.token_mint.key.to_bytes(),
+                &[accounts.instance_bump],
+            ],
+            HihiState::LEN,
+        )?;
 
-        let account_info_iter = &mut accounts.iter();
-        let instance_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
-        let token_mint_info = next_account_info(account_info_iter)?;
-        let authority_info = next_account_info(account_info_iter)?;
-        let from_info = next_account_info(account_info_iter)?;
-        let to_token_info = next_account_info(account_info_iter)?;
-        let system_program_info = next_account_info(account_info_iter)?;
+        Self::register(
+            program_id,
+            accounts.registry,
+            accounts.registry_bump,
+            accounts.initializer,
+            accounts.system_program,
+            accounts.instance.key,
+        )?;
 
-        if instance_info.owner != program_id || instance_info.is_writable == false {
-            return Err(HihiError::InvalidOwner.into());
-        }
+        Self::start_instance(
+            accounts.instance,
+            accounts.token_mint,
+            accounts.admin_one,
+            accounts.admin_two,
+            accounts.withdraw,
+            accounts.slot_hashes,
+            nonce,
+            halving_epochs,
+        )
+    }
 
-        if from_info.is_signer == false || from_info.is_writable == false {
-            return Err(HihiError::InvalidInstruction.into());
-        }
+    /// List an instance that predates canonical instances in the registry, approved by its
+    /// admins.
+    pub fn process_register(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = RegisterAccounts::parse(program_id, accounts)?;
+
+        Self::register(
+            program_id,
+            accounts.registry,
+            accounts.registry_bump,
+            accounts.payer,
+            accounts.system_program,
+            accounts.instance.key,
+        )
+    }
 
-        let mut instance = HihiState::unpack_unchecked(&instance_info.data.borrow_mut())?;
+    /// Add an instance to the registry at `bump`, creating the registry on first use.
+    fn register<'a>(
+        program_id: &Pubkey,
+        registry_info: &AccountInfo<'a>,
+        bump: u8,
+        payer_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        instance_id: &Pubkey,
+    ) -> ProgramResult {
+        let mut registry = if registry_info.owner == program_id {
+            RegistryState::unpack(&registry_info.data.borrow())?
+        } else {
+            create_program_account(
+                program_id,
+                payer_info,
+                registry_info,
+                system_program_info,
+                &[REGISTRY_SEED, &[bump]],
+                RegistryState::LEN,
+            )?;
+            RegistryState {
+                is_initialized: true,
+                bump,
+                instances: Vec::new(),
+            }
+        };
 
-        if instance.is_initialized == false {
-            return Err(HihiError::NotInitialized.into());
-        }
+        registry.add(instance_id)?;
+        RegistryState::pack(registry, &mut registry_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-        let id = Self::authority_id(program_id, instance_info.key, instance.nonce)?;
+    pub fn process_breach(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        lamports: u64,
+    ) -> ProgramResult {
+        if lamports < 10000 {
+            return Err(HihiError::BreachTooSmall.into());
+        }
 
-        if &id != authority_info.key {
-            return Err(HihiError::InvalidOwner.into());
+        let accounts = BreachAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let token_program_info = accounts.token_program;
+        let token_mint_info = accounts.token_mint;
+        let authority_info = accounts.authority;
+        let from_info = accounts.from;
+        let to_token_info = accounts.to_token;
+        let system_program_info = accounts.system_program;
+        let slot_hashes_info = accounts.slot_hashes;
+        let (page_infos, mut pages): (Vec<&AccountInfo>, Vec<PageState>) =
+            accounts.pages.into_iter().unzip();
+        let mut instance = accounts.state;
+
+        if instance.is_supply_exhausted() {
+            return Err(HihiError::SupplyExhausted.into());
         }
 
         let mut breaches: u64 = 0;
-        let mut b_tokens = instance.breach_count
-            - instance.breach_count_this_window as i32
-            - instance.work_cached as i32;
-        if b_tokens < 0 {
-            b_tokens = 0;
-        }
-        let base_tokens = calculate_tokens(b_tokens);
 
         let clock = Clock::get()?;
+        let base_tokens = breach_base_tokens(&instance, clock.epoch);
 
         if instance.difficulty + LB_DIFF <= MAX_DIFF {
             //Transfer Lamports.
@@ -208,54 +394,184 @@ impl Processor {
                 to_token_info,
             )?;
 
-            if clock.slot - instance.current_slot >= BREACH_WINDOW as u64 {
-                instance.breach_count_this_window = 0;
-                instance.current_slot = clock.slot;
-                instance.breach_price = calculate_price(instance.breach_count, START_PRICE);
-            }
+            update_window(&mut instance, &clock);
 
-            if lamports > instance.breach_price * 10 {
-                return Err(HihiError::InsufficientFundsForTransaction.into());
+            if lamports > breaches_cost(&instance, clock.slot, MAX_BREACHES) {
+                return Err(HihiError::BreachTooLarge.into());
             }
 
-            let sol = lamports_to_sol(lamports);
-            let bp_sol = lamports_to_sol(instance.breach_price);
-            let br_sol = lamports_to_sol(instance.breach_remain);
-            let result: f64 = (sol + br_sol) / bp_sol;
+            breaches = buy_breaches(&mut instance, clock.slot, lamports);
 
-            let sf = split_float(result);
-            breaches = sf.0;
-            instance.breach_remain = sol_to_lamports(bp_sol * sf.1);
+            let tokens_to_send = base_tokens as u64 * breaches;
 
             if valid_to_id == true {
-                let tokens_to_send = base_tokens as u64 * breaches;
-
-                Self::token_mint_to(
-                    instance_info.key,
-                    token_program_info.clone(),
-                    token_mint_info.clone(),
-                    to_token_info.clone(),
-                    authority_info.clone(),
-                    instance.nonce,
-                    sol_to_lamports(tokens_to_send as f64),
-                )?;
+                let amount = instance.take_supply(sol_to_lamports(tokens_to_send as f64));
+                if amount > 0 {
+                    Self::token_mint_to(
+                        instance_info.key,
+                        token_program_info.clone(),
+                        token_mint_info.clone(),
+                        to_token_info.clone(),
+                        authority_info.clone(),
+                        instance.nonce,
+                        amount,
+                    )?;
+                }
             } else {
                 instance.token_doubles += breaches;
             }
 
-            //change hash of limit break.
-            if breaches > 0 {
-                instance.limit_break = create_limit_break(
-                    &clock,
-                    &instance,
-                    instance_info.key,
-                    LB_TOKEN_COUNT,
-                    instance.difficulty + LB_DIFF,
-                );
+            //`BreachAccounts` requires the token account of a referrer.
+            if let (Some((referrer_info, referrer)), Some(referrer_token_info)) =
+                (accounts.referrer, accounts.referrer_token)
+            {
+                Self::pay_referrer(
+                    &mut instance,
+                    instance_info,
+                    token_program_info,
+                    token_mint_info,
+                    authority_info,
+                    from_info.key,
+                    referrer_info,
+                    referrer,
+                    referrer_token_info,
+                    breaches,
+                    sol_to_lamports(tokens_to_send as f64),
+                )?;
             }
         }
 
-        let free = instance.get_work_free_space();
+        Self::add_breaches(
+            &mut instance,
+            &mut pages,
+            &clock,
+            instance_info.key,
+            &recent_slot_hash(slot_hashes_info)?,
+            breaches,
+            lamports,
+            base_tokens,
+        )?;
+
+        Self::store_pages(pages, &page_infos)?;
+        HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    pub fn process_breach_with_tokens(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        breaches: u64,
+    ) -> ProgramResult {
+        if breaches == 0 {
+            return Err(HihiError::BreachTooSmall.into());
+        }
+        if breaches > MAX_BREACHES {
+            return Err(HihiError::BreachTooLarge.into());
+        }
+
+        let accounts = BreachWithTokensAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let token_program_info = accounts.token_program;
+        let token_mint_info = accounts.token_mint;
+        let owner_info = accounts.owner;
+        let from_token_info = accounts.from_token;
+        let slot_hashes_info = accounts.slot_hashes;
+        let (page_infos, mut pages): (Vec<&AccountInfo>, Vec<PageState>) =
+            accounts.pages.into_iter().unzip();
+        let mut instance = accounts.state;
+
+        if instance.is_supply_exhausted() {
+            return Err(HihiError::SupplyExhausted.into());
+        }
+
+        let valid_from_id = check_accounts(
+            &instance,
+            token_program_info.key,
+            token_mint_info.key,
+            from_token_info,
+        )?;
+
+        if valid_from_id == false {
+            return Err(HihiError::InvalidTokenAddress.into());
+        }
+
+        if instance.difficulty + LB_DIFF > MAX_DIFF {
+            return Err(HihiError::GameOver.into());
+        }
+
+        let clock = Clock::get()?;
+        let base_tokens = breach_base_tokens(&instance, clock.epoch);
+
+        update_window(&mut instance, &clock);
+
+        let amount = token_breach_price(breaches_cost(&instance, clock.slot, breaches))?;
+        let ix = spl_token::instruction::burn(
+            &spl_token::id(),
+            from_token_info.key,
+            token_mint_info.key,
+            owner_info.key,
+            &[],
+            amount,
+        )?;
+        invoke(
+            &ix,
+            &[
+                from_token_info.clone(),
+                token_mint_info.clone(),
+                owner_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        Self::add_breaches(
+            &mut instance,
+            &mut pages,
+            &clock,
+            instance_info.key,
+            &recent_slot_hash(slot_hashes_info)?,
+            breaches,
+            amount,
+            base_tokens,
+        )?;
+
+        Self::store_pages(pages, &page_infos)?;
+        HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Record paid breaches: new limit break hash, a puzzle batch or, once the batches are
+    /// full, puzzles for the heap and then the pages (topped up from the cache, overflow
+    /// cached) and the breach counters.
+    pub fn add_breaches(
+        instance: &mut HihiState,
+        pages: &mut [PageState],
+        clock: &Clock,
+        instance_id: &Pubkey,
+        recent_hash: &[u8; 32],
+        breaches: u64,
+        paid: u64,
+        base_tokens: u8,
+    ) -> ProgramResult {
+        //change hash of limit break.
+        if breaches > 0 {
+            instance.limit_break = create_limit_break(
+                clock,
+                instance,
+                instance_id,
+                recent_hash,
+                LB_TOKEN_COUNT,
+                instance.difficulty + LB_DIFF,
+            );
+        }
+
+        let batch_free = instance.get_batch_free_space() > 0;
+        let mut free = instance.get_work_free_space();
+        for page in pages.iter() {
+            free += page.get_work_free_space();
+        }
+        if batch_free {
+            free = MAX_BATCH_PUZZLES as i32;
+        }
 
         if free != 0 {
             let mut count = 0;
@@ -275,35 +591,75 @@ impl Processor {
                 count = count - remain as u64;
             }
 
-            let work = create_hash_puzzles(
-                &clock,
-                count as u8,
-                &instance,
-                instance_info.key,
-                lamports,
-                base_tokens,
-                instance.difficulty,
-            );
+            if batch_free && count > 0 {
+                let doubled = min(instance.token_doubles, count);
+                let batch = PuzzleBatch {
+                    seed: puzzle_seed(clock, instance, instance_id, recent_hash, paid),
+                    count: count as u8,
+                    reward: base_tokens,
+                    doubled: doubled as u8,
+                    difficulty: instance.difficulty,
+                    claimed: 0,
+                };
+                instance.batches.push(batch);
+                instance.token_doubles -= doubled;
+            } else {
+                let work = create_hash_puzzles(
+                    clock,
+                    count as u8,
+                    instance,
+                    instance_id,
+                    recent_hash,
+                    paid,
+                    base_tokens,
+                    instance.difficulty,
+                );
 
-            instance.add_work(work.0.as_slice())?;
-            instance.token_doubles = work.1;
+                let mut rest = work.0.as_slice();
+                let take = min(
+                    instance.get_work_free_space() as usize * WORK_BYTES,
+                    rest.len(),
+                );
+                instance.add_work(&rest[..take])?;
+                rest = &rest[take..];
+                for page in pages.iter_mut() {
+                    let take = min(page.get_work_free_space() as usize * WORK_BYTES, rest.len());
+                    page.add_work(&rest[..take])?;
+                    rest = &rest[take..];
+                }
+                instance.token_doubles = work.1;
+            }
         } else {
             instance.work_cached += breaches;
         }
 
-        if instance.breach_count + breaches as i32 > i32::MAX {
-            return Err(HihiError::InvalidInstruction.into());
-        }
-
-        instance.breach_count += breaches as i32;
+        instance.breach_count = instance
+            .breach_count
+            .checked_add(breaches as i32)
+            .ok_or(HihiError::BreachCountOverflow)?;
         instance.breach_count_this_window += breaches as u32;
+        if breaches > 0 {
+            instance.last_breach_slot = clock.slot;
+        }
+        Ok(())
+    }
 
-        HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
+    pub fn process_limit_break(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        nonce: Option<u64>,
+    ) -> ProgramResult {
+        Self::process_claim_and_breaks(program_id, accounts, None, nonce)?;
         Ok(())
     }
 
-    pub fn process_limit_break(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        Self::process_claim_and_breaks(program_id, accounts, None)?;
+    pub fn process_claim_puzzle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        claim: ClaimPuzzle,
+        nonce: Option<u64>,
+    ) -> ProgramResult {
+        Self::process_claim_and_breaks(program_id, accounts, Some(Solution::Puzzle(claim)), nonce)?;
         Ok(())
     }
 
@@ -311,37 +667,36 @@ impl Processor {
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         work: [u8; 57],
+        nonce: Option<u64>,
     ) -> ProgramResult {
-        Self::process_claim_and_breaks(program_id, accounts, Some(work))?;
+        Self::process_claim_and_breaks(program_id, accounts, Some(Solution::Work(work)), nonce)?;
         Ok(())
     }
 
+    //`nonce` selects the `sha || claim || pool || nonce` solution format.
     fn process_claim_and_breaks(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
-        work: Option<[u8; 57]>,
+        solution: Option<Solution>,
+        nonce: Option<u64>,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let instance_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
-        let token_mint_info = next_account_info(account_info_iter)?;
-        let authority_info = next_account_info(account_info_iter)?;
-        let claim_info = next_account_info(account_info_iter)?;
-        let pool_info = next_account_info(account_info_iter)?;
-        let to_token_info = next_account_info(account_info_iter)?;
-
-        if instance_info.owner != program_id
-            || instance_info.is_writable == false
-            || claim_info.is_signer == false
-            || pool_info.is_signer == false
-        {
-            return Err(HihiError::InvalidOwner.into());
-        }
-
-        let mut instance = HihiState::unpack_unchecked(&instance_info.data.borrow_mut())?;
-
-        if instance.is_initialized == false {
-            return Err(HihiError::NotInitialized.into());
+        let kind = match &solution {
+            Some(Solution::Work(_)) => ClaimKind::Work,
+            Some(Solution::Puzzle(_)) => ClaimKind::Puzzle,
+            None => ClaimKind::LimitBreak,
+        };
+        let accounts = ClaimAccounts::parse(program_id, accounts, kind)?;
+        let instance_info = accounts.instance;
+        let token_program_info = accounts.token_program;
+        let token_mint_info = accounts.token_mint;
+        let authority_info = accounts.authority;
+        let claim_info = accounts.claim;
+        let pool_info = accounts.pool;
+        let to_token_info = accounts.to_token;
+        let mut instance = accounts.state;
+
+        if instance.is_supply_exhausted() {
+            return Err(HihiError::SupplyExhausted.into());
         }
 
         let valid_to_id = check_accounts(
@@ -355,22 +710,26 @@ impl Processor {
             return Err(HihiError::InvalidTokenAddress.into());
         }
 
-        if let Some(work) = work {
-            let work_vec = work.to_vec();
-
-            let mut index = -1;
-
-            for (i, wrk) in instance.work.iter().enumerate() {
-                if wrk.iter().eq(work_vec.iter()) {
-                    index = i as i32;
-                    break;
+        let clock = Clock::get()?;
+        let minted: u64;
+        let solved_difficulty: u8;
+
+        if let Some(solution) = &solution {
+            let work = match solution {
+                Solution::Work(work) => {
+                    Self::take_work(accounts.page, &mut instance, work)?;
+                    work.to_vec()
                 }
-            }
+                Solution::Puzzle(ClaimPuzzle { seed, index }) => {
+                    Self::take_puzzle(&mut instance, seed, *index)?
+                }
+            };
 
-            if index == -1 {
-                return Err(HihiError::InvalidClaimHash.into());
-            }
-            check_claim(claim_info.key, pool_info.key, &work)?;
+            check_solution(claim_info.key, pool_info.key, &work, nonce)?;
+
+            //the last reward before the cap is cut to what is left.
+            minted = instance.take_supply(sol_to_lamports(work[0] as f64));
+            solved_difficulty = work[33];
 
             Self::token_mint_to(
                 instance_info.key,
@@ -379,193 +738,589 @@ impl Processor {
                 to_token_info.clone(),
                 authority_info.clone(),
                 instance.nonce,
-                sol_to_lamports(work[0] as f64),
+                minted,
             )?;
-
-            //remove work from heap.
-            instance.remove_work(index as usize)?;
         } else {
-            //limit break
-            let to_lamports_info = next_account_info(account_info_iter)?;
-            let system_program_info = next_account_info(account_info_iter)?;
+            //limit break, `ClaimKind::LimitBreak` parsed these.
+            let to_lamports_info = accounts.to_lamports.unwrap();
+            let system_program_info = accounts.system_program.unwrap();
+            let slot_hashes_info = accounts.slot_hashes.unwrap();
 
             if instance.difficulty + LB_DIFF > MAX_DIFF {
-                return Err(HihiError::InvalidInstruction.into());
+                return Err(HihiError::GameOver.into());
             }
 
-            check_claim(
+            check_solution(
                 claim_info.key,
                 pool_info.key,
                 instance.limit_break.as_slice(),
+                nonce,
             )?;
 
             let count: u8 = LB_TOKEN_COUNT / calculate_tokens(instance.breach_count);
-            if instance.breach_count + count as i32 > i32::MAX {
-                return Err(HihiError::InvalidInstruction.into());
-            }
 
-            Self::token_mint_to(
-                instance_info.key,
-                token_program_info.clone(),
-                token_mint_info.clone(),
-                to_token_info.clone(),
-                authority_info.clone(),
-                instance.nonce,
-                sol_to_lamports(instance.limit_break[0] as f64),
-            )?;
-            let clock = Clock::get()?;
-
-            if clock.slot - instance.current_slot >= BREACH_WINDOW as u64 {
-                instance.breach_count_this_window = 0;
-                instance.current_slot = clock.slot;
-                instance.breach_price = calculate_price(instance.breach_count, START_PRICE);
-            }
-
-            //for testing use slots for epochs instead of epochs
-            //if clock.slot - instance.current_epoch >= 200 {
-            if clock.epoch - instance.current_epoch > 0 {
-                if instance.limit_breaks_this_epoch > LB_DIFF_INCREASE as u32 {
-                    instance.difficulty += 1;
-                    if instance.difficulty + LB_DIFF > MAX_DIFF {
-                        let account = authority_info.lamports();
-                        send_lamports(
-                            account,
-                            instance_info.key,
-                            instance.nonce,
-                            authority_info,
-                            to_lamports_info,
-                            system_program_info,
-                        )?;
-                        instance.lamports = 0;
-                    } else {
-                        let amount = instance.lamports * 5 / 100;
-                        send_lamports(
-                            amount,
-                            instance_info.key,
-                            instance.nonce,
-                            authority_info,
-                            to_lamports_info,
-                            system_program_info,
-                        )?;
-                        instance.lamports = instance.lamports - amount;
-                    }
-                }
-                instance.limit_breaks_this_epoch = 0;
-                instance.current_epoch = clock.epoch;
-            }
+            minted = instance.take_supply(sol_to_lamports(instance.limit_break[0] as f64));
+            solved_difficulty = instance.limit_break[33];
 
-            if instance.limit_breaks_this_epoch > LB_MAX_PER_EPOCH as u32 {
-                return Err(HihiError::WorkLimitExceeded.into());
+            //vesting rewards are added to the vesting account below.
+            if instance.vesting_epochs == 0 {
+                Self::token_mint_to(
+                    instance_info.key,
+                    token_program_info.clone(),
+                    token_mint_info.clone(),
+                    to_token_info.clone(),
+                    authority_info.clone(),
+                    instance.nonce,
+                    minted,
+                )?;
             }
 
-            instance.breach_count += count as i32;
-            instance.breach_count_this_window += count as u32;
-
-            instance.limit_breaks_this_epoch += 1;
+            update_window(&mut instance, &clock);
+
+            if let Some(released) = roll_epoch(&mut instance, clock.epoch) {
+                //the whole authority balance goes once the game is over.
+                let amount = if instance.difficulty + LB_DIFF > MAX_DIFF {
+                    authority_info.lamports()
+                } else {
+                    released
+                };
+                send_lamports(
+                    amount,
+                    instance_info.key,
+                    instance.nonce,
+                    authority_info,
+                    to_lamports_info,
+                    system_program_info,
+                )?;
+            }
 
-            instance.limit_count += 1;
+            record_limit_break(&mut instance, count)?;
 
             if instance.difficulty + LB_DIFF <= MAX_DIFF {
                 instance.limit_break = create_limit_break(
                     &clock,
                     &instance,
                     instance_info.key,
+                    &recent_slot_hash(slot_hashes_info)?,
                     LB_TOKEN_COUNT,
                     instance.difficulty + LB_DIFF,
                 );
             }
         }
 
+        if let Some((miner_info, mut miner)) = accounts.miner {
+            miner.record(clock.slot, minted, solved_difficulty, solution.is_none());
+            MinerState::pack(miner, &mut miner_info.data.borrow_mut())?;
+        }
+
+        if solution.is_none() && instance.vesting_epochs > 0 {
+            //`ClaimKind::LimitBreak` requires the vesting account once rewards vest.
+            let (vesting_info, mut vesting) = accounts.vesting.unwrap();
+            vesting.grant(minted, clock.epoch, instance.vesting_epochs as u64);
+            VestingState::pack(vesting, &mut vesting_info.data.borrow_mut())?;
+        }
+
         HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
         Ok(())
     }
 
-    pub fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let instance_info = next_account_info(account_info_iter)?;
-        let authority_info = next_account_info(account_info_iter)?;
-        let withdraw_info = next_account_info(account_info_iter)?;
-        let system_program_info = next_account_info(account_info_iter)?;
+    /// Remove a stored puzzle from the heap, or from the page account if one is passed.
+    fn take_work(
+        page: Option<(&AccountInfo, PageState)>,
+        instance: &mut HihiState,
+        work: &[u8; 57],
+    ) -> ProgramResult {
+        let (page_info, mut page) = match page {
+            Some((page_info, page)) => (Some(page_info), Some(page)),
+            None => (None, None),
+        };
+        let heap = match &page {
+            Some(page) => &page.work,
+            None => &instance.work,
+        };
+
+        let work_vec = work.to_vec();
+
+        let mut index = -1;
+
+        for (i, wrk) in heap.iter().enumerate() {
+            if wrk.iter().eq(work_vec.iter()) {
+                index = i as i32;
+                break;
+            }
+        }
 
-        let instance = HihiState::unpack_unchecked(&instance_info.data.borrow())?;
+        if index == -1 {
+            return Err(HihiError::InvalidClaimHash.into());
+        }
 
-        if instance_info.owner != program_id
-            || withdraw_info.key != &instance.withdraw_id
-            || withdraw_info.is_signer == false
-        {
-            return Err(HihiError::InvalidOwner.into());
+        //remove work from heap.
+        match page.as_mut() {
+            Some(page) => page.remove_work(index as usize)?,
+            None => instance.remove_work(index as usize)?,
         }
+        if let (Some(page), Some(page_info)) = (page, page_info) {
+            PageState::pack(page, &mut page_info.data.borrow_mut())?;
+        }
+        Ok(())
+    }
 
-        let account = authority_info.lamports();
-        if account <= instance.lamports {
-            return Err(HihiError::InsufficientFundsForTransaction.into());
+    /// Mark puzzle `index` of the batch with `seed` claimed and return the puzzle.
+    /// Spent batches are removed.
+    pub fn take_puzzle(
+        instance: &mut HihiState,
+        seed: &[u8; SEED_BYTES],
+        index: u8,
+    ) -> Result<Vec<u8>, ProgramError> {
+        let position = instance
+            .find_batch(seed)
+            .ok_or(HihiError::InvalidClaimHash)?;
+        let batch = &mut instance.batches[position];
+
+        if index >= batch.count {
+            return Err(HihiError::InvalidPuzzleIndex.into());
+        }
+        if batch.is_claimed(index) {
+            return Err(HihiError::PuzzleAlreadyClaimed.into());
         }
-        let amount = account - instance.lamports;
 
-        send_lamports(
-            amount,
-            instance_info.key,
-            instance.nonce,
-            authority_info,
-            withdraw_info,
-            system_program_info,
-        )?;
+        let work = batch_puzzle(batch, index);
+        batch.set_claimed(index);
 
-        HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
+        if batch.open_count() == 0 {
+            instance.batches.swap_remove(position);
+        }
+        Ok(work)
+    }
+
+    fn store_pages(pages: Vec<PageState>, page_infos: &[&AccountInfo]) -> ProgramResult {
+        for (page, page_info) in pages.into_iter().zip(page_infos) {
+            PageState::pack(page, &mut page_info.data.borrow_mut())?;
+        }
         Ok(())
     }
 
-    pub fn process_change_keys(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let instance_info = next_account_info(account_info_iter)?;
-        let admin_one_info = next_account_info(account_info_iter)?;
-        let admin_two_info = next_account_info(account_info_iter)?;
-        let withdraw_info = next_account_info(account_info_iter)?;
-        let new_admin_one_info = next_account_info(account_info_iter)?;
-        let new_admin_two_info = next_account_info(account_info_iter)?;
-        let new_withdraw_info = next_account_info(account_info_iter)?;
+    /// Create the `[seed, instance, wallet]` account of a wallet, paid for by the wallet.
+    fn create_wallet_account(
+        program_id: &Pubkey,
+        accounts: &WalletAccounts,
+        seed: &[u8],
+        space: usize,
+    ) -> ProgramResult {
+        create_program_account(
+            program_id,
+            accounts.wallet,
+            accounts.new,
+            accounts.system_program,
+            &[
+                seed,
+                &accounts.instance.key.to_bytes(),
+                &accounts.wallet.key.to_bytes(),
+                &[accounts.bump],
+            ],
+            space,
+        )
+    }
 
-        if instance_info.owner != program_id || instance_info.is_writable == false {
-            return Err(HihiError::InvalidOwner.into());
-        }
+    pub fn process_create_miner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = WalletAccounts::parse(program_id, accounts, MINER_SEED)?;
+        Self::create_wallet_account(program_id, &accounts, MINER_SEED, MinerState::LEN)?;
 
-        if admin_one_info.is_signer == false
-            || admin_two_info.is_signer == false
-            || withdraw_info.is_signer == false
-            || new_admin_one_info.is_signer == false
-            || new_admin_two_info.is_signer == false
-            || new_withdraw_info.is_signer == false
-        {
-            return Err(HihiError::InvalidOwner.into());
-        }
+        let miner = MinerState {
+            is_initialized: true,
+            instance_id: *accounts.instance.key,
+            wallet_id: *accounts.wallet.key,
+            bump: accounts.bump,
+            ..MinerState::default()
+        };
+
+        MinerState::pack(miner, &mut accounts.new.data.borrow_mut())?;
+        Ok(())
+    }
 
-        let mut instance = HihiState::unpack_unchecked(&instance_info.data.borrow_mut())?;
+    pub fn process_create_referrer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = WalletAccounts::parse(program_id, accounts, REFERRER_SEED)?;
+        Self::create_wallet_account(program_id, &accounts, REFERRER_SEED, ReferrerState::LEN)?;
 
-        if instance.is_initialized == false {
-            return Err(HihiError::NotInitialized.into());
-        }
+        let referrer = ReferrerState {
+            is_initialized: true,
+            instance_id: *accounts.instance.key,
+            wallet_id: *accounts.wallet.key,
+            bump: accounts.bump,
+            ..ReferrerState::default()
+        };
 
-        if admin_one_info.key != &instance.admin_one_id
-            || admin_two_info.key != &instance.admin_two_id
-            || withdraw_info.key != &instance.withdraw_id
-        {
-            return Err(HihiError::InvalidOwner.into());
-        }
+        ReferrerState::pack(referrer, &mut accounts.new.data.borrow_mut())?;
+        Ok(())
+    }
 
-        instance.admin_one_id = *new_admin_one_info.key;
-        instance.admin_two_id = *new_admin_two_info.key;
-        instance.withdraw_id = *new_withdraw_info.key;
+    pub fn process_create_page(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = CreatePageAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let page_info = accounts.page;
+        let payer_info = accounts.payer;
+        let system_program_info = accounts.system_program;
+        let bump = accounts.bump;
+        let mut instance = accounts.state;
+        let index = instance.page_count;
+
+        //the next page address is known to everyone, lamports sent to it must not block breaches.
+        create_program_account(
+            program_id,
+            payer_info,
+            page_info,
+            system_program_info,
+            &[
+                PAGE_SEED,
+                &instance_info.key.to_bytes(),
+                &index.to_le_bytes(),
+                &[bump],
+            ],
+            PageState::LEN,
+        )?;
+
+        let page = PageState {
+            is_initialized: true,
+            instance_id: *instance_info.key,
+            index,
+            bump,
+            work: Vec::new(),
+        };
+        PageState::pack(page, &mut page_info.data.borrow_mut())?;
+
+        instance.page_count += 1;
 
         HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
         Ok(())
     }
 
-    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-        let instruction = HihiInstruction::unpack(input)?;
-        match instruction {
-            HihiInstruction::Initialize(Initialize { nonce }) => {
+    pub fn process_create_vesting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = WalletAccounts::parse(program_id, accounts, VESTING_SEED)?;
+        Self::create_wallet_account(program_id, &accounts, VESTING_SEED, VestingState::LEN)?;
+
+        let vesting = VestingState {
+            is_initialized: true,
+            instance_id: *accounts.instance.key,
+            wallet_id: *accounts.wallet.key,
+            bump: accounts.bump,
+            ..VestingState::default()
+        };
+
+        VestingState::pack(vesting, &mut accounts.new.data.borrow_mut())?;
+
+        let mut instance = accounts.state;
+        instance.vesting_count = instance
+            .vesting_count
+            .checked_add(1)
+            .ok_or(HihiError::VestingCountOverflow)?;
+        HihiState::pack(instance, &mut accounts.instance.data.borrow_mut())?;
+        Ok(())
+    }
+
+    pub fn process_claim_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = ClaimVestedAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let token_program_info = accounts.token_program;
+        let token_mint_info = accounts.token_mint;
+        let authority_info = accounts.authority;
+        let vesting_info = accounts.vesting;
+        let to_token_info = accounts.to_token;
+        let instance = accounts.state;
+        let mut vesting = accounts.vesting_state;
+
+        let valid_to_id = check_accounts(
+            &instance,
+            token_program_info.key,
+            token_mint_info.key,
+            to_token_info,
+        )?;
+        if valid_to_id == false {
+            return Err(HihiError::InvalidTokenAddress.into());
+        }
+
+        let clock = Clock::get()?;
+        let amount = vesting.release(clock.epoch);
+        if amount == 0 {
+            return Err(HihiError::NothingVested.into());
+        }
+
+        Self::token_mint_to(
+            instance_info.key,
+            token_program_info.clone(),
+            token_mint_info.clone(),
+            to_token_info.clone(),
+            authority_info.clone(),
+            instance.nonce,
+            amount,
+        )?;
+
+        VestingState::pack(vesting, &mut vesting_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Mint the referral share of a breach to the referrer and add it to their totals.
+    pub fn pay_referrer<'a>(
+        instance: &mut HihiState,
+        instance_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        token_mint_info: &AccountInfo<'a>,
+        authority_info: &AccountInfo<'a>,
+        payer_id: &Pubkey,
+        referrer_info: &AccountInfo<'a>,
+        mut referrer: ReferrerState,
+        referrer_token_info: &AccountInfo<'a>,
+        breaches: u64,
+        tokens: u64,
+    ) -> ProgramResult {
+        if &referrer.wallet_id == payer_id {
+            return Err(HihiError::SelfReferral.into());
+        }
+
+        let valid_to_id = check_accounts(
+            instance,
+            token_program_info.key,
+            token_mint_info.key,
+            referrer_token_info,
+        )?;
+        if valid_to_id == false {
+            return Err(HihiError::InvalidTokenAddress.into());
+        }
+        let to_account =
+            spl_token::state::Account::unpack_unchecked(&referrer_token_info.data.borrow())?;
+        if to_account.owner != referrer.wallet_id {
+            return Err(HihiError::InvalidReferrerAccount.into());
+        }
+
+        let amount = instance.take_supply(referral_tokens(instance.referral_share, tokens));
+        if amount > 0 {
+            Self::token_mint_to(
+                instance_info.key,
+                token_program_info.clone(),
+                token_mint_info.clone(),
+                referrer_token_info.clone(),
+                authority_info.clone(),
+                instance.nonce,
+                amount,
+            )?;
+        }
+
+        referrer.referred_breaches += breaches;
+        referrer.tokens_earned += amount;
+
+        ReferrerState::pack(referrer, &mut referrer_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    pub fn process_configure(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        config: Configure,
+    ) -> ProgramResult {
+        let accounts = ConfigureAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let mut instance = accounts.state;
+
+        if config.referral_share > MAX_REFERRAL_SHARE {
+            return Err(HihiError::InvalidReferralShare.into());
+        }
+        check_max_supply(&instance, config.max_supply)?;
+        check_price_curve(config.price_scale, config.price_exponent)?;
+        if config.surge_bps > MAX_SURGE_BPS {
+            return Err(HihiError::InvalidSurge.into());
+        }
+        check_decay(config.decay_bps, config.decay_floor_bps)?;
+        //a new decay starts now, not at a breach from long ago.
+        if instance.decay_bps == 0 && config.decay_bps > 0 {
+            instance.last_breach_slot = Clock::get()?.slot;
+        }
+        instance.referral_share = config.referral_share;
+        instance.vesting_epochs = config.vesting_epochs;
+        instance.max_supply = config.max_supply;
+        instance.price_scale = config.price_scale;
+        instance.price_exponent = config.price_exponent;
+        instance.surge_bps = config.surge_bps;
+        instance.decay_bps = config.decay_bps;
+        instance.decay_floor_bps = config.decay_floor_bps;
+        //a new curve applies right away.
+        instance.breach_price = current_price(&instance);
+
+        HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Close a finished instance: vest and mint what the passed vesting accounts still hold,
+    /// hand the mint to `new_mint_authority` and send the authority balance and the rent of
+    /// the instance and its pages to the withdraw key.
+    pub fn process_close(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = CloseAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let token_program_info = accounts.token_program;
+        let token_mint_info = accounts.token_mint;
+        let authority_info = accounts.authority;
+        let withdraw_info = accounts.withdraw;
+        let system_program_info = accounts.system_program;
+        let instance = accounts.state;
+
+        let (page_infos, pages): (Vec<&AccountInfo>, Vec<PageState>) =
+            accounts.pages.into_iter().unzip();
+        if !is_closable(&instance, &pages) {
+            return Err(HihiError::InstanceStillActive.into());
+        }
+
+        //rewards of breaches still waiting for puzzles are left unminted.
+        for (vesting_info, to_token_info, mut vesting) in accounts.vestings {
+            if check_accounts(
+                &instance,
+                token_program_info.key,
+                token_mint_info.key,
+                to_token_info,
+            )? == false
+            {
+                return Err(HihiError::InvalidTokenAddress.into());
+            }
+
+            //the schedule ends with the instance.
+            let amount = vesting.release(u64::MAX);
+            if amount > 0 {
+                Self::token_mint_to(
+                    instance_info.key,
+                    token_program_info.clone(),
+                    token_mint_info.clone(),
+                    (*to_token_info).clone(),
+                    authority_info.clone(),
+                    instance.nonce,
+                    amount,
+                )?;
+            }
+            VestingState::pack(vesting, &mut vesting_info.data.borrow_mut())?;
+        }
+
+        Self::token_set_mint_authority(
+            instance_info.key,
+            token_program_info.clone(),
+            token_mint_info.clone(),
+            authority_info.clone(),
+            accounts.new_mint_authority.key,
+            instance.nonce,
+        )?;
+
+        //the miner reserve goes too, no one can earn it any more.
+        let amount = authority_info.lamports();
+        if amount > 0 {
+            send_lamports(
+                amount,
+                instance_info.key,
+                instance.nonce,
+                authority_info,
+                withdraw_info,
+                system_program_info,
+            )?;
+        }
+
+        if accounts.registry.owner == program_id {
+            let mut registry = RegistryState::unpack(&accounts.registry.data.borrow())?;
+            if registry.remove(instance_info.key) {
+                RegistryState::pack(registry, &mut accounts.registry.data.borrow_mut())?;
+            }
+        }
+
+        for page_info in page_infos {
+            close_account(page_info, withdraw_info);
+        }
+        close_account(instance_info, withdraw_info);
+        Ok(())
+    }
+
+    /// Rewrite an instance of the first release in the current layout. Puzzles that don't fit
+    /// the smaller heap move to page 0, created here.
+    pub fn process_migrate(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = MigrateAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let mut instance = accounts.state;
+
+        if instance.work.len() > HEAP_COUNT {
+            let page_info = accounts.page.ok_or(HihiError::InvalidPageAccount)?;
+            create_program_account(
+                program_id,
+                accounts.payer,
+                page_info,
+                accounts.system_program,
+                &[
+                    PAGE_SEED,
+                    &instance_info.key.to_bytes(),
+                    &0u32.to_le_bytes(),
+                    &[accounts.page_bump],
+                ],
+                PageState::LEN,
+            )?;
+
+            let page = PageState {
+                is_initialized: true,
+                instance_id: *instance_info.key,
+                index: 0,
+                bump: accounts.page_bump,
+                work: instance.work.split_off(HEAP_COUNT),
+            };
+            PageState::pack(page, &mut page_info.data.borrow_mut())?;
+            instance.page_count = 1;
+        }
+
+        HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    pub fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = WithdrawAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let authority_info = accounts.authority;
+        let instance = accounts.state;
+
+        let account = authority_info.lamports();
+        if account <= instance.lamports {
+            return Err(HihiError::InsufficientFundsForTransaction.into());
+        }
+        let amount = account - instance.lamports;
+
+        send_lamports(
+            amount,
+            instance_info.key,
+            instance.nonce,
+            authority_info,
+            accounts.withdraw,
+            accounts.system_program,
+        )?;
+        Ok(())
+    }
+
+    pub fn process_change_keys(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts = ChangeKeysAccounts::parse(program_id, accounts)?;
+        let instance_info = accounts.instance;
+        let new_admin_one_info = accounts.new_admin_one;
+        let new_admin_two_info = accounts.new_admin_two;
+        let new_withdraw_info = accounts.new_withdraw;
+        let mut instance = accounts.state;
+
+        instance.admin_one_id = *new_admin_one_info.key;
+        instance.admin_two_id = *new_admin_two_info.key;
+        instance.withdraw_id = *new_withdraw_info.key;
+
+        HihiState::pack(instance, &mut instance_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        let instruction = HihiInstruction::unpack(input)?;
+        match instruction {
+            HihiInstruction::Initialize(Initialize { nonce }) => {
                 msg!("Instruction: Initialize");
-                return Self::process_initialize(program_id, accounts, &nonce);
+                return Self::process_initialize(program_id, accounts, &nonce, 0);
+            }
+            HihiInstruction::InitializeWithHalving(InitializeWithHalving {
+                nonce,
+                halving_epochs,
+            }) => {
+                msg!("Instruction: Initialize With Halving");
+                return Self::process_initialize(program_id, accounts, &nonce, halving_epochs);
             }
             HihiInstruction::Breach(Breach { lamports }) => {
                 msg!("Instruction: Breach");
@@ -573,11 +1328,11 @@ impl Processor {
             }
             HihiInstruction::LimitBreak => {
                 msg!("Instruction: Limit Break");
-                return Self::process_limit_break(program_id, accounts);
+                return Self::process_limit_break(program_id, accounts, None);
             }
             HihiInstruction::Claim(Claim { work }) => {
                 msg!("Instruction: Claim");
-                return Self::process_claim(program_id, accounts, work);
+                return Self::process_claim(program_id, accounts, work, None);
             }
             HihiInstruction::Withdraw => {
                 msg!("Instruction: Withdraw");
@@ -587,10 +1342,165 @@ impl Processor {
                 msg!("Instruction: Change Keys");
                 return Self::process_change_keys(program_id, accounts);
             }
+            HihiInstruction::CreateMiner => {
+                msg!("Instruction: Create Miner");
+                return Self::process_create_miner(program_id, accounts);
+            }
+            HihiInstruction::CreateReferrer => {
+                msg!("Instruction: Create Referrer");
+                return Self::process_create_referrer(program_id, accounts);
+            }
+            HihiInstruction::BreachWithTokens(BreachWithTokens { breaches }) => {
+                msg!("Instruction: Breach With Tokens");
+                return Self::process_breach_with_tokens(program_id, accounts, breaches);
+            }
+            HihiInstruction::CreateVesting => {
+                msg!("Instruction: Create Vesting");
+                return Self::process_create_vesting(program_id, accounts);
+            }
+            HihiInstruction::ClaimVested => {
+                msg!("Instruction: Claim Vested");
+                return Self::process_claim_vested(program_id, accounts);
+            }
+            HihiInstruction::CreatePage => {
+                msg!("Instruction: Create Page");
+                return Self::process_create_page(program_id, accounts);
+            }
+            HihiInstruction::ClaimPuzzle(claim) => {
+                msg!("Instruction: Claim Puzzle");
+                return Self::process_claim_puzzle(program_id, accounts, claim, None);
+            }
+            HihiInstruction::ClaimWithNonce(ClaimWithNonce {
+                claim: Claim { work },
+                nonce,
+            }) => {
+                msg!("Instruction: Claim With Nonce");
+                return Self::process_claim(program_id, accounts, work, Some(nonce));
+            }
+            HihiInstruction::ClaimPuzzleWithNonce(ClaimPuzzleWithNonce { seed, index, nonce }) => {
+                msg!("Instruction: Claim Puzzle With Nonce");
+                return Self::process_claim_puzzle(
+                    program_id,
+                    accounts,
+                    ClaimPuzzle { seed, index },
+                    Some(nonce),
+                );
+            }
+            HihiInstruction::LimitBreakWithNonce(LimitBreakWithNonce { nonce }) => {
+                msg!("Instruction: Limit Break With Nonce");
+                return Self::process_limit_break(program_id, accounts, Some(nonce));
+            }
+            HihiInstruction::Configure(config) => {
+                msg!("Instruction: Configure");
+                return Self::process_configure(program_id, accounts, config);
+            }
+            HihiInstruction::Close => {
+                msg!("Instruction: Close");
+                return Self::process_close(program_id, accounts);
+            }
+            HihiInstruction::InitializeCanonical(InitializeWithHalving {
+                nonce,
+                halving_epochs,
+            }) => {
+                msg!("Instruction: Initialize Canonical");
+                return Self::process_initialize_canonical(
+                    program_id,
+                    accounts,
+                    nonce,
+                    halving_epochs,
+                );
+            }
+            HihiInstruction::Register => {
+                msg!("Instruction: Register");
+                return Self::process_register(program_id, accounts);
+            }
+            HihiInstruction::Migrate => {
+                msg!("Instruction: Migrate");
+                return Self::process_migrate(program_id, accounts);
+            }
         }
     }
 }
 
+/// Optional accounts are passed as the program id when absent, or left off the end.
+pub fn next_optional_account<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    iter: &mut I,
+    program_id: &Pubkey,
+) -> Option<&'a AccountInfo<'b>> {
+    match iter.next() {
+        Some(info) if info.key != program_id => Some(info),
+        _ => None,
+    }
+}
+
+/// Create a rent exempt account owned by the program at a program address. Lamports sent to
+/// the address beforehand don't block the creation.
+pub fn create_program_account<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    new_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    seeds: &[&[u8]],
+    space: usize,
+) -> ProgramResult {
+    let rent = Rent::get()?;
+    let instructions = create_program_account_instructions(
+        program_id,
+        payer_info.key,
+        new_info.key,
+        new_info.lamports(),
+        rent.minimum_balance(space),
+        space,
+    );
+
+    for ix in instructions {
+        invoke_signed(
+            &ix,
+            &[
+                payer_info.clone(),
+                new_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[seeds],
+        )?;
+    }
+    Ok(())
+}
+
+/// System instructions creating the account at `new_id`. `create_account` fails once the
+/// address holds lamports, so a funded address is topped up to `minimum_balance`, allocated
+/// and assigned instead.
+pub fn create_program_account_instructions(
+    program_id: &Pubkey,
+    payer_id: &Pubkey,
+    new_id: &Pubkey,
+    lamports: u64,
+    minimum_balance: u64,
+    space: usize,
+) -> Vec<Instruction> {
+    if lamports == 0 {
+        return vec![system_instruction::create_account(
+            payer_id,
+            new_id,
+            minimum_balance,
+            space as u64,
+            program_id,
+        )];
+    }
+
+    let mut instructions = Vec::with_capacity(3);
+    if lamports < minimum_balance {
+        instructions.push(system_instruction::transfer(
+            payer_id,
+            new_id,
+            minimum_balance - lamports,
+        ));
+    }
+    instructions.push(system_instruction::allocate(new_id, space as u64));
+    instructions.push(system_instruction::assign(new_id, program_id));
+    instructions
+}
+
 pub fn send_lamports<'a>(
     amount: u64,
     instance_id: &Pubkey,
@@ -618,10 +1528,47 @@ pub fn send_lamports<'a>(
     Ok(())
 }
 
+/// Move all lamports of a program owned account to `to_info` and clear its data.
+pub fn close_account(account_info: &AccountInfo, to_info: &AccountInfo) {
+    let lamports = account_info.lamports();
+    **to_info.lamports.borrow_mut() += lamports;
+    **account_info.lamports.borrow_mut() = 0;
+    let mut data = account_info.data.borrow_mut();
+    let len = data.len();
+    sol_memset(&mut data, 0, len);
+}
+
+/// An instance can close once the game is over, or when no puzzle is left to claim in its
+/// heap, cache, batches or any of its `pages`.
+pub fn is_closable(instance: &HihiState, pages: &[PageState]) -> bool {
+    if instance.difficulty + LB_DIFF > MAX_DIFF {
+        return true;
+    }
+    instance.work.is_empty()
+        && instance.work_cached == 0
+        && instance.batches.iter().all(|b| b.open_count() == 0)
+        && pages.iter().all(|p| p.work.is_empty())
+}
+
+/// Most recent entry of the `SlotHashes` sysvar, mixed into puzzle seeds so they can't be
+/// known before the transaction that makes them lands.
+pub fn recent_slot_hash(slot_hashes_info: &AccountInfo) -> Result<[u8; 32], ProgramError> {
+    if slot_hashes_info.key != &sysvar::slot_hashes::id() {
+        return Err(HihiError::InvalidSlotHashes.into());
+    }
+    //the sysvar is too large to deserialize, read the entry count and first (slot, hash).
+    let data = slot_hashes_info.data.borrow();
+    if data.len() < 8 + 8 + 32 || u64::from_le_bytes(*array_ref![data, 0, 8]) == 0 {
+        return Err(HihiError::InvalidSlotHashes.into());
+    }
+    Ok(*array_ref![data, 16, 32])
+}
+
 pub fn create_limit_break(
     clock: &Clock,
     instance: &HihiState,
     instance_id: &Pubkey,
+    recent_hash: &[u8; 32],
     claimable_tokens: u8,
     magic_len: u8,
 ) -> Vec<u8> {
@@ -634,6 +1581,7 @@ pub fn create_limit_break(
     data_vec.extend_from_slice(&clock.slot.to_le_bytes());
     data_vec.extend_from_slice(&clock.epoch.to_le_bytes());
     data_vec.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
+    data_vec.extend_from_slice(recent_hash);
     out_vec.push(claimable_tokens);
     out_vec.extend_from_slice(&hash(data_vec.as_slice()).to_bytes());
     out_vec.extend_from_slice(&[
@@ -642,18 +1590,14 @@ pub fn create_limit_break(
     return out_vec;
 }
 
-//magic len must be below < 17
-pub fn create_hash_puzzles(
+/// Root of the hash chain of a breach's puzzles.
+pub fn puzzle_seed(
     clock: &Clock,
-    count: u8,
     instance: &HihiState,
     instance_id: &Pubkey,
+    recent_hash: &[u8; 32],
     lamports_paid: u64,
-    claimable_tokens: u8,
-    magic_len: u8,
-) -> (Vec<u8>, u64) {
-    let mut out_vec = Vec::<u8>::new();
-    let mut doubles = instance.token_doubles;
+) -> [u8; SEED_BYTES] {
     let mut data_vec = instance_id.to_bytes().to_vec();
     data_vec.extend_from_slice(&instance.token_mint_id.to_bytes());
     data_vec.extend_from_slice(&lamports_paid.to_le_bytes());
@@ -662,23 +1606,283 @@ pub fn create_hash_puzzles(
     data_vec.extend_from_slice(&clock.slot.to_le_bytes());
     data_vec.extend_from_slice(&clock.epoch.to_le_bytes());
     data_vec.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
-    let mut hash_vec = hash(data_vec.as_slice()).to_bytes().to_vec();
+    data_vec.extend_from_slice(recent_hash);
+    hash(data_vec.as_slice()).to_bytes()
+}
+
+/// Puzzle `index` of the `count` puzzle hash chain from `seed`. Puzzles are made from
+/// `count - 1` down to 0, each hashing the seed and the indices made so far.
+pub fn derive_puzzle(
+    seed: &[u8; SEED_BYTES],
+    count: u8,
+    index: u8,
+    claimable_tokens: u8,
+    magic_len: u8,
+) -> Vec<u8> {
+    let mut hash_vec = seed.to_vec();
+    hash_vec.extend((index..count).rev());
+    let mut out_vec = vec![claimable_tokens];
+    out_vec.extend_from_slice(&hash(hash_vec.as_slice()).to_bytes());
+    out_vec.extend_from_slice(&[
+        magic_len, 33, 232, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ]);
+    out_vec
+}
+
+/// The stored form of puzzle `index` of a batch, as `Claim` takes it.
+pub fn batch_puzzle(batch: &PuzzleBatch, index: u8) -> Vec<u8> {
+    derive_puzzle(
+        &batch.seed,
+        batch.count,
+        index,
+        batch.reward_of(index),
+        batch.difficulty,
+    )
+}
+
+/// Index and puzzle of every open puzzle of a batch, for miners.
+pub fn open_batch_puzzles(batch: &PuzzleBatch) -> Vec<(u8, Vec<u8>)> {
+    (0..batch.count)
+        .filter(|index| batch.is_claimed(*index) == false)
+        .map(|index| (index, batch_puzzle(batch, index)))
+        .collect()
+}
+
+//magic len must be below < 17
+pub fn create_hash_puzzles(
+    clock: &Clock,
+    count: u8,
+    instance: &HihiState,
+    instance_id: &Pubkey,
+    recent_hash: &[u8; 32],
+    lamports_paid: u64,
+    claimable_tokens: u8,
+    magic_len: u8,
+) -> (Vec<u8>, u64) {
+    let mut out_vec = Vec::<u8>::new();
+    let mut doubles = instance.token_doubles;
+    let seed = puzzle_seed(clock, instance, instance_id, recent_hash, lamports_paid);
     for i in (0..count).rev() {
-        hash_vec.push(i);
+        let mut tokens = claimable_tokens;
         if doubles > 0 {
-            out_vec.push(claimable_tokens * 2);
+            tokens = claimable_tokens * 2;
             doubles = doubles - 1;
-        } else {
-            out_vec.push(claimable_tokens);
         }
-        out_vec.extend_from_slice(&hash(hash_vec.as_slice()).to_bytes());
-        out_vec.extend_from_slice(&[
-            magic_len, 33, 232, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]);
+        out_vec.extend_from_slice(&derive_puzzle(&seed, count, i, tokens, magic_len));
     }
     return (out_vec, doubles);
 }
 
+/// Start a new breach window and reprice once `BREACH_WINDOW` slots have passed.
+pub fn update_window(instance: &mut HihiState, clock: &Clock) {
+    if clock.slot - instance.current_slot >= BREACH_WINDOW as u64 {
+        instance.breach_count_this_window = 0;
+        instance.current_slot = clock.slot;
+        instance.breach_price = current_price(instance);
+    }
+}
+
+/// Whole breaches bought by `lamports` and the carried remainder, at most `MAX_BREACHES`,
+/// the new remainder is kept.
+pub fn buy_breaches(instance: &mut HihiState, slot: u64, lamports: u64) -> u64 {
+    if instance.surge_bps > 0 {
+        //one at a time, each costs more than the last.
+        let mut funds = lamports.saturating_add(instance.breach_remain);
+        let mut bought = 0;
+        loop {
+            let price = surge_price(instance, slot, bought);
+            if bought == MAX_BREACHES || price == 0 || price > funds {
+                break;
+            }
+            funds -= price;
+            bought += 1;
+        }
+        instance.breach_remain = funds;
+        return bought;
+    }
+    let price = decayed_price(instance, slot);
+    let sol = lamports_to_sol(lamports);
+    let bp_sol = lamports_to_sol(price);
+    let br_sol = lamports_to_sol(instance.breach_remain);
+    let result: f64 = (sol + br_sol) / bp_sol;
+
+    let sf = split_float(result);
+    if sf.0 > MAX_BREACHES {
+        //the remainder was carried at an older, higher price, keep what the cap leaves over.
+        let funds = lamports.saturating_add(instance.breach_remain);
+        instance.breach_remain = funds.saturating_sub(price.saturating_mul(MAX_BREACHES));
+        return MAX_BREACHES;
+    }
+    instance.breach_remain = sol_to_lamports(bp_sol * sf.1);
+    sf.0
+}
+
+/// Start a new epoch on the first limit break after an epoch change. More than
+/// `LB_DIFF_INCREASE` limit breaks in the past epoch raise the difficulty and release 5% of
+/// the reserve, or all of it once the game is over. Returns the released lamports.
+pub fn roll_epoch(instance: &mut HihiState, epoch: u64) -> Option<u64> {
+    let mut released = None;
+    //for testing use slots for epochs instead of epochs
+    //if clock.slot - instance.current_epoch >= 200 {
+    if epoch - instance.current_epoch > 0 {
+        if instance.limit_breaks_this_epoch > LB_DIFF_INCREASE as u32 {
+            instance.difficulty += 1;
+            let amount = if instance.difficulty + LB_DIFF > MAX_DIFF {
+                instance.lamports
+            } else {
+                instance.lamports * 5 / 100
+            };
+            instance.lamports -= amount;
+            released = Some(amount);
+        }
+        instance.limit_breaks_this_epoch = 0;
+        instance.current_epoch = epoch;
+    }
+    released
+}
+
+/// Count a limit break and the `count` breaches it is worth.
+pub fn record_limit_break(instance: &mut HihiState, count: u8) -> ProgramResult {
+    if instance.limit_breaks_this_epoch > LB_MAX_PER_EPOCH as u32 {
+        return Err(HihiError::LimitBreaksExhausted.into());
+    }
+
+    instance.breach_count = instance
+        .breach_count
+        .checked_add(count as i32)
+        .ok_or(HihiError::BreachCountOverflow)?;
+    instance.breach_count_this_window += count as u32;
+
+    instance.limit_breaks_this_epoch += 1;
+
+    instance.limit_count += 1;
+    Ok(())
+}
+
+/// A max supply can be set once and then only lowered, never below what is already minted.
+pub fn check_max_supply(instance: &HihiState, max_supply: u64) -> ProgramResult {
+    if max_supply == instance.max_supply {
+        return Ok(());
+    }
+    if max_supply == 0
+        || max_supply < instance.minted
+        || (instance.max_supply != 0 && max_supply > instance.max_supply)
+    {
+        return Err(HihiError::InvalidMaxSupply.into());
+    }
+    Ok(())
+}
+
+/// Puzzle reward of new breaches, priced on breaches that already have puzzles.
+pub fn breach_base_tokens(instance: &HihiState, epoch: u64) -> u8 {
+    if instance.halving_epochs > 0 {
+        return halving_tokens(
+            epoch.saturating_sub(instance.start_epoch),
+            instance.halving_epochs,
+        );
+    }
+    let mut b_tokens = instance.breach_count
+        - instance.breach_count_this_window as i32
+        - instance.work_cached as i32;
+    if b_tokens < 0 {
+        b_tokens = 0;
+    }
+    calculate_tokens(b_tokens)
+}
+
+/// Reward `epochs` after the start when it halves every `halving_epochs`, 0 after 7 halvings.
+pub fn halving_tokens(epochs: u64, halving_epochs: u64) -> u8 {
+    let halvings = epochs / halving_epochs.max(1);
+    if halvings >= 8 {
+        return 0;
+    }
+    calculate_tokens(0) >> halvings
+}
+
+/// Whole tokens minted in each of the next `epochs` epochs with `breaches_per_epoch`
+/// breaches, starting at `breach_count` and `epoch` epochs after initialize. Every breach
+/// mints its reward to the breacher and once more to the puzzle solver. `halving_epochs` of
+/// 0 projects the breach count tiers.
+pub fn project_emission(
+    halving_epochs: u64,
+    breach_count: i32,
+    epoch: u64,
+    breaches_per_epoch: u64,
+    epochs: u64,
+) -> Vec<u64> {
+    let mut count = breach_count;
+    (epoch..epoch + epochs)
+        .map(|e| {
+            let tokens = if halving_epochs > 0 {
+                breaches_per_epoch * halving_tokens(e, halving_epochs) as u64
+            } else {
+                //the tier can change within an epoch.
+                let mut tokens = 0;
+                for _ in 0..breaches_per_epoch {
+                    tokens += calculate_tokens(count) as u64;
+                    count = count.saturating_add(1);
+                }
+                tokens
+            };
+            tokens * 2
+        })
+        .collect()
+}
+
+/// `breach_price` at `slot`, dropping `decay_bps` per slot since the last breach down to
+/// `decay_floor_bps` of it.
+pub fn decayed_price(instance: &HihiState, slot: u64) -> u64 {
+    if instance.decay_bps == 0 {
+        return instance.breach_price;
+    }
+    let slots = slot.saturating_sub(instance.last_breach_slot) as u128;
+    let drop = min(instance.decay_bps as u128 * slots, MAX_DECAY_BPS as u128);
+    let bps = (MAX_DECAY_BPS as u128 - drop).max(instance.decay_floor_bps as u128);
+    (instance.breach_price as u128 * bps / MAX_DECAY_BPS as u128) as u64
+}
+
+/// Price of a breach at `slot` after `extra` more in this window, the decayed price raised by
+/// `surge_bps` for every breach already in the window.
+pub fn surge_price(instance: &HihiState, slot: u64, extra: u64) -> u64 {
+    let price = decayed_price(instance, slot);
+    if instance.surge_bps == 0 {
+        return price;
+    }
+    let breaches = instance.breach_count_this_window as u128 + extra as u128;
+    let bps = MAX_SURGE_BPS as u128 + instance.surge_bps as u128 * breaches;
+    min(
+        price as u128 * bps / MAX_SURGE_BPS as u128,
+        u64::MAX as u128,
+    ) as u64
+}
+
+/// Lamports the next `breaches` breaches of this window cost together at `slot`.
+pub fn breaches_cost(instance: &HihiState, slot: u64, breaches: u64) -> u64 {
+    (0..breaches).fold(0u64, |cost, extra| {
+        cost.saturating_add(surge_price(instance, slot, extra))
+    })
+}
+
+/// A decay needs a floor above 0 so the price never reaches nothing.
+pub fn check_decay(decay_bps: u16, floor_bps: u16) -> ProgramResult {
+    if decay_bps > MAX_DECAY_BPS || floor_bps > MAX_DECAY_BPS || (decay_bps > 0 && floor_bps == 0) {
+        return Err(HihiError::InvalidDecay.into());
+    }
+    Ok(())
+}
+
+/// Share of `tokens` minted to the referrer at `referral_share` basis points.
+pub fn referral_tokens(referral_share: u16, tokens: u64) -> u64 {
+    (tokens as u128 * referral_share as u128 / MAX_REFERRAL_SHARE as u128) as u64
+}
+
+/// HIHI base units burned for one breach by `BreachWithTokens`.
+pub fn token_breach_price(breach_price: u64) -> Result<u64, HihiError> {
+    breach_price
+        .checked_mul(TOKEN_BREACH_MULTIPLIER)
+        .ok_or(HihiError::TokenPriceOverflow)
+}
+
 pub fn calculate_tokens(count: i32) -> u8 {
     if count < 1000 {
         return 100;
@@ -691,6 +1895,107 @@ pub fn calculate_tokens(count: i32) -> u8 {
     }
 }
 
+/// Breach price at the instance's breach count, on its curve when one is configured.
+pub fn current_price(instance: &HihiState) -> u64 {
+    if instance.price_scale == 0 {
+        return calculate_price(instance.breach_count, START_PRICE);
+    }
+    curve_price(
+        instance.breach_count,
+        START_PRICE,
+        instance.price_scale,
+        instance.price_exponent,
+    )
+}
+
+/// A curve can be turned off with a 0 scale, otherwise its exponent must be in bounds.
+pub fn check_price_curve(scale: u32, exponent: u32) -> ProgramResult {
+    if scale > 0 && (exponent == 0 || exponent > MAX_CURVE_EXPONENT) {
+        return Err(HihiError::InvalidPriceCurve.into());
+    }
+    Ok(())
+}
+
+/// `start_price * (1 + count / scale) ^ (exponent / 1e6)`, saturating at `u64::MAX`.
+/// Evaluated in fixed point so it is cheap on-chain and never decreases with `count`.
+pub fn curve_price(count: i32, start_price: u64, scale: u32, exponent: u32) -> u64 {
+    let scale = scale.max(1) as u64;
+    let log = log2_q32(scale + count.max(0) as u64) - log2_q32(scale);
+    let power = log as u128 * exponent as u128 / 1_000_000;
+    let int = (power >> 32) as u32;
+    if int >= 64 {
+        return u64::MAX;
+    }
+    let price = (start_price as u128 * exp2_frac_q62(power as u32)) >> 62;
+    let price = price << int;
+    if price > u64::MAX as u128 {
+        return u64::MAX;
+    }
+    price as u64
+}
+
+//2^(2^-j) for j = 1..=32, in Q62.
+const EXP2_Q62: [u64; 32] = [
+    6521908912666391106,
+    5484249825272419511,
+    5029079263719320435,
+    4815862801830788490,
+    4712668792719003883,
+    4661903986662671289,
+    4636727017470743990,
+    4624189567668517720,
+    4617933561212708776,
+    4614808732577250068,
+    4613247111281068008,
+    4612466498810092974,
+    4612076242109103707,
+    4611881126141011236,
+    4611783571252412753,
+    4611734794581956353,
+    4611710406440186475,
+    4611698212417665819,
+    4611692115418496524,
+    4611689066921934630,
+    4611687542674409371,
+    4611686780550835663,
+    4611686399489096040,
+    4611686208958238036,
+    4611686113692811986,
+    4611686066060099699,
+    4611686042243743740,
+    4611686030335565806,
+    4611686024381476851,
+    4611686021404432376,
+    4611686019915910140,
+    4611686019171649022,
+];
+
+//log2 of `n` (at least 1) in Q32, by repeated squaring of the mantissa.
+fn log2_q32(n: u64) -> u64 {
+    let int = 63 - n.leading_zeros() as u64;
+    let mut mantissa = ((n as u128) << 62) >> int;
+    let mut log = int << 32;
+    for bit in (0..32).rev() {
+        mantissa = (mantissa * mantissa) >> 62;
+        if mantissa >= 1 << 63 {
+            mantissa >>= 1;
+            log |= 1 << bit;
+        }
+    }
+    log
+}
+
+//2^(frac / 2^32) in Q62.
+fn exp2_frac_q62(frac: u32) -> u128 {
+    let mut result: u128 = 1 << 62;
+    for (j, factor) in EXP2_Q62.iter().enumerate() {
+        if frac & (1 << (31 - j)) != 0 {
+            result = (result * *factor as u128) >> 62;
+        }
+    }
+    result
+}
+
 //add some precompute to higher counts to save on-chain compute.
 pub fn calculate_price(count: i32, start_price: u64) -> u64 {
     let mut price: u64 = 0;
@@ -715,18 +2020,74 @@ pub fn price_grower(count: i32, price: f64, rate: f32) -> u64 {
 }
 
 pub fn check_claim(claim_id: &Pubkey, pool_id: &Pubkey, work: &[u8]) -> ProgramResult {
+    let (sha, magic) = split_work(work)?;
+    if claim_hash(sha, claim_id, pool_id).starts_with(magic) == false {
+        return Err(HihiError::IncorrectClaimSolution.into());
+    }
+    Ok(())
+}
+
+/// Zero bytes a nonce solution's hash must have right after the magic. A nonce attempt is
+/// one SHA-256 where a keypair attempt is an ed25519 key, about 256 times the work, so the
+/// extra byte keeps a puzzle as costly to solve in either format.
+pub const NONCE_EXTRA_BYTES: usize = 1;
+
+/// `nonce_claim_hash` must start with the magic followed by `NONCE_EXTRA_BYTES` zeros.
+pub fn check_claim_with_nonce(
+    claim_id: &Pubkey,
+    pool_id: &Pubkey,
+    work: &[u8],
+    nonce: u64,
+) -> ProgramResult {
+    let (sha, magic) = split_work(work)?;
+    let hash = nonce_claim_hash(sha, claim_id, pool_id, nonce);
+    let extra = hash.get(magic.len()..magic.len() + NONCE_EXTRA_BYTES);
+    if !hash.starts_with(magic) || extra.map_or(true, |extra| extra.iter().any(|b| *b != 0)) {
+        return Err(HihiError::IncorrectClaimSolution.into());
+    }
+    Ok(())
+}
+
+fn check_solution(
+    claim_id: &Pubkey,
+    pool_id: &Pubkey,
+    work: &[u8],
+    nonce: Option<u64>,
+) -> ProgramResult {
+    match nonce {
+        Some(nonce) => check_claim_with_nonce(claim_id, pool_id, work, nonce),
+        None => check_claim(claim_id, pool_id, work),
+    }
+}
+
+//splits a puzzle into its sha and magic prefix.
+fn split_work(work: &[u8]) -> Result<(&[u8], &[u8]), ProgramError> {
+    if work.len() < 34 || work.len() - 34 < work[33] as usize {
+        return Err(HihiError::InvalidMagicLength.into());
+    }
     let (_tokens, rest) = work.split_at(1);
     let (sha, rest) = rest.split_at(32);
     let (mag_len, rest) = rest.split_at(1);
     let (magic, _rest) = rest.split_at(mag_len[0] as usize);
+    Ok((sha, magic))
+}
+
+/// `sha256(sha || claim || pool)`, the hash whose prefix must match a puzzle's magic.
+pub fn claim_hash(sha: &[u8], claim_id: &Pubkey, pool_id: &Pubkey) -> [u8; 32] {
     let mut data_vec = sha.to_vec();
     data_vec.extend_from_slice(&claim_id.to_bytes());
     data_vec.extend_from_slice(&pool_id.to_bytes());
-    let hash_vec = hash(data_vec.as_slice()).to_bytes().to_vec();
-    if hash_vec.starts_with(magic) == false {
-        return Err(HihiError::IncorrectClaimSolution.into());
-    }
-    Ok(())
+    hash(data_vec.as_slice()).to_bytes()
+}
+
+/// `sha256(sha || claim || pool || nonce)` with a little endian nonce. Miners search the
+/// nonce instead of grinding claim keys, so claim and pool can be their regular wallets.
+pub fn nonce_claim_hash(sha: &[u8], claim_id: &Pubkey, pool_id: &Pubkey, nonce: u64) -> [u8; 32] {
+    let mut data_vec = sha.to_vec();
+    data_vec.extend_from_slice(&claim_id.to_bytes());
+    data_vec.extend_from_slice(&pool_id.to_bytes());
+    data_vec.extend_from_slice(&nonce.to_le_bytes());
+    hash(data_vec.as_slice()).to_bytes()
 }
 
 pub fn ceil(float: f64) -> u64 {
@@ -757,7 +2118,7 @@ pub fn check_accounts(
     to_info: &AccountInfo,
 ) -> Result<bool, ProgramError> {
     if token_program_id != &spl_token::id() {
-        return Err(HihiError::InvalidOwner.into());
+        return Err(HihiError::InvalidTokenProgram.into());
     }
 
     if token_mint_id != &instance.token_mint_id {
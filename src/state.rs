@@ -21,6 +21,9 @@ pub const LB_COUNT_BYTES: usize = 4;
 pub const LB_PER_EPOCH_BYTES: usize = 4;
 
 pub const MAX_COUNT: usize = 101;
+/// Puzzles the instance heap holds. Breaches fill puzzle batches first, so the heap gave up
+/// the room they take and the account stays no larger than before batches.
+pub const HEAP_COUNT: usize = 78;
 
 pub const INITIALIZED_BYTES: usize = 1;
 pub const NONCE_BYTES: usize = 1;
@@ -41,12 +44,98 @@ pub const ADMIN_ONE_BYTES: usize = 32;
 pub const ADMIN_TWO_BYTES: usize = 32;
 pub const WITHDRAW_BYTES: usize = 32;
 
+pub const REFERRAL_SHARE_BYTES: usize = 2;
+pub const VESTING_EPOCHS_BYTES: usize = 2;
+pub const PAGE_COUNT_BYTES: usize = 4;
+
+pub const SEED_BYTES: usize = 32;
+pub const BATCH_PUZZLE_COUNT_BYTES: usize = 1;
+pub const REWARD_BYTES: usize = 1;
+pub const DOUBLED_BYTES: usize = 1;
+pub const CLAIMED_BYTES: usize = 2;
+pub const BATCH_BYTES: usize = SEED_BYTES + BATCH_PUZZLE_COUNT_BYTES + REWARD_BYTES + DOUBLED_BYTES + DIFFICULTY_BYTES + CLAIMED_BYTES;
+/// Most puzzles in one batch, one breach transaction makes at most this many.
+pub const MAX_BATCH_PUZZLES: usize = 10;
+pub const MAX_BATCHES: usize = 32;
+pub const BATCH_COUNT: usize = 1;
+pub const BATCH_DATA: usize = BATCH_BYTES*MAX_BATCHES;
+
+pub const MAX_SUPPLY_BYTES: usize = 8;
+pub const MINTED_BYTES: usize = 8;
+pub const HALVING_EPOCHS_BYTES: usize = 8;
+pub const START_EPOCH_BYTES: usize = 8;
+pub const PRICE_SCALE_BYTES: usize = 4;
+pub const PRICE_EXPONENT_BYTES: usize = 4;
+pub const SURGE_BPS_BYTES: usize = 2;
+pub const LAST_BREACH_SLOT_BYTES: usize = 8;
+pub const DECAY_BPS_BYTES: usize = 2;
+pub const DECAY_FLOOR_BYTES: usize = 2;
+pub const VESTING_COUNT_BYTES: usize = 4;
+
 pub const VEC_COUNT: usize = 1;
 pub const VEC_DATA_LENGTH: usize = 4;
 pub const VEC_DATA: usize = WORK_BYTES*MAX_COUNT;
-pub const STATE_SPACE: usize = INITIALIZED_BYTES + NONCE_BYTES + SLOT_BYTES + EPOCH_BYTES + DIFFICULTY_BYTES + LAMPORTS_BYTES + PRICE_BYTES + REMAIN_BYTES + COUNT_BYTES + COUNT_PER_WINDOW_BYTES + CACHED_BYTES + TOKEN_MINT_ID_BYTES + TOKEN_DOUBLES_BYTES + LB_COUNT_BYTES + LB_PER_EPOCH_BYTES + LB_BYTES + ADMIN_ONE_BYTES + ADMIN_TWO_BYTES + WITHDRAW_BYTES + VEC_COUNT + VEC_DATA_LENGTH + VEC_DATA;
+pub const HEAP_DATA: usize = WORK_BYTES*HEAP_COUNT;
+/// Fields of the first release, at the same offsets in every layout.
+pub const BASE_SPACE: usize = INITIALIZED_BYTES + NONCE_BYTES + SLOT_BYTES + EPOCH_BYTES + DIFFICULTY_BYTES + LAMPORTS_BYTES + PRICE_BYTES + REMAIN_BYTES + COUNT_BYTES + COUNT_PER_WINDOW_BYTES + CACHED_BYTES + TOKEN_MINT_ID_BYTES + TOKEN_DOUBLES_BYTES + LB_COUNT_BYTES + LB_PER_EPOCH_BYTES + ADMIN_ONE_BYTES + ADMIN_TWO_BYTES + WITHDRAW_BYTES + LB_BYTES;
+/// Instance accounts of the first release, the base fields and a heap of `MAX_COUNT` puzzles.
+pub const LEGACY_STATE_SPACE: usize = BASE_SPACE + VEC_COUNT + VEC_DATA_LENGTH + VEC_DATA;
+/// Instances keep the size of the first release, see `HihiState::unpack_legacy`.
+pub const STATE_SPACE: usize = LEGACY_STATE_SPACE;
+/// Unused tail of the instance. New fields take their bytes from here, so accounts never
+/// grow and already migrated instances read them as 0.
+pub const RESERVED_BYTES: usize = STATE_SPACE - BASE_SPACE - REFERRAL_SHARE_BYTES - VESTING_EPOCHS_BYTES - PAGE_COUNT_BYTES - MAX_SUPPLY_BYTES - MINTED_BYTES - HALVING_EPOCHS_BYTES - START_EPOCH_BYTES - PRICE_SCALE_BYTES - PRICE_EXPONENT_BYTES - SURGE_BPS_BYTES - LAST_BREACH_SLOT_BYTES - DECAY_BPS_BYTES - DECAY_FLOOR_BYTES - BATCH_COUNT - BATCH_DATA - VEC_COUNT - VEC_DATA_LENGTH - HEAP_DATA - VESTING_COUNT_BYTES;
+
+/// First byte of an initialized instance in the first release layout.
+pub const LEGACY_VERSION: u8 = 1;
+/// First byte of an initialized instance in the current layout.
+pub const STATE_VERSION: u8 = 2;
+
+/// (name, type, bytes) of every `HihiState` field in the order `pack_into_slice` writes them.
+/// Keep in sync with the `mut_array_refs!` below, the IDL is generated from it.
+pub const STATE_LAYOUT: [(&str, &str, usize); 39] = [
+    ("version", "u8", INITIALIZED_BYTES),
+    ("nonce", "u8", NONCE_BYTES),
+    ("current_slot", "u64", SLOT_BYTES),
+    ("current_epoch", "u64", EPOCH_BYTES),
+    ("difficulty", "u8", DIFFICULTY_BYTES),
+    ("lamports", "u64", LAMPORTS_BYTES),
+    ("breach_price", "u64", PRICE_BYTES),
+    ("breach_remain", "u64", REMAIN_BYTES),
+    ("breach_count", "i32", COUNT_BYTES),
+    ("breach_count_this_window", "u32", COUNT_PER_WINDOW_BYTES),
+    ("work_cached", "u64", CACHED_BYTES),
+    ("token_mint_id", "publicKey", TOKEN_MINT_ID_BYTES),
+    ("token_doubles", "u64", TOKEN_DOUBLES_BYTES),
+    ("limit_count", "u32", LB_COUNT_BYTES),
+    ("limit_breaks_this_epoch", "u32", LB_PER_EPOCH_BYTES),
+    ("admin_one_id", "publicKey", ADMIN_ONE_BYTES),
+    ("admin_two_id", "publicKey", ADMIN_TWO_BYTES),
+    ("withdraw_id", "publicKey", WITHDRAW_BYTES),
+    ("limit_break", "[u8; 57]", LB_BYTES),
+    ("referral_share", "u16", REFERRAL_SHARE_BYTES),
+    ("vesting_epochs", "u16", VESTING_EPOCHS_BYTES),
+    ("page_count", "u32", PAGE_COUNT_BYTES),
+    ("max_supply", "u64", MAX_SUPPLY_BYTES),
+    ("minted", "u64", MINTED_BYTES),
+    ("halving_epochs", "u64", HALVING_EPOCHS_BYTES),
+    ("start_epoch", "u64", START_EPOCH_BYTES),
+    ("price_scale", "u32", PRICE_SCALE_BYTES),
+    ("price_exponent", "u32", PRICE_EXPONENT_BYTES),
+    ("surge_bps", "u16", SURGE_BPS_BYTES),
+    ("last_breach_slot", "u64", LAST_BREACH_SLOT_BYTES),
+    ("decay_bps", "u16", DECAY_BPS_BYTES),
+    ("decay_floor_bps", "u16", DECAY_FLOOR_BYTES),
+    ("batch_count", "u8", BATCH_COUNT),
+    ("batches", "[PuzzleBatch; 32]", BATCH_DATA),
+    ("work_count", "u8", VEC_COUNT),
+    ("work_data_length", "u32", VEC_DATA_LENGTH),
+    ("work", "[[u8; 57]; 78]", HEAP_DATA),
+    ("vesting_count", "u32", VESTING_COUNT_BYTES),
+    ("reserved", "[u8; 28]", RESERVED_BYTES),
+];
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct HihiState {
     pub is_initialized: bool,
     pub token_mint_id: Pubkey,
@@ -67,6 +156,32 @@ pub struct HihiState {
     pub withdraw_id: Pubkey,
     pub limit_break:Vec<u8>,
     pub work_cached:u64,
+    pub referral_share: u16,
+    pub vesting_epochs: u16,
+    pub page_count: u32,
+    pub batches: Vec<PuzzleBatch>,
+    /// Most token base units the program mints, 0 is uncapped.
+    pub max_supply: u64,
+    /// Token base units minted so far, counted against `max_supply`.
+    pub minted: u64,
+    /// Epochs per reward halving, 0 keeps the breach count tiers of `calculate_tokens`.
+    pub halving_epochs: u64,
+    /// Epoch the instance was initialized in, halvings count from here.
+    pub start_epoch: u64,
+    /// Breaches the price curve is scaled by, 0 keeps the tiered growth of `calculate_price`.
+    pub price_scale: u32,
+    /// Price curve exponent in millionths.
+    pub price_exponent: u32,
+    /// Basis points each breach in a window adds to the price of the next, 0 is off.
+    pub surge_bps: u16,
+    /// Slot of the latest breach, price decay counts from here.
+    pub last_breach_slot: u64,
+    /// Basis points the breach price drops per slot without breaches, 0 is off.
+    pub decay_bps: u16,
+    /// Lowest decayed price in basis points of `breach_price`.
+    pub decay_floor_bps: u16,
+    /// Vesting accounts created for the instance, `close` settles every one of them.
+    pub vesting_count: u32,
     pub work: Vec<Vec<u8>>
 }
 
@@ -78,7 +193,7 @@ impl HihiState {
     pub fn add_work(&mut self, work: &[u8]) -> Result<(), HihiError> {
         let count = work.len()/WORK_BYTES;
         let mut pos = 0;
-        if self.work.len()+count <= MAX_COUNT {
+        if self.work.len()+count <= HEAP_COUNT {
             for _ in 0..count {
                 let w = &work[pos..pos+WORK_BYTES];
                 self.work.push(w.to_vec());
@@ -101,12 +216,154 @@ impl HihiState {
     }
 
     pub fn get_work_free_space(&self) -> i32 {
-        return (MAX_COUNT - self.work.len()-1) as i32;
+        return (HEAP_COUNT - self.work.len()) as i32;
     }
 
     pub fn get_space(&self) -> usize {
         return STATE_SPACE;
     }
+
+    pub fn get_batch_free_space(&self) -> i32 {
+        return (MAX_BATCHES - self.batches.len()) as i32;
+    }
+
+    /// Index of the batch with `seed`.
+    pub fn find_batch(&self, seed: &[u8; SEED_BYTES]) -> Option<usize> {
+        self.batches.iter().position(|batch| &batch.seed == seed)
+    }
+
+    /// Token base units that can still be minted, `u64::MAX` when uncapped.
+    pub fn remaining_supply(&self) -> u64 {
+        if self.max_supply == 0 {
+            return u64::MAX;
+        }
+        self.max_supply.saturating_sub(self.minted)
+    }
+
+    /// The cap is reached, nothing can be minted any more.
+    pub fn is_supply_exhausted(&self) -> bool {
+        self.remaining_supply() == 0
+    }
+
+    /// Count up to `amount` against the cap and return what may be minted.
+    pub fn take_supply(&mut self, amount: u64) -> u64 {
+        let amount = amount.min(self.remaining_supply());
+        self.minted = self.minted.saturating_add(amount);
+        amount
+    }
+
+    /// Read an instance of the first release layout, every later field 0. The heap can hold
+    /// more than `HEAP_COUNT` puzzles, `Processor::process_migrate` moves the rest to a page.
+    pub fn unpack_legacy(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < LEGACY_STATE_SPACE {
+            return Err(HihiError::DeserializationFailure.into());
+        }
+        let input = array_ref![input, 0, LEGACY_STATE_SPACE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            base,
+            vec_count,
+            _vec_data_length,
+            vec_data
+        ) = array_refs![input, BASE_SPACE, VEC_COUNT, VEC_DATA_LENGTH, VEC_DATA];
+
+        if base[0] != LEGACY_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if vec_count[0] as usize > MAX_COUNT {
+            return Err(HihiError::InvalidWorkCount.into());
+        }
+
+        //the base fields sit where they always were, the rest of the account is zeroed.
+        let mut data = [0; STATE_SPACE];
+        data[..BASE_SPACE].copy_from_slice(base);
+        data[0] = STATE_VERSION;
+        let mut instance = Self::unpack_from_slice(&data)?;
+        instance.work = unpack_vec_of_vec(vec_data, vec_count[0]);
+        Ok(instance)
+    }
+}
+
+/// (name, type, bytes) of every `PuzzleBatch` field in pack order.
+pub const BATCH_LAYOUT: [(&str, &str, usize); 6] = [
+    ("seed", "[u8; 32]", SEED_BYTES),
+    ("count", "u8", BATCH_PUZZLE_COUNT_BYTES),
+    ("reward", "u8", REWARD_BYTES),
+    ("doubled", "u8", DOUBLED_BYTES),
+    ("difficulty", "u8", DIFFICULTY_BYTES),
+    ("claimed", "u16", CLAIMED_BYTES),
+];
+
+/// The puzzles of one breach stored as the seed of their hash chain, see
+/// `processor::derive_puzzle`. The first `doubled` puzzles made, the highest
+/// indices, pay twice the reward. Bit `i` of `claimed` is set once puzzle `i` is claimed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PuzzleBatch {
+    pub seed: [u8; SEED_BYTES],
+    pub count: u8,
+    pub reward: u8,
+    pub doubled: u8,
+    pub difficulty: u8,
+    pub claimed: u16,
+}
+
+impl PuzzleBatch {
+    /// Reward of puzzle `index`.
+    pub fn reward_of(&self, index: u8) -> u8 {
+        if index >= self.count.saturating_sub(self.doubled) {
+            return self.reward * 2;
+        }
+        self.reward
+    }
+
+    pub fn is_claimed(&self, index: u8) -> bool {
+        self.claimed & (1 << index) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u8) {
+        self.claimed |= 1 << index;
+    }
+
+    /// Puzzles of the batch not claimed yet.
+    pub fn open_count(&self) -> u32 {
+        self.count as u32 - self.claimed.count_ones()
+    }
+
+    fn pack_into(&self, output: &mut [u8; BATCH_BYTES]) {
+        let (
+            seed,
+            count,
+            reward,
+            doubled,
+            difficulty,
+            claimed
+        ) = mut_array_refs![output, SEED_BYTES, BATCH_PUZZLE_COUNT_BYTES, REWARD_BYTES, DOUBLED_BYTES, DIFFICULTY_BYTES, CLAIMED_BYTES];
+        seed.copy_from_slice(&self.seed);
+        count[0] = self.count;
+        reward[0] = self.reward;
+        doubled[0] = self.doubled;
+        difficulty[0] = self.difficulty;
+        claimed[..].copy_from_slice(&self.claimed.to_le_bytes());
+    }
+
+    fn unpack_from(input: &[u8; BATCH_BYTES]) -> Self {
+        let (
+            seed,
+            count,
+            reward,
+            doubled,
+            difficulty,
+            claimed
+        ) = array_refs![input, SEED_BYTES, BATCH_PUZZLE_COUNT_BYTES, REWARD_BYTES, DOUBLED_BYTES, DIFFICULTY_BYTES, CLAIMED_BYTES];
+        Self {
+            seed:*seed,
+            count:count[0],
+            reward:reward[0],
+            doubled:doubled[0],
+            difficulty:difficulty[0],
+            claimed:u16::from_le_bytes(*claimed),
+        }
+    }
 }
 
 impl Sealed for HihiState {}
@@ -142,11 +399,28 @@ impl Pack for HihiState {
             admin_two_id,
             withdraw_id,
             limit_break,
+            referral_share,
+            vesting_epochs,
+            page_count,
+            max_supply,
+            minted,
+            halving_epochs,
+            start_epoch,
+            price_scale,
+            price_exponent,
+            surge_bps,
+            last_breach_slot,
+            decay_bps,
+            decay_floor_bps,
+            batch_count,
+            batch_data,
             vec_count,
             vec_data_length,
-            vec_data
-        ) = mut_array_refs![output, INITIALIZED_BYTES, NONCE_BYTES, SLOT_BYTES, EPOCH_BYTES, DIFFICULTY_BYTES, LAMPORTS_BYTES, PRICE_BYTES, REMAIN_BYTES, COUNT_BYTES, COUNT_PER_WINDOW_BYTES, CACHED_BYTES, TOKEN_MINT_ID_BYTES, TOKEN_DOUBLES_BYTES, LB_COUNT_BYTES, LB_PER_EPOCH_BYTES, ADMIN_ONE_BYTES , ADMIN_TWO_BYTES , WITHDRAW_BYTES, LB_BYTES, VEC_COUNT, VEC_DATA_LENGTH, VEC_DATA];
-        is_initialized[0] = self.is_initialized as u8;
+            vec_data,
+            vesting_count,
+            reserved
+        ) = mut_array_refs![output, INITIALIZED_BYTES, NONCE_BYTES, SLOT_BYTES, EPOCH_BYTES, DIFFICULTY_BYTES, LAMPORTS_BYTES, PRICE_BYTES, REMAIN_BYTES, COUNT_BYTES, COUNT_PER_WINDOW_BYTES, CACHED_BYTES, TOKEN_MINT_ID_BYTES, TOKEN_DOUBLES_BYTES, LB_COUNT_BYTES, LB_PER_EPOCH_BYTES, ADMIN_ONE_BYTES , ADMIN_TWO_BYTES , WITHDRAW_BYTES, LB_BYTES, REFERRAL_SHARE_BYTES, VESTING_EPOCHS_BYTES, PAGE_COUNT_BYTES, MAX_SUPPLY_BYTES, MINTED_BYTES, HALVING_EPOCHS_BYTES, START_EPOCH_BYTES, PRICE_SCALE_BYTES, PRICE_EXPONENT_BYTES, SURGE_BPS_BYTES, LAST_BREACH_SLOT_BYTES, DECAY_BPS_BYTES, DECAY_FLOOR_BYTES, BATCH_COUNT, BATCH_DATA, VEC_COUNT, VEC_DATA_LENGTH, HEAP_DATA, VESTING_COUNT_BYTES, RESERVED_BYTES];
+        is_initialized[0] = if self.is_initialized { STATE_VERSION } else { 0 };
         nonce[0] = self.nonce as u8;
         current_slot[..].copy_from_slice(&self.current_slot.to_le_bytes());
         current_epoch[..].copy_from_slice(&self.current_epoch.to_le_bytes());
@@ -164,19 +438,50 @@ impl Pack for HihiState {
         admin_one_id.copy_from_slice(self.admin_one_id.as_ref());
         admin_two_id.copy_from_slice(self.admin_two_id.as_ref());
         withdraw_id.copy_from_slice(self.withdraw_id.as_ref());
-        sol_memcpy(limit_break, &self.limit_break, LB_BYTES);
+        //uninitialized accounts have no limit break yet.
+        if self.limit_break.len() == LB_BYTES {
+            sol_memcpy(limit_break, &self.limit_break, LB_BYTES);
+        } else {
+            *limit_break = [0; LB_BYTES];
+        }
+        referral_share[..].copy_from_slice(&self.referral_share.to_le_bytes());
+        vesting_epochs[..].copy_from_slice(&self.vesting_epochs.to_le_bytes());
+        page_count[..].copy_from_slice(&self.page_count.to_le_bytes());
+        if self.batches.len() > MAX_BATCHES {
+            panic!("Not allowed to excede {} puzzle batches.", MAX_BATCHES);
+        }
+        batch_count[0] = self.batches.len() as u8;
+        *batch_data = [0; BATCH_DATA];
+        for (batch, dst) in self.batches.iter().zip(batch_data.chunks_exact_mut(BATCH_BYTES)) {
+            batch.pack_into(array_mut_ref![dst, 0, BATCH_BYTES]);
+        }
+        max_supply[..].copy_from_slice(&self.max_supply.to_le_bytes());
+        minted[..].copy_from_slice(&self.minted.to_le_bytes());
+        halving_epochs[..].copy_from_slice(&self.halving_epochs.to_le_bytes());
+        start_epoch[..].copy_from_slice(&self.start_epoch.to_le_bytes());
+        price_scale[..].copy_from_slice(&self.price_scale.to_le_bytes());
+        price_exponent[..].copy_from_slice(&self.price_exponent.to_le_bytes());
+        surge_bps[..].copy_from_slice(&self.surge_bps.to_le_bytes());
+        last_breach_slot[..].copy_from_slice(&self.last_breach_slot.to_le_bytes());
+        decay_bps[..].copy_from_slice(&self.decay_bps.to_le_bytes());
+        decay_floor_bps[..].copy_from_slice(&self.decay_floor_bps.to_le_bytes());
         vec_count[0] = self.work.len() as u8;
         let data = pack_vec_of_vec(&self.work);
         let data_len = data.len();
-        if data_len < VEC_DATA {
+        if data_len <= HEAP_DATA {
             vec_data_length[..].copy_from_slice(&(data_len as u32).to_le_bytes());
             sol_memcpy(vec_data, &data, data_len);
         } else {
-            panic!("Not allowed to excede {} pow account limit.", MAX_COUNT);
+            panic!("Not allowed to excede {} pow account limit.", HEAP_COUNT);
         }
+        vesting_count[..].copy_from_slice(&self.vesting_count.to_le_bytes());
+        *reserved = [0; RESERVED_BYTES];
     }
 
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < STATE_SPACE {
+            return Err(HihiError::DeserializationFailure.into());
+        }
         let input = array_ref![input, 0, STATE_SPACE];
         #[allow(clippy::ptr_offset_with_cast)]
         let (
@@ -199,17 +504,42 @@ impl Pack for HihiState {
             admin_two_id,
             withdraw_id,
             limit_break,
+            referral_share,
+            vesting_epochs,
+            page_count,
+            max_supply,
+            minted,
+            halving_epochs,
+            start_epoch,
+            price_scale,
+            price_exponent,
+            surge_bps,
+            last_breach_slot,
+            decay_bps,
+            decay_floor_bps,
+            batch_count,
+            batch_data,
             vec_count,
             _vec_data_length,
-            vec_data
-        ) = array_refs![input, INITIALIZED_BYTES, NONCE_BYTES, SLOT_BYTES, EPOCH_BYTES, DIFFICULTY_BYTES, LAMPORTS_BYTES, PRICE_BYTES, REMAIN_BYTES, COUNT_BYTES, COUNT_PER_WINDOW_BYTES, CACHED_BYTES, TOKEN_MINT_ID_BYTES, TOKEN_DOUBLES_BYTES, LB_COUNT_BYTES, LB_PER_EPOCH_BYTES, ADMIN_ONE_BYTES , ADMIN_TWO_BYTES , WITHDRAW_BYTES, LB_BYTES, VEC_COUNT, VEC_DATA_LENGTH, VEC_DATA];
+            vec_data,
+            vesting_count,
+            _reserved
+        ) = array_refs![input, INITIALIZED_BYTES, NONCE_BYTES, SLOT_BYTES, EPOCH_BYTES, DIFFICULTY_BYTES, LAMPORTS_BYTES, PRICE_BYTES, REMAIN_BYTES, COUNT_BYTES, COUNT_PER_WINDOW_BYTES, CACHED_BYTES, TOKEN_MINT_ID_BYTES, TOKEN_DOUBLES_BYTES, LB_COUNT_BYTES, LB_PER_EPOCH_BYTES, ADMIN_ONE_BYTES , ADMIN_TWO_BYTES , WITHDRAW_BYTES, LB_BYTES, REFERRAL_SHARE_BYTES, VESTING_EPOCHS_BYTES, PAGE_COUNT_BYTES, MAX_SUPPLY_BYTES, MINTED_BYTES, HALVING_EPOCHS_BYTES, START_EPOCH_BYTES, PRICE_SCALE_BYTES, PRICE_EXPONENT_BYTES, SURGE_BPS_BYTES, LAST_BREACH_SLOT_BYTES, DECAY_BPS_BYTES, DECAY_FLOOR_BYTES, BATCH_COUNT, BATCH_DATA, VEC_COUNT, VEC_DATA_LENGTH, HEAP_DATA, VESTING_COUNT_BYTES, RESERVED_BYTES];
 
         let is_init = match is_initialized {
             [0] => false,
-            [1] => true,
+            [STATE_VERSION] => true,
+            [LEGACY_VERSION] => return Err(HihiError::LegacyState.into()),
             _ => return Err(ProgramError::InvalidAccountData)
         };
         
+        if vec_count[0] as usize > HEAP_COUNT {
+            return Err(HihiError::InvalidWorkCount.into());
+        }
+        if batch_count[0] as usize > MAX_BATCHES {
+            return Err(HihiError::InvalidBatchCount.into());
+        }
+        
         if is_init == false {
             Ok(Self{
                 is_initialized:is_init,
@@ -231,6 +561,21 @@ impl Pack for HihiState {
                 admin_two_id:Pubkey::new_from_array(*admin_two_id),
                 withdraw_id:Pubkey::new_from_array(*withdraw_id),
                 limit_break:Vec::<u8>::new(),
+                referral_share:0,
+                vesting_epochs:0,
+                page_count:0,
+                batches:Vec::<PuzzleBatch>::new(),
+                max_supply:0,
+                minted:0,
+                halving_epochs:0,
+                start_epoch:0,
+                price_scale:0,
+                price_exponent:0,
+                surge_bps:0,
+                last_breach_slot:0,
+                decay_bps:0,
+                decay_floor_bps:0,
+                vesting_count:0,
                 work:Vec::<Vec<u8>>::new()
             })
         } else {
@@ -254,7 +599,26 @@ impl Pack for HihiState {
                 admin_two_id:Pubkey::new_from_array(*admin_two_id),
                 withdraw_id:Pubkey::new_from_array(*withdraw_id),
                 limit_break:limit_break.to_vec(),
-                work:unpack_vec_of_vec(&vec_data, vec_count[0])
+                referral_share:u16::from_le_bytes(*referral_share),
+                vesting_epochs:u16::from_le_bytes(*vesting_epochs),
+                page_count:u32::from_le_bytes(*page_count),
+                batches:batch_data
+                    .chunks_exact(BATCH_BYTES)
+                    .take(batch_count[0] as usize)
+                    .map(|src| PuzzleBatch::unpack_from(array_ref![src, 0, BATCH_BYTES]))
+                    .collect(),
+                max_supply:u64::from_le_bytes(*max_supply),
+                minted:u64::from_le_bytes(*minted),
+                halving_epochs:u64::from_le_bytes(*halving_epochs),
+                start_epoch:u64::from_le_bytes(*start_epoch),
+                price_scale:u32::from_le_bytes(*price_scale),
+                price_exponent:u32::from_le_bytes(*price_exponent),
+                surge_bps:u16::from_le_bytes(*surge_bps),
+                last_breach_slot:u64::from_le_bytes(*last_breach_slot),
+                decay_bps:u16::from_le_bytes(*decay_bps),
+                decay_floor_bps:u16::from_le_bytes(*decay_floor_bps),
+                vesting_count:u32::from_le_bytes(*vesting_count),
+                work:unpack_vec_of_vec(vec_data, vec_count[0])
             })
         }
     }
@@ -268,7 +632,7 @@ fn pack_vec_of_vec(args: &Vec<Vec<u8>>) -> Vec<u8> {
     return buf;
 }
 
-fn unpack_vec_of_vec(slice: &[u8; VEC_DATA], count:u8) -> Vec<Vec<u8>> {
+fn unpack_vec_of_vec(slice: &[u8], count:u8) -> Vec<Vec<u8>> {
     let mut buf = Vec::<Vec<u8>>::new();
     let mut m_rest:&[u8] = slice;
     for _ in 0..count {
@@ -278,3 +642,646 @@ fn unpack_vec_of_vec(slice: &[u8; VEC_DATA], count:u8) -> Vec<Vec<u8>> {
     }
     return buf;
 }
+
+pub const ACCOUNT_TYPE_BYTES: usize = 1;
+/// `account_type` of the wallet and page accounts. They share their first fields, the tag keeps
+/// one kind from being unpacked as another.
+pub const MINER_ACCOUNT: u8 = 1;
+pub const REFERRER_ACCOUNT: u8 = 2;
+pub const VESTING_ACCOUNT: u8 = 3;
+pub const PAGE_ACCOUNT: u8 = 4;
+
+pub const MINER_SEED: &[u8] = b"miner";
+
+pub const INSTANCE_ID_BYTES: usize = 32;
+pub const WALLET_BYTES: usize = 32;
+pub const BUMP_BYTES: usize = 1;
+pub const SOLVED_BYTES: usize = 8;
+pub const EARNED_BYTES: usize = 8;
+pub const LIMIT_BREAKS_BYTES: usize = 4;
+pub const HIGHEST_DIFFICULTY_BYTES: usize = 1;
+pub const MINER_SPACE: usize = INITIALIZED_BYTES + ACCOUNT_TYPE_BYTES + INSTANCE_ID_BYTES + WALLET_BYTES + BUMP_BYTES + SOLVED_BYTES + EARNED_BYTES + LIMIT_BREAKS_BYTES + SLOT_BYTES + SLOT_BYTES + HIGHEST_DIFFICULTY_BYTES;
+
+/// (name, type, bytes) of every `MinerState` field in pack order.
+pub const MINER_LAYOUT: [(&str, &str, usize); 11] = [
+    ("is_initialized", "bool", INITIALIZED_BYTES),
+    ("account_type", "u8", ACCOUNT_TYPE_BYTES),
+    ("instance_id", "publicKey", INSTANCE_ID_BYTES),
+    ("wallet_id", "publicKey", WALLET_BYTES),
+    ("bump", "u8", BUMP_BYTES),
+    ("puzzles_solved", "u64", SOLVED_BYTES),
+    ("tokens_earned", "u64", EARNED_BYTES),
+    ("limit_breaks", "u32", LIMIT_BREAKS_BYTES),
+    ("first_slot", "u64", SLOT_BYTES),
+    ("last_slot", "u64", SLOT_BYTES),
+    ("highest_difficulty", "u8", HIGHEST_DIFFICULTY_BYTES),
+];
+
+/// Per wallet mining statistics, a PDA of `[MINER_SEED, instance, wallet]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MinerState {
+    pub is_initialized: bool,
+    pub instance_id: Pubkey,
+    pub wallet_id: Pubkey,
+    pub bump: u8,
+    pub puzzles_solved: u64,
+    pub tokens_earned: u64,
+    pub limit_breaks: u32,
+    pub first_slot: u64,
+    pub last_slot: u64,
+    pub highest_difficulty: u8,
+}
+
+impl MinerState {
+    pub fn record(&mut self, slot: u64, tokens: u64, difficulty: u8, limit_break: bool) {
+        if limit_break {
+            self.limit_breaks += 1;
+        } else {
+            self.puzzles_solved += 1;
+        }
+        self.tokens_earned += tokens;
+        if self.first_slot == 0 {
+            self.first_slot = slot;
+        }
+        self.last_slot = slot;
+        if difficulty > self.highest_difficulty {
+            self.highest_difficulty = difficulty;
+        }
+    }
+}
+
+impl Sealed for MinerState {}
+
+impl IsInitialized for MinerState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for MinerState {
+    const LEN: usize = MINER_SPACE;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, MINER_SPACE];
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            wallet_id,
+            bump,
+            puzzles_solved,
+            tokens_earned,
+            limit_breaks,
+            first_slot,
+            last_slot,
+            highest_difficulty
+        ) = mut_array_refs![output, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, WALLET_BYTES, BUMP_BYTES, SOLVED_BYTES, EARNED_BYTES, LIMIT_BREAKS_BYTES, SLOT_BYTES, SLOT_BYTES, HIGHEST_DIFFICULTY_BYTES];
+        is_initialized[0] = self.is_initialized as u8;
+        account_type[0] = MINER_ACCOUNT;
+        instance_id.copy_from_slice(self.instance_id.as_ref());
+        wallet_id.copy_from_slice(self.wallet_id.as_ref());
+        bump[0] = self.bump;
+        puzzles_solved[..].copy_from_slice(&self.puzzles_solved.to_le_bytes());
+        tokens_earned[..].copy_from_slice(&self.tokens_earned.to_le_bytes());
+        limit_breaks[..].copy_from_slice(&self.limit_breaks.to_le_bytes());
+        first_slot[..].copy_from_slice(&self.first_slot.to_le_bytes());
+        last_slot[..].copy_from_slice(&self.last_slot.to_le_bytes());
+        highest_difficulty[0] = self.highest_difficulty;
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < MINER_SPACE {
+            return Err(HihiError::DeserializationFailure.into());
+        }
+        let input = array_ref![input, 0, MINER_SPACE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            wallet_id,
+            bump,
+            puzzles_solved,
+            tokens_earned,
+            limit_breaks,
+            first_slot,
+            last_slot,
+            highest_difficulty
+        ) = array_refs![input, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, WALLET_BYTES, BUMP_BYTES, SOLVED_BYTES, EARNED_BYTES, LIMIT_BREAKS_BYTES, SLOT_BYTES, SLOT_BYTES, HIGHEST_DIFFICULTY_BYTES];
+
+        let is_init = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData)
+        };
+        if is_init && account_type[0] != MINER_ACCOUNT {
+            return Err(HihiError::InvalidAccountType.into());
+        }
+
+        Ok(Self {
+            is_initialized:is_init,
+            instance_id:Pubkey::new_from_array(*instance_id),
+            wallet_id:Pubkey::new_from_array(*wallet_id),
+            bump:bump[0],
+            puzzles_solved:u64::from_le_bytes(*puzzles_solved),
+            tokens_earned:u64::from_le_bytes(*tokens_earned),
+            limit_breaks:u32::from_le_bytes(*limit_breaks),
+            first_slot:u64::from_le_bytes(*first_slot),
+            last_slot:u64::from_le_bytes(*last_slot),
+            highest_difficulty:highest_difficulty[0],
+        })
+    }
+}
+
+pub const REFERRER_SEED: &[u8] = b"referrer";
+
+pub const REFERRED_BYTES: usize = 8;
+pub const REFERRER_SPACE: usize = INITIALIZED_BYTES + ACCOUNT_TYPE_BYTES + INSTANCE_ID_BYTES + WALLET_BYTES + BUMP_BYTES + REFERRED_BYTES + EARNED_BYTES;
+
+/// (name, type, bytes) of every `ReferrerState` field in pack order.
+pub const REFERRER_LAYOUT: [(&str, &str, usize); 7] = [
+    ("is_initialized", "bool", INITIALIZED_BYTES),
+    ("account_type", "u8", ACCOUNT_TYPE_BYTES),
+    ("instance_id", "publicKey", INSTANCE_ID_BYTES),
+    ("wallet_id", "publicKey", WALLET_BYTES),
+    ("bump", "u8", BUMP_BYTES),
+    ("referred_breaches", "u64", REFERRED_BYTES),
+    ("tokens_earned", "u64", EARNED_BYTES),
+];
+
+/// Referral totals of a wallet, a PDA of `[REFERRER_SEED, instance, wallet]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReferrerState {
+    pub is_initialized: bool,
+    pub instance_id: Pubkey,
+    pub wallet_id: Pubkey,
+    pub bump: u8,
+    pub referred_breaches: u64,
+    pub tokens_earned: u64,
+}
+
+impl Sealed for ReferrerState {}
+
+impl IsInitialized for ReferrerState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ReferrerState {
+    const LEN: usize = REFERRER_SPACE;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, REFERRER_SPACE];
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            wallet_id,
+            bump,
+            referred_breaches,
+            tokens_earned
+        ) = mut_array_refs![output, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, WALLET_BYTES, BUMP_BYTES, REFERRED_BYTES, EARNED_BYTES];
+        is_initialized[0] = self.is_initialized as u8;
+        account_type[0] = REFERRER_ACCOUNT;
+        instance_id.copy_from_slice(self.instance_id.as_ref());
+        wallet_id.copy_from_slice(self.wallet_id.as_ref());
+        bump[0] = self.bump;
+        referred_breaches[..].copy_from_slice(&self.referred_breaches.to_le_bytes());
+        tokens_earned[..].copy_from_slice(&self.tokens_earned.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < REFERRER_SPACE {
+            return Err(HihiError::DeserializationFailure.into());
+        }
+        let input = array_ref![input, 0, REFERRER_SPACE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            wallet_id,
+            bump,
+            referred_breaches,
+            tokens_earned
+        ) = array_refs![input, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, WALLET_BYTES, BUMP_BYTES, REFERRED_BYTES, EARNED_BYTES];
+
+        let is_init = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData)
+        };
+        if is_init && account_type[0] != REFERRER_ACCOUNT {
+            return Err(HihiError::InvalidAccountType.into());
+        }
+
+        Ok(Self {
+            is_initialized:is_init,
+            instance_id:Pubkey::new_from_array(*instance_id),
+            wallet_id:Pubkey::new_from_array(*wallet_id),
+            bump:bump[0],
+            referred_breaches:u64::from_le_bytes(*referred_breaches),
+            tokens_earned:u64::from_le_bytes(*tokens_earned),
+        })
+    }
+}
+
+pub const VESTING_SEED: &[u8] = b"vesting";
+
+pub const AMOUNT_BYTES: usize = 8;
+pub const VESTING_SPACE: usize = INITIALIZED_BYTES + ACCOUNT_TYPE_BYTES + INSTANCE_ID_BYTES + WALLET_BYTES + BUMP_BYTES + AMOUNT_BYTES + AMOUNT_BYTES + AMOUNT_BYTES + EPOCH_BYTES + EPOCH_BYTES;
+
+/// (name, type, bytes) of every `VestingState` field in pack order.
+pub const VESTING_LAYOUT: [(&str, &str, usize); 10] = [
+    ("is_initialized", "bool", INITIALIZED_BYTES),
+    ("account_type", "u8", ACCOUNT_TYPE_BYTES),
+    ("instance_id", "publicKey", INSTANCE_ID_BYTES),
+    ("wallet_id", "publicKey", WALLET_BYTES),
+    ("bump", "u8", BUMP_BYTES),
+    ("total", "u64", AMOUNT_BYTES),
+    ("released", "u64", AMOUNT_BYTES),
+    ("unlocked", "u64", AMOUNT_BYTES),
+    ("start_epoch", "u64", EPOCH_BYTES),
+    ("end_epoch", "u64", EPOCH_BYTES),
+];
+
+/// Limit break rewards of a wallet vesting linearly from `start_epoch` to `end_epoch`,
+/// a PDA of `[VESTING_SEED, instance, wallet]`.
+/// `released` is the part of `total` already claimed, `unlocked` is vested tokens of
+/// earlier schedules that were not claimed yet.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VestingState {
+    pub is_initialized: bool,
+    pub instance_id: Pubkey,
+    pub wallet_id: Pubkey,
+    pub bump: u8,
+    pub total: u64,
+    pub released: u64,
+    pub unlocked: u64,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+}
+
+impl VestingState {
+    /// Part of `total` vested at `epoch`.
+    pub fn vested(&self, epoch: u64) -> u64 {
+        if epoch >= self.end_epoch {
+            return self.total;
+        }
+        if epoch <= self.start_epoch {
+            return 0;
+        }
+        (self.total as u128 * (epoch - self.start_epoch) as u128
+            / (self.end_epoch - self.start_epoch) as u128) as u64
+    }
+
+    /// Tokens `release` would hand out at `epoch`.
+    pub fn claimable(&self, epoch: u64) -> u64 {
+        self.unlocked + self.vested(epoch) - self.released
+    }
+
+    /// Add `amount` and restart the schedule at `epoch` for `epochs` epochs.
+    /// What already vested stays claimable, the rest vests again with the new amount.
+    pub fn grant(&mut self, amount: u64, epoch: u64, epochs: u64) {
+        let vested = self.vested(epoch);
+        self.unlocked += vested - self.released;
+        self.total = self.total - vested + amount;
+        self.released = 0;
+        self.start_epoch = epoch;
+        self.end_epoch = epoch + epochs;
+    }
+
+    /// Mark everything vested at `epoch` as claimed and return the amount.
+    pub fn release(&mut self, epoch: u64) -> u64 {
+        let amount = self.claimable(epoch);
+        self.released = self.vested(epoch);
+        self.unlocked = 0;
+        amount
+    }
+}
+
+impl Sealed for VestingState {}
+
+impl IsInitialized for VestingState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for VestingState {
+    const LEN: usize = VESTING_SPACE;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, VESTING_SPACE];
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            wallet_id,
+            bump,
+            total,
+            released,
+            unlocked,
+            start_epoch,
+            end_epoch
+        ) = mut_array_refs![output, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, WALLET_BYTES, BUMP_BYTES, AMOUNT_BYTES, AMOUNT_BYTES, AMOUNT_BYTES, EPOCH_BYTES, EPOCH_BYTES];
+        is_initialized[0] = self.is_initialized as u8;
+        account_type[0] = VESTING_ACCOUNT;
+        instance_id.copy_from_slice(self.instance_id.as_ref());
+        wallet_id.copy_from_slice(self.wallet_id.as_ref());
+        bump[0] = self.bump;
+        total[..].copy_from_slice(&self.total.to_le_bytes());
+        released[..].copy_from_slice(&self.released.to_le_bytes());
+        unlocked[..].copy_from_slice(&self.unlocked.to_le_bytes());
+        start_epoch[..].copy_from_slice(&self.start_epoch.to_le_bytes());
+        end_epoch[..].copy_from_slice(&self.end_epoch.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < VESTING_SPACE {
+            return Err(HihiError::DeserializationFailure.into());
+        }
+        let input = array_ref![input, 0, VESTING_SPACE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            wallet_id,
+            bump,
+            total,
+            released,
+            unlocked,
+            start_epoch,
+            end_epoch
+        ) = array_refs![input, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, WALLET_BYTES, BUMP_BYTES, AMOUNT_BYTES, AMOUNT_BYTES, AMOUNT_BYTES, EPOCH_BYTES, EPOCH_BYTES];
+
+        let is_init = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData)
+        };
+        if is_init && account_type[0] != VESTING_ACCOUNT {
+            return Err(HihiError::InvalidAccountType.into());
+        }
+
+        Ok(Self {
+            is_initialized:is_init,
+            instance_id:Pubkey::new_from_array(*instance_id),
+            wallet_id:Pubkey::new_from_array(*wallet_id),
+            bump:bump[0],
+            total:u64::from_le_bytes(*total),
+            released:u64::from_le_bytes(*released),
+            unlocked:u64::from_le_bytes(*unlocked),
+            start_epoch:u64::from_le_bytes(*start_epoch),
+            end_epoch:u64::from_le_bytes(*end_epoch),
+        })
+    }
+}
+
+pub const PAGE_SEED: &[u8] = b"page";
+
+pub const PAGE_INDEX_BYTES: usize = 4;
+pub const PAGE_SPACE: usize = INITIALIZED_BYTES + ACCOUNT_TYPE_BYTES + INSTANCE_ID_BYTES + PAGE_INDEX_BYTES + BUMP_BYTES + VEC_COUNT + VEC_DATA_LENGTH + VEC_DATA;
+
+/// (name, type, bytes) of every `PageState` field in pack order.
+pub const PAGE_LAYOUT: [(&str, &str, usize); 8] = [
+    ("is_initialized", "bool", INITIALIZED_BYTES),
+    ("account_type", "u8", ACCOUNT_TYPE_BYTES),
+    ("instance_id", "publicKey", INSTANCE_ID_BYTES),
+    ("index", "u32", PAGE_INDEX_BYTES),
+    ("bump", "u8", BUMP_BYTES),
+    ("work_count", "u8", VEC_COUNT),
+    ("work_data_length", "u32", VEC_DATA_LENGTH),
+    ("work", "[[u8; 57]; 101]", VEC_DATA),
+];
+
+/// Extra work heap of an instance, a PDA of `[PAGE_SEED, instance, index]`.
+/// Breaches fill pages once the instance heap is full.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PageState {
+    pub is_initialized: bool,
+    pub instance_id: Pubkey,
+    pub index: u32,
+    pub bump: u8,
+    pub work: Vec<Vec<u8>>,
+}
+
+impl PageState {
+    pub fn add_work(&mut self, work: &[u8]) -> Result<(), HihiError> {
+        let count = work.len()/WORK_BYTES;
+        if self.work.len()+count > MAX_COUNT {
+            return Err(HihiError::WorkLimitExceeded);
+        }
+        for w in work.chunks_exact(WORK_BYTES) {
+            self.work.push(w.to_vec());
+        }
+        Ok(())
+    }
+
+    //make sure the index is valid before calling this.
+    pub fn remove_work(&mut self, index: usize) -> ProgramResult {
+        self.work.swap_remove(index);
+        Ok(())
+    }
+
+    pub fn get_work_free_space(&self) -> i32 {
+        return (MAX_COUNT - self.work.len()) as i32;
+    }
+}
+
+impl Sealed for PageState {}
+
+impl IsInitialized for PageState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for PageState {
+    const LEN: usize = PAGE_SPACE;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, PAGE_SPACE];
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            index,
+            bump,
+            vec_count,
+            vec_data_length,
+            vec_data
+        ) = mut_array_refs![output, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, PAGE_INDEX_BYTES, BUMP_BYTES, VEC_COUNT, VEC_DATA_LENGTH, VEC_DATA];
+        is_initialized[0] = self.is_initialized as u8;
+        account_type[0] = PAGE_ACCOUNT;
+        instance_id.copy_from_slice(self.instance_id.as_ref());
+        index[..].copy_from_slice(&self.index.to_le_bytes());
+        bump[0] = self.bump;
+        vec_count[0] = self.work.len() as u8;
+        let data = pack_vec_of_vec(&self.work);
+        let data_len = data.len();
+        if data_len <= VEC_DATA {
+            vec_data_length[..].copy_from_slice(&(data_len as u32).to_le_bytes());
+            sol_memcpy(vec_data, &data, data_len);
+        } else {
+            panic!("Not allowed to excede {} pow account limit.", MAX_COUNT);
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < PAGE_SPACE {
+            return Err(HihiError::DeserializationFailure.into());
+        }
+        let input = array_ref![input, 0, PAGE_SPACE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            account_type,
+            instance_id,
+            index,
+            bump,
+            vec_count,
+            _vec_data_length,
+            vec_data
+        ) = array_refs![input, INITIALIZED_BYTES, ACCOUNT_TYPE_BYTES, INSTANCE_ID_BYTES, PAGE_INDEX_BYTES, BUMP_BYTES, VEC_COUNT, VEC_DATA_LENGTH, VEC_DATA];
+
+        let is_init = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData)
+        };
+        if is_init && account_type[0] != PAGE_ACCOUNT {
+            return Err(HihiError::InvalidAccountType.into());
+        }
+
+        if vec_count[0] as usize > MAX_COUNT {
+            return Err(HihiError::InvalidWorkCount.into());
+        }
+
+        Ok(Self {
+            is_initialized:is_init,
+            instance_id:Pubkey::new_from_array(*instance_id),
+            index:u32::from_le_bytes(*index),
+            bump:bump[0],
+            work:unpack_vec_of_vec(vec_data, vec_count[0]),
+        })
+    }
+}
+
+/// Seed of the canonical instance of a mint, a PDA of `[INSTANCE_SEED, mint]`.
+pub const INSTANCE_SEED: &[u8] = b"instance";
+
+pub const REGISTRY_SEED: &[u8] = b"registry";
+
+pub const MAX_INSTANCES: usize = 256;
+pub const INSTANCE_COUNT_BYTES: usize = 2;
+pub const INSTANCES_BYTES: usize = INSTANCE_ID_BYTES * MAX_INSTANCES;
+pub const REGISTRY_SPACE: usize = INITIALIZED_BYTES + BUMP_BYTES + INSTANCE_COUNT_BYTES + INSTANCES_BYTES;
+
+/// (name, type, bytes) of every `RegistryState` field in pack order.
+pub const REGISTRY_LAYOUT: [(&str, &str, usize); 4] = [
+    ("is_initialized", "bool", INITIALIZED_BYTES),
+    ("bump", "u8", BUMP_BYTES),
+    ("instance_count", "u16", INSTANCE_COUNT_BYTES),
+    ("instances", "[publicKey; 256]", INSTANCES_BYTES),
+];
+
+/// Every registered instance of the program, a PDA of `[REGISTRY_SEED]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegistryState {
+    pub is_initialized: bool,
+    pub bump: u8,
+    pub instances: Vec<Pubkey>,
+}
+
+impl RegistryState {
+    pub fn add(&mut self, instance: &Pubkey) -> ProgramResult {
+        if self.instances.contains(instance) {
+            return Err(HihiError::AlreadyRegistered.into());
+        }
+        if self.instances.len() >= MAX_INSTANCES {
+            return Err(HihiError::RegistryFull.into());
+        }
+        self.instances.push(*instance);
+        Ok(())
+    }
+
+    /// Drops `instance`, keeping the order of the rest; false if it was never registered.
+    pub fn remove(&mut self, instance: &Pubkey) -> bool {
+        match self.instances.iter().position(|id| id == instance) {
+            Some(i) => {
+                self.instances.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Sealed for RegistryState {}
+
+impl IsInitialized for RegistryState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RegistryState {
+    const LEN: usize = REGISTRY_SPACE;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, REGISTRY_SPACE];
+        let (
+            is_initialized,
+            bump,
+            instance_count,
+            instances
+        ) = mut_array_refs![output, INITIALIZED_BYTES, BUMP_BYTES, INSTANCE_COUNT_BYTES, INSTANCES_BYTES];
+        is_initialized[0] = self.is_initialized as u8;
+        bump[0] = self.bump;
+        instance_count[..].copy_from_slice(&(self.instances.len() as u16).to_le_bytes());
+        for (i, chunk) in instances.chunks_exact_mut(INSTANCE_ID_BYTES).enumerate() {
+            match self.instances.get(i) {
+                Some(instance) => chunk.copy_from_slice(instance.as_ref()),
+                None => chunk.fill(0),
+            }
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < REGISTRY_SPACE {
+            return Err(HihiError::DeserializationFailure.into());
+        }
+        let input = array_ref![input, 0, REGISTRY_SPACE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump,
+            instance_count,
+            instances
+        ) = array_refs![input, INITIALIZED_BYTES, BUMP_BYTES, INSTANCE_COUNT_BYTES, INSTANCES_BYTES];
+
+        let is_init = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData)
+        };
+
+        let count = u16::from_le_bytes(*instance_count) as usize;
+        if count > MAX_INSTANCES {
+            return Err(HihiError::RegistryFull.into());
+        }
+
+        Ok(Self {
+            is_initialized:is_init,
+            bump:bump[0],
+            instances:instances
+                .chunks_exact(INSTANCE_ID_BYTES)
+                .take(count)
+                .map(Pubkey::new)
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,569 @@
+mod common;
+
+use common::{instance, Account};
+use solana_program::{
+    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_instruction,
+    system_program, sysvar,
+};
+use Hihi::{
+    accounts::{
+        self, ClaimAccounts, ClaimKind, ConfigureAccounts, CreatePageAccounts, WalletAccounts,
+        WithdrawAccounts,
+    },
+    error::HihiError,
+    processor::{create_program_account_instructions, Processor},
+    state::{
+        HihiState, MinerState, PageState, VestingState, MINER_SEED, PAGE_SPACE, STATE_SPACE,
+        VESTING_SEED,
+    },
+};
+
+struct Setup {
+    program_id: Pubkey,
+    instance: Account,
+    state: HihiState,
+    authority_id: Pubkey,
+}
+
+fn setup() -> Setup {
+    let program_id = Pubkey::new_unique();
+    let instance_id = Pubkey::new_unique();
+    let (authority_id, nonce) =
+        Pubkey::find_program_address(&[&instance_id.to_bytes()[..32]], &program_id);
+
+    let mut state = instance();
+    state.is_initialized = true;
+    state.nonce = nonce;
+    state.admin_one_id = Pubkey::new_unique();
+    state.admin_two_id = Pubkey::new_unique();
+    state.withdraw_id = Pubkey::new_unique();
+
+    let mut instance = Account::new(instance_id, false, program_id);
+    instance.data = vec![0; STATE_SPACE];
+    HihiState::pack(state.clone(), &mut instance.data).unwrap();
+
+    Setup {
+        program_id,
+        instance,
+        state,
+        authority_id,
+    }
+}
+
+fn error(error: HihiError) -> ProgramError {
+    error.into()
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey) -> Account {
+    let mut account = Account::new(Pubkey::new_unique(), false, spl_token::id());
+    account.data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        },
+        &mut account.data,
+    )
+    .unwrap();
+    account
+}
+
+fn page_account(setup: &Setup, index: u32) -> Account {
+    let (page_id, bump) = Processor::page_id(&setup.program_id, &setup.instance.key, index);
+    let mut page = Account::new(page_id, false, setup.program_id);
+    page.data = vec![0; PAGE_SPACE];
+    PageState::pack(
+        PageState {
+            is_initialized: true,
+            instance_id: setup.instance.key,
+            index,
+            bump,
+            work: vec![],
+        },
+        &mut page.data,
+    )
+    .unwrap();
+    page
+}
+
+fn miner_account(setup: &Setup, wallet_id: &Pubkey) -> Account {
+    let (miner_id, bump) = Processor::miner_id(&setup.program_id, &setup.instance.key, wallet_id);
+    let mut miner = Account::new(miner_id, false, setup.program_id);
+    miner.data = vec![0; MinerState::LEN];
+    MinerState::pack(
+        MinerState {
+            is_initialized: true,
+            instance_id: setup.instance.key,
+            wallet_id: *wallet_id,
+            bump,
+            ..MinerState::default()
+        },
+        &mut miner.data,
+    )
+    .unwrap();
+    miner
+}
+
+#[test]
+fn withdraw_checks_authority_and_system_program() {
+    let mut setup = setup();
+    let mut authority = Account::new(setup.authority_id, false, system_program::id());
+    let mut withdraw = Account::new(setup.state.withdraw_id, true, system_program::id());
+    let mut system = Account::new(system_program::id(), false, Pubkey::default());
+
+    let accounts = [
+        setup.instance.info(),
+        authority.info(),
+        withdraw.info(),
+        system.info(),
+    ];
+    assert!(WithdrawAccounts::parse(&setup.program_id, &accounts).is_ok());
+
+    let mut wrong = Account::new(Pubkey::new_unique(), false, system_program::id());
+    let accounts = [
+        setup.instance.info(),
+        authority.info(),
+        withdraw.info(),
+        wrong.info(),
+    ];
+    assert_eq!(
+        WithdrawAccounts::parse(&setup.program_id, &accounts).err(),
+        Some(error(HihiError::InvalidSystemProgram))
+    );
+
+    let accounts = [
+        setup.instance.info(),
+        wrong.info(),
+        withdraw.info(),
+        system.info(),
+    ];
+    assert_eq!(
+        WithdrawAccounts::parse(&setup.program_id, &accounts).err(),
+        Some(error(HihiError::InvalidAuthority))
+    );
+}
+
+#[test]
+fn configure_needs_both_admins() {
+    let mut setup = setup();
+    let mut admin_one = Account::new(setup.state.admin_one_id, true, system_program::id());
+    let mut admin_two = Account::new(setup.state.admin_two_id, false, system_program::id());
+
+    let accounts = [setup.instance.info(), admin_one.info(), admin_two.info()];
+    assert_eq!(
+        ConfigureAccounts::parse(&setup.program_id, &accounts).err(),
+        Some(error(HihiError::AdminTwoNotSigner))
+    );
+
+    let mut impostor = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let accounts = [setup.instance.info(), impostor.info(), admin_one.info()];
+    assert_eq!(
+        ConfigureAccounts::parse(&setup.program_id, &accounts).err(),
+        Some(error(HihiError::InvalidAdminOne))
+    );
+
+    setup.instance.writable = false;
+    admin_two.signer = true;
+    let accounts = [setup.instance.info(), admin_one.info(), admin_two.info()];
+    assert_eq!(
+        ConfigureAccounts::parse(&setup.program_id, &accounts).err(),
+        Some(error(HihiError::InstanceNotWritable))
+    );
+}
+
+#[test]
+fn limit_break_checks_its_extra_accounts() {
+    let mut setup = setup();
+    let mut token_program = Account::new(spl_token::id(), false, Pubkey::default());
+    let mut mint = Account::new(setup.state.token_mint_id, false, spl_token::id());
+    let mut authority = Account::new(setup.authority_id, false, system_program::id());
+    let mut claim = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let mut pool = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let mut to_token = token_account(&setup.state.token_mint_id, &Pubkey::new_unique());
+    let mut to_lamports = Account::new(Pubkey::new_unique(), false, system_program::id());
+    let mut wrong = Account::new(Pubkey::new_unique(), false, Pubkey::default());
+
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        to_lamports.info(),
+        wrong.info(),
+    ];
+    //a work claim reads its optional accounts right after the shared ones.
+    assert_eq!(
+        ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::Work).err(),
+        Some(error(HihiError::InvalidMinerAccount))
+    );
+    assert_eq!(
+        ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::LimitBreak).err(),
+        Some(ProgramError::NotEnoughAccountKeys)
+    );
+
+    let mut slot_hashes = Account::new(Pubkey::new_unique(), false, Pubkey::default());
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        to_lamports.info(),
+        wrong.info(),
+        slot_hashes.info(),
+    ];
+    assert_eq!(
+        ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::LimitBreak).err(),
+        Some(error(HihiError::InvalidSystemProgram))
+    );
+}
+
+#[test]
+fn wallet_account_must_be_its_program_address() {
+    let mut setup = setup();
+    let wallet_id = Pubkey::new_unique();
+    let (miner_id, _) = Pubkey::find_program_address(
+        &[
+            MINER_SEED,
+            &setup.instance.key.to_bytes(),
+            &wallet_id.to_bytes(),
+        ],
+        &setup.program_id,
+    );
+    let mut miner = Account::new(miner_id, false, system_program::id());
+    let mut wallet = Account::new(wallet_id, true, system_program::id());
+    let mut system = Account::new(system_program::id(), false, Pubkey::default());
+
+    let accounts = [
+        setup.instance.info(),
+        miner.info(),
+        wallet.info(),
+        system.info(),
+    ];
+    assert!(WalletAccounts::parse(&setup.program_id, &accounts, MINER_SEED).is_ok());
+
+    let (page_id, _) = Processor::page_id(&setup.program_id, &setup.instance.key, 0);
+    let mut page = Account::new(page_id, false, system_program::id());
+    let accounts = [
+        setup.instance.info(),
+        page.info(),
+        wallet.info(),
+        system.info(),
+    ];
+    assert_eq!(
+        WalletAccounts::parse(&setup.program_id, &accounts, MINER_SEED).err(),
+        Some(error(HihiError::InvalidProgramAddress))
+    );
+}
+
+#[test]
+fn vesting_accounts_need_a_writable_instance() {
+    let mut setup = setup();
+    let wallet_id = Pubkey::new_unique();
+    let vesting_id = Processor::vesting_id(&setup.program_id, &setup.instance.key, &wallet_id).0;
+    let mut vesting = Account::new(vesting_id, false, system_program::id());
+    let mut wallet = Account::new(wallet_id, true, system_program::id());
+    let mut system = Account::new(system_program::id(), false, Pubkey::default());
+
+    setup.instance.writable = false;
+    let accounts = [
+        setup.instance.info(),
+        vesting.info(),
+        wallet.info(),
+        system.info(),
+    ];
+    assert_eq!(
+        WalletAccounts::parse(&setup.program_id, &accounts, VESTING_SEED).err(),
+        Some(error(HihiError::InstanceNotWritable))
+    );
+
+    setup.instance.writable = true;
+    let accounts = [
+        setup.instance.info(),
+        vesting.info(),
+        wallet.info(),
+        system.info(),
+    ];
+    assert!(WalletAccounts::parse(&setup.program_id, &accounts, VESTING_SEED).is_ok());
+}
+
+#[test]
+fn vesting_accounts_are_checked_against_their_program_address() {
+    let setup = setup();
+    let instance_id = setup.instance.key;
+    let wallet_id = Pubkey::new_unique();
+    let (vesting_id, bump) = Processor::vesting_id(&setup.program_id, &instance_id, &wallet_id);
+    let mut vesting = Account::new(vesting_id, false, setup.program_id);
+    vesting.data = vec![0; VestingState::LEN];
+    VestingState::pack(
+        VestingState {
+            is_initialized: true,
+            instance_id,
+            wallet_id,
+            bump,
+            ..VestingState::default()
+        },
+        &mut vesting.data,
+    )
+    .unwrap();
+    assert!(
+        accounts::vesting(&setup.program_id, &instance_id, &wallet_id, &vesting.info()).is_ok()
+    );
+
+    //the same state at any other address of the program is not the wallet's vesting account.
+    vesting.key = Pubkey::new_unique();
+    assert_eq!(
+        accounts::vesting(&setup.program_id, &instance_id, &wallet_id, &vesting.info()).err(),
+        Some(error(HihiError::InvalidVestingAccount))
+    );
+
+    vesting.key = vesting_id;
+    vesting.writable = false;
+    assert_eq!(
+        accounts::vesting(&setup.program_id, &instance_id, &wallet_id, &vesting.info()).err(),
+        Some(error(HihiError::InvalidVestingAccount))
+    );
+}
+
+#[test]
+fn miner_accounts_are_checked_against_their_program_address() {
+    let setup = setup();
+    let instance_id = setup.instance.key;
+    let wallet_id = Pubkey::new_unique();
+    let (miner_id, bump) = Processor::miner_id(&setup.program_id, &instance_id, &wallet_id);
+    let miner_state = MinerState {
+        is_initialized: true,
+        instance_id,
+        wallet_id,
+        bump,
+        ..MinerState::default()
+    };
+    let mut miner = Account::new(miner_id, false, setup.program_id);
+    miner.data = vec![0; MinerState::LEN];
+    MinerState::pack(miner_state.clone(), &mut miner.data).unwrap();
+    assert_eq!(
+        accounts::miner(&setup.program_id, &instance_id, &wallet_id, &miner.info()),
+        Ok(miner_state)
+    );
+
+    miner.key = Pubkey::new_unique();
+    assert_eq!(
+        accounts::miner(&setup.program_id, &instance_id, &wallet_id, &miner.info()).err(),
+        Some(error(HihiError::InvalidMinerAccount))
+    );
+
+    //the wallet's vesting account, cut to the miner size, is still no miner account.
+    let (vesting_id, bump) = Processor::vesting_id(&setup.program_id, &instance_id, &wallet_id);
+    let mut data = vec![0; VestingState::LEN];
+    VestingState::pack(
+        VestingState {
+            is_initialized: true,
+            instance_id,
+            wallet_id,
+            bump,
+            ..VestingState::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    let mut vesting = Account::new(vesting_id, false, setup.program_id);
+    vesting.data = data[..MinerState::LEN].to_vec();
+    assert_eq!(
+        accounts::miner(&setup.program_id, &instance_id, &wallet_id, &vesting.info()).err(),
+        Some(error(HihiError::InvalidAccountType))
+    );
+}
+
+#[test]
+fn work_claims_check_their_miner_and_page() {
+    let mut setup = setup();
+    let wallet_id = Pubkey::new_unique();
+    let mut token_program = Account::new(spl_token::id(), false, Pubkey::default());
+    let mut mint = Account::new(setup.state.token_mint_id, false, spl_token::id());
+    let mut authority = Account::new(setup.authority_id, false, system_program::id());
+    let mut claim = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let mut pool = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let mut to_token = token_account(&setup.state.token_mint_id, &wallet_id);
+    let mut miner = miner_account(&setup, &wallet_id);
+    let mut page = page_account(&setup, 0);
+
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        miner.info(),
+        page.info(),
+    ];
+    let parsed = ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::Work).unwrap();
+    assert_eq!(parsed.miner.unwrap().1.wallet_id, wallet_id);
+    assert_eq!(parsed.page.unwrap().1.index, 0);
+
+    //the miner account of another wallet.
+    let mut other = miner_account(&setup, &Pubkey::new_unique());
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        other.info(),
+        page.info(),
+    ];
+    assert_eq!(
+        ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::Work).err(),
+        Some(error(HihiError::InvalidMinerAccount))
+    );
+
+    //page 0 stored at the address of page 1.
+    page.key = Processor::page_id(&setup.program_id, &setup.instance.key, 1).0;
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        miner.info(),
+        page.info(),
+    ];
+    assert_eq!(
+        ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::Work).err(),
+        Some(error(HihiError::InvalidPageAccount))
+    );
+
+    let mut page = page_account(&setup, 0);
+    page.writable = false;
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        miner.info(),
+        page.info(),
+    ];
+    assert_eq!(
+        ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::Work).err(),
+        Some(error(HihiError::InvalidPageAccount))
+    );
+}
+
+#[test]
+fn vesting_limit_breaks_need_the_vesting_account() {
+    let mut setup = setup();
+    setup.state.vesting_epochs = 4;
+    HihiState::pack(setup.state.clone(), &mut setup.instance.data).unwrap();
+    let wallet_id = Pubkey::new_unique();
+    let mut token_program = Account::new(spl_token::id(), false, Pubkey::default());
+    let mut mint = Account::new(setup.state.token_mint_id, false, spl_token::id());
+    let mut authority = Account::new(setup.authority_id, false, system_program::id());
+    let mut claim = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let mut pool = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let mut to_token = token_account(&setup.state.token_mint_id, &wallet_id);
+    let mut to_lamports = Account::new(wallet_id, false, system_program::id());
+    let mut system = Account::new(system_program::id(), false, Pubkey::default());
+    let mut slot_hashes = Account::new(sysvar::slot_hashes::id(), false, Pubkey::default());
+    let mut program = Account::new(setup.program_id, false, Pubkey::default());
+
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        to_lamports.info(),
+        system.info(),
+        slot_hashes.info(),
+        program.info(),
+    ];
+    assert_eq!(
+        ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::LimitBreak).err(),
+        Some(error(HihiError::InvalidVestingAccount))
+    );
+
+    let (vesting_id, bump) =
+        Processor::vesting_id(&setup.program_id, &setup.instance.key, &wallet_id);
+    let mut vesting = Account::new(vesting_id, false, setup.program_id);
+    vesting.data = vec![0; VestingState::LEN];
+    VestingState::pack(
+        VestingState {
+            is_initialized: true,
+            instance_id: setup.instance.key,
+            wallet_id,
+            bump,
+            ..VestingState::default()
+        },
+        &mut vesting.data,
+    )
+    .unwrap();
+    let accounts = [
+        setup.instance.info(),
+        token_program.info(),
+        mint.info(),
+        authority.info(),
+        claim.info(),
+        pool.info(),
+        to_token.info(),
+        to_lamports.info(),
+        system.info(),
+        slot_hashes.info(),
+        program.info(),
+        vesting.info(),
+    ];
+    let parsed = ClaimAccounts::parse(&setup.program_id, &accounts, ClaimKind::LimitBreak).unwrap();
+    assert!(parsed.miner.is_none());
+    assert_eq!(parsed.vesting.unwrap().1.wallet_id, wallet_id);
+}
+
+#[test]
+fn pre_funded_page_address_can_still_be_created() {
+    let mut setup = setup();
+    let (page_id, _) = Processor::page_id(&setup.program_id, &setup.instance.key, 0);
+    //lamports sent to the next page address before anyone creates the page.
+    let mut page = Account::new(page_id, false, system_program::id());
+    page.lamports = 1;
+    let mut payer = Account::new(Pubkey::new_unique(), true, system_program::id());
+    let mut system = Account::new(system_program::id(), false, Pubkey::default());
+
+    let accounts = [
+        setup.instance.info(),
+        page.info(),
+        payer.info(),
+        system.info(),
+    ];
+    let parsed = CreatePageAccounts::parse(&setup.program_id, &accounts).unwrap();
+    let minimum = 10_000_000;
+    assert_eq!(
+        create_program_account_instructions(
+            &setup.program_id,
+            parsed.payer.key,
+            parsed.page.key,
+            parsed.page.lamports(),
+            minimum,
+            PAGE_SPACE,
+        ),
+        vec![
+            system_instruction::transfer(parsed.payer.key, &page_id, minimum - 1),
+            system_instruction::allocate(&page_id, PAGE_SPACE as u64),
+            system_instruction::assign(&page_id, &setup.program_id),
+        ]
+    );
+}
@@ -0,0 +1,372 @@
+mod common;
+
+use common::Account;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_token::{instruction::TokenInstruction, state::AccountState};
+use std::{cell::RefCell, sync::Once};
+use Hihi::{
+    error::HihiError,
+    processor::{close_account, is_closable, Processor, LB_DIFF, MAX_DIFF},
+    state::{
+        HihiState, PageState, PuzzleBatch, RegistryState, VestingState, REGISTRY_SPACE,
+        STATE_SPACE, WORK_BYTES,
+    },
+};
+
+fn instance() -> HihiState {
+    let mut instance = common::instance();
+    instance.difficulty = 2;
+    instance
+}
+
+fn batch(count: u8) -> PuzzleBatch {
+    PuzzleBatch {
+        seed: [1; 32],
+        count,
+        reward: 100,
+        doubled: 0,
+        difficulty: 2,
+        claimed: 0,
+    }
+}
+
+thread_local! {
+    static INVOKED: RefCell<Vec<Instruction>> = RefCell::new(Vec::new());
+}
+
+/// Keeps the instructions a program invokes on this thread instead of dropping them.
+struct RecordingStubs;
+
+impl SyscallStubs for RecordingStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOKED.with(|invoked| invoked.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+}
+
+/// Run `f` and return the token account and amount of every `MintTo` it invoked.
+fn record_mints<F: FnOnce()>(f: F) -> Vec<(Pubkey, u64)> {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(RecordingStubs));
+    });
+    INVOKED.with(|invoked| invoked.borrow_mut().clear());
+    f();
+    INVOKED.with(|invoked| {
+        invoked
+            .borrow()
+            .iter()
+            .filter(|ix| ix.program_id == spl_token::id())
+            .filter_map(|ix| match TokenInstruction::unpack(&ix.data) {
+                Ok(TokenInstruction::MintTo { amount }) => Some((ix.accounts[1].pubkey, amount)),
+                _ => None,
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn only_finished_or_empty_instances_close() {
+    let mut instance = instance();
+    let mut page = PageState::default();
+    assert!(is_closable(&instance, &[page.clone()]));
+
+    instance.work_cached = 1;
+    assert!(!is_closable(&instance, &[]));
+    instance.work_cached = 0;
+
+    instance.batches.push(batch(2));
+    assert!(!is_closable(&instance, &[]));
+    instance.batches[0].set_claimed(0);
+    instance.batches[0].set_claimed(1);
+    assert!(is_closable(&instance, &[]));
+
+    page.add_work(&[1; WORK_BYTES]).unwrap();
+    assert!(!is_closable(&instance, &[page.clone()]));
+    instance.add_work(&[1; WORK_BYTES]).unwrap();
+    assert!(!is_closable(&instance, &[]));
+
+    //the game is over, leftover puzzles no longer matter.
+    instance.difficulty = MAX_DIFF - LB_DIFF + 1;
+    assert!(is_closable(&instance, &[page]));
+}
+
+#[test]
+fn closed_accounts_are_emptied_into_the_receiver() {
+    let owner = Pubkey::new_unique();
+    let (key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut lamports, mut to_lamports) = (5000, 7);
+    let (mut data, mut to_data) = (vec![9; 64], vec![]);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    let to_info = AccountInfo::new(
+        &to_key,
+        false,
+        true,
+        &mut to_lamports,
+        &mut to_data,
+        &owner,
+        false,
+        0,
+    );
+    close_account(&info, &to_info);
+    assert_eq!(info.lamports(), 0);
+    assert_eq!(to_info.lamports(), 5007);
+    assert!(info.data.borrow().iter().all(|b| *b == 0));
+}
+
+#[test]
+fn closing_releases_all_unvested_tokens() {
+    let mut vesting = VestingState::default();
+    vesting.grant(1000, 10, 40);
+    assert_eq!(vesting.release(20), 250);
+    vesting.grant(100, 20, 40);
+    assert_eq!(vesting.release(u64::MAX), 850);
+    assert_eq!(vesting.claimable(u64::MAX), 0);
+}
+
+struct Setup {
+    program_id: Pubkey,
+    state: HihiState,
+    instance: Account,
+    admin_one: Account,
+    admin_two: Account,
+    token_program: Account,
+    token_mint: Account,
+    authority: Account,
+    new_mint_authority: Account,
+    withdraw: Account,
+    system: Account,
+    registry: Account,
+    /// Vesting and token account pairs of the instance.
+    vestings: Vec<(Account, Account)>,
+}
+
+fn token_account(owner: &Pubkey, mint: &Pubkey) -> Account {
+    let mut account = Account::new(Pubkey::new_unique(), false, spl_token::id());
+    account.data = vec![0; spl_token::state::Account::LEN];
+    let token = spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        state: AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    };
+    spl_token::state::Account::pack(token, &mut account.data).unwrap();
+    account
+}
+
+/// A finished instance with `vestings` vesting accounts, listed in a registry next to `others`.
+fn setup(others: &[Pubkey], vestings: usize) -> Setup {
+    let program_id = Pubkey::new_unique();
+    let instance_id = Pubkey::new_unique();
+    let (authority_id, nonce) =
+        Pubkey::find_program_address(&[&instance_id.to_bytes()[..32]], &program_id);
+
+    let mut state = instance();
+    state.is_initialized = true;
+    state.nonce = nonce;
+    state.admin_one_id = Pubkey::new_unique();
+    state.admin_two_id = Pubkey::new_unique();
+    state.withdraw_id = Pubkey::new_unique();
+    state.vesting_count = vestings as u32;
+    let mut instance = Account::new(instance_id, false, program_id);
+    instance.lamports = 1_000;
+    instance.data = vec![0; STATE_SPACE];
+
+    let mut registry = RegistryState {
+        is_initialized: true,
+        bump: 255,
+        instances: Vec::new(),
+    };
+    registry.add(&instance_id).unwrap();
+    for other in others {
+        registry.add(other).unwrap();
+    }
+    let (registry_id, _) = Processor::registry_id(&program_id);
+    let mut registry_account = Account::new(registry_id, false, program_id);
+    registry_account.data = vec![0; REGISTRY_SPACE];
+    RegistryState::pack(registry, &mut registry_account.data).unwrap();
+
+    let vestings = (0..vestings)
+        .map(|_| {
+            let wallet_id = Pubkey::new_unique();
+            let (vesting_id, bump) = Processor::vesting_id(&program_id, &instance_id, &wallet_id);
+            let mut vesting_state = VestingState {
+                is_initialized: true,
+                instance_id,
+                wallet_id,
+                bump,
+                ..VestingState::default()
+            };
+            vesting_state.grant(1000, 10, 40);
+            let mut vesting = Account::new(vesting_id, false, program_id);
+            vesting.data = vec![0; VestingState::LEN];
+            VestingState::pack(vesting_state, &mut vesting.data).unwrap();
+            (vesting, token_account(&wallet_id, &state.token_mint_id))
+        })
+        .collect::<Vec<_>>();
+
+    let mut authority = Account::new(authority_id, false, system_program::id());
+    authority.lamports = 500;
+    Setup {
+        program_id,
+        instance,
+        admin_one: Account::new(state.admin_one_id, true, system_program::id()),
+        admin_two: Account::new(state.admin_two_id, true, system_program::id()),
+        token_program: Account::new(spl_token::id(), false, Pubkey::default()),
+        token_mint: Account::new(state.token_mint_id, false, spl_token::id()),
+        authority,
+        new_mint_authority: Account::new(Pubkey::new_unique(), false, system_program::id()),
+        withdraw: Account::new(state.withdraw_id, false, system_program::id()),
+        system: Account::new(system_program::id(), false, Pubkey::default()),
+        registry: registry_account,
+        vestings,
+        state,
+    }
+}
+
+/// Close with the vesting pairs at `vestings`, after writing `setup.state` to the instance.
+fn close(setup: &mut Setup, vestings: &[usize]) -> Result<(), ProgramError> {
+    setup.state.pack_into_slice(&mut setup.instance.data);
+    let pairs: Vec<(Pubkey, Pubkey)> = vestings
+        .iter()
+        .map(|i| (setup.vestings[*i].0.key, setup.vestings[*i].1.key))
+        .collect();
+    let data = Hihi::instruction::close(
+        &setup.program_id,
+        &setup.instance.key,
+        &setup.admin_one.key,
+        &setup.admin_two.key,
+        &setup.token_program.key,
+        &setup.token_mint.key,
+        &setup.authority.key,
+        &setup.new_mint_authority.key,
+        &setup.withdraw.key,
+        &setup.registry.key,
+        &[],
+        &pairs,
+    )
+    .unwrap()
+    .data;
+    let vesting_infos: Vec<(AccountInfo, AccountInfo)> = setup
+        .vestings
+        .iter_mut()
+        .map(|(vesting, token)| (vesting.info(), token.info()))
+        .collect();
+    let mut accounts = vec![
+        setup.instance.info(),
+        setup.admin_one.info(),
+        setup.admin_two.info(),
+        setup.token_program.info(),
+        setup.token_mint.info(),
+        setup.authority.info(),
+        setup.new_mint_authority.info(),
+        setup.withdraw.info(),
+        setup.system.info(),
+        setup.registry.info(),
+    ];
+    for i in vestings {
+        accounts.push(vesting_infos[*i].0.clone());
+        accounts.push(vesting_infos[*i].1.clone());
+    }
+    Processor::process(&setup.program_id, &accounts, &data)
+}
+
+#[test]
+fn closed_instances_leave_the_registry() {
+    let other = Pubkey::new_unique();
+    let mut setup = setup(&[other], 0);
+    close(&mut setup, &[]).unwrap();
+    assert_eq!(setup.instance.lamports, 0);
+    assert_eq!(setup.withdraw.lamports, 1_000);
+
+    let registry = RegistryState::unpack(&setup.registry.data).unwrap();
+    assert_eq!(registry.instances, vec![other]);
+}
+
+#[test]
+fn close_works_without_a_registry() {
+    let mut setup = setup(&[], 0);
+    setup.registry.owner = system_program::id();
+    setup.registry.data = Vec::new();
+    close(&mut setup, &[]).unwrap();
+    assert_eq!(setup.instance.lamports, 0);
+}
+
+#[test]
+fn close_rejects_another_registry() {
+    let mut other = setup(&[], 0);
+    other.registry.key = Pubkey::new_unique();
+    assert_eq!(
+        close(&mut other, &[]),
+        Err(HihiError::InvalidRegistry.into())
+    );
+
+    let mut read_only = setup(&[], 0);
+    read_only.registry.writable = false;
+    assert_eq!(
+        close(&mut read_only, &[]),
+        Err(HihiError::InvalidRegistry.into())
+    );
+    assert_eq!(read_only.instance.lamports, 1_000);
+}
+
+#[test]
+fn close_settles_every_vesting_account() {
+    let mut setup = setup(&[], 2);
+    assert_eq!(
+        close(&mut setup, &[0]),
+        Err(HihiError::VestingsNotSettled.into())
+    );
+    //passing one twice does not stand in for the other.
+    assert_eq!(
+        close(&mut setup, &[0, 0]),
+        Err(HihiError::InvalidVestingAccount.into())
+    );
+    assert_eq!(setup.instance.lamports, 1_000);
+
+    close(&mut setup, &[1, 0]).unwrap();
+    assert_eq!(setup.instance.lamports, 0);
+    for (vesting, _) in setup.vestings.iter() {
+        let vesting = VestingState::unpack(&vesting.data).unwrap();
+        assert_eq!(vesting.released, 1000);
+        assert_eq!(vesting.claimable(u64::MAX), 0);
+    }
+}
+
+#[test]
+fn breaches_without_puzzles_are_left_unminted() {
+    //the game is over, the cached breaches will never get their puzzles.
+    let mut setup = setup(&[], 1);
+    setup.state.difficulty = MAX_DIFF - LB_DIFF + 1;
+    setup.state.breach_count = 30;
+    setup.state.work_cached = 3;
+    setup.state.token_doubles = 2;
+
+    //only the vesting wallet is paid, nothing goes to the admins or the withdraw key.
+    let mints = record_mints(|| close(&mut setup, &[0]).unwrap());
+    assert_eq!(mints, vec![(setup.vestings[0].1.key, 1000)]);
+    assert_eq!(setup.instance.lamports, 0);
+}
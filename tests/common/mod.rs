@@ -0,0 +1,48 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use solana_program::{account_info::AccountInfo, program_pack::Pack, pubkey::Pubkey};
+use Hihi::state::{HihiState, STATE_SPACE};
+
+/// An uninitialized instance of a fresh mint, every other field zeroed.
+pub fn instance() -> HihiState {
+    let mut instance = HihiState::unpack_unchecked(&[0; STATE_SPACE]).unwrap();
+    instance.token_mint_id = Pubkey::new_unique();
+    instance
+}
+
+/// An owned account to hand out as `AccountInfo`.
+pub struct Account {
+    pub key: Pubkey,
+    pub signer: bool,
+    pub writable: bool,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+}
+
+impl Account {
+    pub fn new(key: Pubkey, signer: bool, owner: Pubkey) -> Self {
+        Self {
+            key,
+            signer,
+            writable: true,
+            lamports: 0,
+            data: Vec::new(),
+            owner,
+        }
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.signer,
+            self.writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
@@ -0,0 +1,379 @@
+//! Compute unit budgets of the heaviest instruction paths. These only run the BPF build, use
+//! `cargo test-bpf` (see the README); a missing `Hihi.so` fails the run instead of falling back
+//! to native code. Each case bisects the bank's compute budget for the fewest units it needs and
+//! prints them, run with `--nocapture` to see the report.
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use solana_program::{
+    clock::Clock, program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    transport::TransportError,
+};
+use std::future::Future;
+use Hihi::{
+    instruction,
+    processor::{breaches_cost, current_price, update_window, LB_DIFF_INCREASE, MAX_BREACHES},
+    state::{
+        HihiState, PuzzleBatch, HEAP_COUNT, LB_BYTES, MAX_BATCHES, MAX_BATCH_PUZZLES, STATE_SPACE,
+        WORK_BYTES,
+    },
+};
+
+/// Units one instruction may use, the runtime's per instruction limit.
+const COMPUTE_BUDGET: u64 = 200_000;
+
+/// Slot the bank is warped to, past the first breach window and epoch.
+const WARP_SLOT: u64 = 100_000;
+
+struct Setup {
+    program_id: Pubkey,
+    instance_id: Pubkey,
+    authority_id: Pubkey,
+    mint_id: Pubkey,
+    token_id: Pubkey,
+}
+
+/// A distinct puzzle without magic, so solved by any key, for every heap slot.
+fn full_heap() -> Vec<u8> {
+    let mut work = vec![0; HEAP_COUNT * WORK_BYTES];
+    for (i, puzzle) in work.chunks_mut(WORK_BYTES).enumerate() {
+        puzzle[0] = 1;
+        puzzle[1] = i as u8;
+    }
+    work
+}
+
+/// Every batch slot taken by a batch of blank puzzles.
+fn full_batches() -> Vec<PuzzleBatch> {
+    (0..MAX_BATCHES)
+        .map(|i| PuzzleBatch {
+            seed: [i as u8; 32],
+            count: MAX_BATCH_PUZZLES as u8,
+            reward: 1,
+            doubled: 0,
+            difficulty: 0,
+            claimed: 0,
+        })
+        .collect()
+}
+
+fn instance(mint_id: &Pubkey, nonce: u8) -> HihiState {
+    let mut instance = common::instance();
+    instance.is_initialized = true;
+    instance.token_mint_id = *mint_id;
+    instance.nonce = nonce;
+    instance.difficulty = 2;
+    let mut limit_break = vec![0; LB_BYTES];
+    limit_break[0] = 1;
+    instance.limit_break = limit_break;
+    instance
+}
+
+fn program_account(owner: &Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Start a bank running the BPF build under `budget` units, holding `instance` (as altered by
+/// `edit`), its mint and a token account of its own, warped to `WARP_SLOT`.
+async fn start(
+    budget: u64,
+    edit: impl FnOnce(&mut HihiState),
+    authority_lamports: u64,
+) -> (ProgramTestContext, Setup) {
+    let program_id = Pubkey::new_unique();
+    let instance_id = Pubkey::new_unique();
+    let mint_id = Pubkey::new_unique();
+    let token_id = Pubkey::new_unique();
+    let (authority_id, nonce) =
+        Pubkey::find_program_address(&[&instance_id.to_bytes()[..32]], &program_id);
+
+    // No native processor, so a missing shared object panics rather than running natively.
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(true);
+    program_test.add_program("Hihi", program_id, None);
+    program_test.set_bpf_compute_max_units(budget);
+
+    let mut state = instance(&mint_id, nonce);
+    edit(&mut state);
+    let mut data = vec![0; STATE_SPACE];
+    HihiState::pack(state, &mut data).unwrap();
+    program_test.add_account(instance_id, program_account(&program_id, data));
+
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(
+        spl_token::state::Mint {
+            mint_authority: COption::Some(authority_id),
+            supply: 0,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        &mut data,
+    )
+    .unwrap();
+    program_test.add_account(mint_id, program_account(&spl_token::id(), data));
+
+    if authority_lamports > 0 {
+        program_test.add_account(
+            authority_id,
+            Account {
+                lamports: authority_lamports,
+                ..Account::default()
+            },
+        );
+    }
+
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            mint: mint_id,
+            owner: Pubkey::new_unique(),
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    program_test.add_account(token_id, program_account(&spl_token::id(), data));
+
+    let mut context = program_test.start_with_context().await;
+    context.warp_to_slot(WARP_SLOT).unwrap();
+
+    (
+        context,
+        Setup {
+            program_id,
+            instance_id,
+            authority_id,
+            mint_id,
+            token_id,
+        },
+    )
+}
+
+async fn run(
+    context: &mut ProgramTestContext,
+    ix: solana_program::instruction::Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransportError> {
+    let blockhash = context.banks_client.get_recent_blockhash().await.unwrap();
+    let mut all = vec![&context.payer];
+    all.extend_from_slice(signers);
+    let tx =
+        Transaction::new_signed_with_payer(&[ix], Some(&context.payer.pubkey()), &all, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+/// Fewest units `case` succeeds with, bisected between zero and `COMPUTE_BUDGET`. Panics with
+/// the error if the case fails under the full budget.
+async fn measure<F, Fut>(name: &str, case: F) -> u64
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<(), TransportError>>,
+{
+    if let Err(err) = case(COMPUTE_BUDGET).await {
+        panic!("{} fails within {} units: {:?}", name, COMPUTE_BUDGET, err);
+    }
+    let (mut low, mut high) = (0, COMPUTE_BUDGET);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if case(mid).await.is_ok() {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    println!("{}: {} of {} compute units", name, high, COMPUTE_BUDGET);
+    high
+}
+
+/// Lamports buying `MAX_BREACHES` breaches once the breach window has rolled over.
+async fn max_breach_lamports(context: &mut ProgramTestContext, instance: &HihiState) -> u64 {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let mut instance = instance.clone();
+    update_window(&mut instance, &clock);
+    instance.breach_price = current_price(&instance);
+    breaches_cost(&instance, clock.slot, MAX_BREACHES)
+}
+
+/// Ten breaches with every batch taken, so each one hashes a puzzle into the heap.
+async fn breach_with_max_puzzles(budget: u64) -> Result<(), TransportError> {
+    let edit = |instance: &mut HihiState| instance.batches = full_batches();
+    let mut state = instance(&Pubkey::default(), 0);
+    edit(&mut state);
+    let (mut context, setup) = start(budget, edit, 0).await;
+    let lamports = max_breach_lamports(&mut context, &state).await;
+
+    let ix = instruction::breach(
+        &setup.program_id,
+        &setup.instance_id,
+        &spl_token::id(),
+        &setup.mint_id,
+        &setup.authority_id,
+        &setup.token_id,
+        &context.payer.pubkey(),
+        None,
+        &[],
+        lamports,
+    )
+    .unwrap();
+    run(&mut context, ix, &[]).await
+}
+
+/// Surge pricing buys breaches one by one.
+async fn breach_with_surge(budget: u64) -> Result<(), TransportError> {
+    let edit = |instance: &mut HihiState| {
+        instance.batches = full_batches();
+        instance.surge_bps = 500;
+    };
+    let mut state = instance(&Pubkey::default(), 0);
+    edit(&mut state);
+    let (mut context, setup) = start(budget, edit, 0).await;
+    let lamports = max_breach_lamports(&mut context, &state).await;
+
+    let ix = instruction::breach(
+        &setup.program_id,
+        &setup.instance_id,
+        &spl_token::id(),
+        &setup.mint_id,
+        &setup.authority_id,
+        &setup.token_id,
+        &context.payer.pubkey(),
+        None,
+        &[],
+        lamports,
+    )
+    .unwrap();
+    run(&mut context, ix, &[]).await
+}
+
+/// Claiming the last puzzle of a full heap.
+async fn claim_from_full_heap(budget: u64) -> Result<(), TransportError> {
+    let work = full_heap();
+    let last = work[(HEAP_COUNT - 1) * WORK_BYTES..].to_vec();
+    let (mut context, setup) =
+        start(budget, move |instance| instance.add_work(&work).unwrap(), 0).await;
+    let claim = Keypair::new();
+    let pool = Keypair::new();
+
+    let mut puzzle = [0; WORK_BYTES];
+    puzzle.copy_from_slice(&last);
+    let ix = instruction::claim(
+        &setup.program_id,
+        &setup.instance_id,
+        &spl_token::id(),
+        &setup.mint_id,
+        &setup.authority_id,
+        &claim.pubkey(),
+        &pool.pubkey(),
+        &setup.token_id,
+        None,
+        None,
+        puzzle,
+    )
+    .unwrap();
+    run(&mut context, ix, &[&claim, &pool]).await
+}
+
+/// Claiming the last puzzle of the last batch.
+async fn claim_puzzle_from_full_batches(budget: u64) -> Result<(), TransportError> {
+    let (mut context, setup) = start(budget, |instance| instance.batches = full_batches(), 0).await;
+    let claim = Keypair::new();
+    let pool = Keypair::new();
+
+    let ix = instruction::claim_puzzle(
+        &setup.program_id,
+        &setup.instance_id,
+        &spl_token::id(),
+        &setup.mint_id,
+        &setup.authority_id,
+        &claim.pubkey(),
+        &pool.pubkey(),
+        &setup.token_id,
+        None,
+        [MAX_BATCHES as u8 - 1; 32],
+        MAX_BATCH_PUZZLES as u8 - 1,
+    )
+    .unwrap();
+    run(&mut context, ix, &[&claim, &pool]).await
+}
+
+/// A limit break rolling the epoch over, raising the difficulty and paying out the reserve.
+async fn limit_break_with_epoch_payout(budget: u64) -> Result<(), TransportError> {
+    let reserve = 10_000_000_000;
+    let (mut context, setup) = start(
+        budget,
+        |instance| {
+            instance.lamports = reserve;
+            instance.limit_breaks_this_epoch = LB_DIFF_INCREASE as u32 + 1;
+            instance.batches = full_batches();
+            instance.add_work(&full_heap()).unwrap();
+        },
+        reserve,
+    )
+    .await;
+    let claim = Keypair::new();
+    let pool = Keypair::new();
+
+    let ix = instruction::limit_break(
+        &setup.program_id,
+        &setup.instance_id,
+        &spl_token::id(),
+        &setup.mint_id,
+        &setup.authority_id,
+        &setup.token_id,
+        &context.payer.pubkey(),
+        &claim.pubkey(),
+        &pool.pubkey(),
+        None,
+        None,
+    )
+    .unwrap();
+    run(&mut context, ix, &[&claim, &pool]).await
+}
+
+#[tokio::test]
+async fn breach_with_max_puzzles_units() {
+    measure("breach with max puzzles", breach_with_max_puzzles).await;
+}
+
+#[tokio::test]
+async fn breach_with_surge_units() {
+    measure("breach with surge", breach_with_surge).await;
+}
+
+#[tokio::test]
+async fn claim_from_full_heap_units() {
+    measure("claim from full heap", claim_from_full_heap).await;
+}
+
+#[tokio::test]
+async fn claim_puzzle_from_full_batches_units() {
+    measure(
+        "claim puzzle from full batches",
+        claim_puzzle_from_full_batches,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn limit_break_with_epoch_payout_units() {
+    measure(
+        "limit break with epoch payout",
+        limit_break_with_epoch_payout,
+    )
+    .await;
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use Hihi::{
+    processor::{breach_base_tokens, calculate_tokens, halving_tokens, project_emission},
+    state::HihiState,
+};
+
+fn instance(halving_epochs: u64, start_epoch: u64) -> HihiState {
+    let mut instance = common::instance();
+    instance.halving_epochs = halving_epochs;
+    instance.start_epoch = start_epoch;
+    instance
+}
+
+#[test]
+fn rewards_halve_every_period() {
+    assert_eq!(halving_tokens(0, 10), 100);
+    assert_eq!(halving_tokens(9, 10), 100);
+    assert_eq!(halving_tokens(10, 10), 50);
+    assert_eq!(halving_tokens(25, 10), 25);
+    assert_eq!(halving_tokens(69, 10), 1);
+    assert_eq!(halving_tokens(70, 10), 0);
+    assert_eq!(halving_tokens(u64::MAX, 10), 0);
+}
+
+#[test]
+fn halving_counts_from_the_start_epoch_and_ignores_breaches() {
+    let mut halving = instance(10, 300);
+    halving.breach_count = 200_000;
+    assert_eq!(breach_base_tokens(&halving, 300), 100);
+    assert_eq!(breach_base_tokens(&halving, 320), 25);
+
+    let mut tiers = instance(0, 300);
+    tiers.breach_count = 200_000;
+    assert_eq!(breach_base_tokens(&tiers, 300), calculate_tokens(200_000));
+    assert_eq!(breach_base_tokens(&tiers, 1_000), 10);
+}
+
+#[test]
+fn projections_of_both_schedules() {
+    //1000 breaches an epoch, the first tier lasts one epoch.
+    let tiers = project_emission(0, 0, 0, 1000, 200);
+    let halving = project_emission(50, 0, 0, 1000, 200);
+
+    assert_eq!(tiers[0], 2 * 100 * 1000);
+    assert_eq!(tiers[1], 2 * 50 * 1000);
+    assert_eq!(tiers[10], 2 * 25 * 1000);
+    assert_eq!(tiers[199], 2 * 10 * 1000);
+    assert_eq!(halving[49], 2 * 100 * 1000);
+    assert_eq!(halving[50], 2 * 50 * 1000);
+    assert_eq!(halving[199], 2 * 12 * 1000);
+
+    //the halving schedule front loads for the first period and then falls below the tiers.
+    let total = |v: &[u64]| v.iter().sum::<u64>();
+    assert!(total(&halving[..100]) > total(&tiers[..100]));
+    let late = project_emission(50, 0, 400, 1000, 100);
+    let late_tiers = project_emission(0, 400_000, 400, 1000, 100);
+    assert!(late.iter().all(|e| *e == 0));
+    assert!(late_tiers.iter().all(|e| *e == 2 * 10 * 1000));
+}
+
+#[test]
+fn halving_emission_is_bounded() {
+    //100 + 50 + ... + 1 per breach per period, so never more than 2 * 200 per breach a period.
+    let emission = project_emission(7, 0, 0, 10, 1000);
+    let total: u64 = emission.iter().sum();
+    assert_eq!(total, 2 * 10 * 7 * (100 + 50 + 25 + 12 + 6 + 3 + 1));
+    assert!(emission[56..].iter().all(|e| *e == 0));
+}
@@ -0,0 +1,122 @@
+use solana_program::{instruction::InstructionError, program_error::ProgramError};
+use Hihi::error::{explain_instruction_error, HihiError};
+
+/// Every variant with the code clients already rely on, append new ones at the end.
+const CODES: [(HihiError, u32); 69] = [
+    (HihiError::InvalidInstruction, 0),
+    (HihiError::InvalidOwner, 1),
+    (HihiError::InvalidProgramAddress, 2),
+    (HihiError::InvalidTokenMint, 3),
+    (HihiError::InvalidTokenAddress, 4),
+    (HihiError::WorkLimitExceeded, 5),
+    (HihiError::InvalidClaimHash, 6),
+    (HihiError::IncorrectClaimSolution, 7),
+    (HihiError::DeserializationFailure, 8),
+    (HihiError::NotInitialized, 9),
+    (HihiError::AlreadyInitialized, 10),
+    (HihiError::NotRentExempt, 11),
+    (HihiError::InsufficientFundsForTransaction, 12),
+    (HihiError::UnknownError, 13),
+    (HihiError::InstanceNotOwnedByProgram, 14),
+    (HihiError::InstanceNotWritable, 15),
+    (HihiError::InstanceNotSigner, 16),
+    (HihiError::InitializerNotSigner, 17),
+    (HihiError::InvalidInitializer, 18),
+    (HihiError::AdminOneNotSigner, 19),
+    (HihiError::AdminTwoNotSigner, 20),
+    (HihiError::WithdrawNotSigner, 21),
+    (HihiError::InvalidAdminOne, 22),
+    (HihiError::InvalidAdminTwo, 23),
+    (HihiError::InvalidWithdrawKey, 24),
+    (HihiError::NewAdminOneNotSigner, 25),
+    (HihiError::NewAdminTwoNotSigner, 26),
+    (HihiError::NewWithdrawNotSigner, 27),
+    (HihiError::PayerNotSigner, 28),
+    (HihiError::PayerNotWritable, 29),
+    (HihiError::InvalidAuthority, 30),
+    (HihiError::InvalidTokenProgram, 31),
+    (HihiError::InvalidSystemProgram, 32),
+    (HihiError::ClaimKeyNotSigner, 33),
+    (HihiError::PoolKeyNotSigner, 34),
+    (HihiError::BreachTooSmall, 35),
+    (HihiError::BreachTooLarge, 36),
+    (HihiError::BreachCountOverflow, 37),
+    (HihiError::GameOver, 38),
+    (HihiError::LimitBreaksExhausted, 39),
+    (HihiError::InvalidWorkCount, 40),
+    (HihiError::InvalidMagicLength, 41),
+    (HihiError::InvalidMinerAccount, 42),
+    (HihiError::InvalidReferrerAccount, 43),
+    (HihiError::SelfReferral, 44),
+    (HihiError::InvalidReferralShare, 45),
+    (HihiError::InvalidVestingAccount, 46),
+    (HihiError::NothingVested, 47),
+    (HihiError::InvalidPageAccount, 48),
+    (HihiError::InvalidBatchCount, 49),
+    (HihiError::InvalidPuzzleIndex, 50),
+    (HihiError::PuzzleAlreadyClaimed, 51),
+    (HihiError::InvalidSlotHashes, 52),
+    (HihiError::SupplyExhausted, 53),
+    (HihiError::InvalidMaxSupply, 54),
+    (HihiError::InvalidPriceCurve, 55),
+    (HihiError::InvalidSurge, 56),
+    (HihiError::InvalidDecay, 57),
+    (HihiError::InstanceStillActive, 58),
+    (HihiError::InvalidInstanceAddress, 59),
+    (HihiError::InvalidMintAuthority, 60),
+    (HihiError::InvalidRegistry, 61),
+    (HihiError::RegistryFull, 62),
+    (HihiError::AlreadyRegistered, 63),
+    (HihiError::TokenPriceOverflow, 64),
+    (HihiError::LegacyState, 65),
+    (HihiError::VestingsNotSettled, 66),
+    (HihiError::VestingCountOverflow, 67),
+    (HihiError::InvalidAccountType, 68),
+];
+
+#[test]
+fn codes_are_stable() {
+    for (error, code) in CODES.iter() {
+        assert_eq!(error.code(), *code, "{:?}", error);
+        assert_eq!(
+            ProgramError::from(error.clone()),
+            ProgramError::Custom(*code)
+        );
+    }
+    //no variant is missing from the table.
+    assert_eq!(HihiError::from_code(CODES.len() as u32), None);
+}
+
+#[test]
+fn every_variant_round_trips() {
+    for (error, code) in CODES.iter() {
+        assert_eq!(HihiError::from_code(*code).as_ref(), Some(error));
+        assert_eq!(
+            HihiError::decode(&ProgramError::from(error.clone())).as_ref(),
+            Some(error)
+        );
+        let instruction_error = InstructionError::Custom(*code);
+        assert_eq!(
+            HihiError::decode_instruction_error(&instruction_error).as_ref(),
+            Some(error)
+        );
+        assert_eq!(
+            explain_instruction_error(&instruction_error),
+            format!("HihiError {}: {}", code, error)
+        );
+    }
+}
+
+#[test]
+fn other_errors_are_not_decoded() {
+    assert_eq!(HihiError::from_code(u32::MAX), None);
+    assert_eq!(HihiError::decode(&ProgramError::InvalidArgument), None);
+    assert_eq!(
+        HihiError::decode_instruction_error(&InstructionError::InvalidArgument),
+        None
+    );
+    assert_eq!(
+        explain_instruction_error(&InstructionError::Custom(u32::MAX)),
+        InstructionError::Custom(u32::MAX).to_string()
+    );
+}
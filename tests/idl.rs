@@ -0,0 +1,26 @@
+use Hihi::{
+    idl,
+    state::{STATE_LAYOUT, STATE_SPACE},
+};
+
+const IDL_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/idl/hihi.json");
+
+// Run with UPDATE_IDL=1 to rewrite idl/hihi.json after an interface change.
+#[test]
+fn idl_is_up_to_date() {
+    let generated = idl::generate();
+    if std::env::var("UPDATE_IDL").is_ok() {
+        std::fs::write(IDL_PATH, &generated).unwrap();
+    }
+    let checked_in = std::fs::read_to_string(IDL_PATH).unwrap();
+    assert!(
+        checked_in == generated,
+        "idl/hihi.json is stale, rerun the tests with UPDATE_IDL=1"
+    );
+}
+
+#[test]
+fn state_layout_covers_state_space() {
+    let total: usize = STATE_LAYOUT.iter().map(|(_, _, size)| size).sum();
+    assert_eq!(total, STATE_SPACE);
+}
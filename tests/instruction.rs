@@ -0,0 +1,113 @@
+use Hihi::instruction::{
+    Breach, BreachWithTokens, Claim, ClaimPuzzle, ClaimPuzzleWithNonce, ClaimWithNonce, Configure,
+    HihiInstruction, Initialize, InitializeWithHalving, LimitBreakWithNonce, V2_TAG_OFFSET,
+};
+
+fn legacy_instructions() -> Vec<HihiInstruction> {
+    vec![
+        HihiInstruction::Initialize(Initialize { nonce: 254 }),
+        HihiInstruction::Breach(Breach {
+            lamports: 150000000,
+        }),
+        HihiInstruction::LimitBreak,
+        HihiInstruction::Claim(Claim { work: [7; 57] }),
+        HihiInstruction::Withdraw,
+        HihiInstruction::ChangeKeys,
+    ]
+}
+
+fn all_instructions() -> Vec<HihiInstruction> {
+    let mut all = legacy_instructions();
+    all.push(HihiInstruction::CreateMiner);
+    all.push(HihiInstruction::CreateReferrer);
+    all.push(HihiInstruction::Configure(Configure {
+        referral_share: 500,
+        vesting_epochs: 4,
+        max_supply: 1_000_000,
+        price_scale: 86,
+        price_exponent: 865_245,
+        surge_bps: 250,
+        decay_bps: 2,
+        decay_floor_bps: 5000,
+    }));
+    all.push(HihiInstruction::CreateVesting);
+    all.push(HihiInstruction::ClaimVested);
+    all.push(HihiInstruction::CreatePage);
+    all.push(HihiInstruction::ClaimPuzzle(ClaimPuzzle {
+        seed: [9; 32],
+        index: 4,
+    }));
+    all.push(HihiInstruction::BreachWithTokens(BreachWithTokens {
+        breaches: 3,
+    }));
+    all.push(HihiInstruction::ClaimWithNonce(ClaimWithNonce {
+        claim: Claim { work: [5; 57] },
+        nonce: u64::MAX,
+    }));
+    all.push(HihiInstruction::ClaimPuzzleWithNonce(
+        ClaimPuzzleWithNonce {
+            seed: [8; 32],
+            index: 2,
+            nonce: 77,
+        },
+    ));
+    all.push(HihiInstruction::LimitBreakWithNonce(LimitBreakWithNonce {
+        nonce: 1 << 40,
+    }));
+    all.push(HihiInstruction::InitializeWithHalving(
+        InitializeWithHalving {
+            nonce: 253,
+            halving_epochs: 210,
+        },
+    ));
+    all.push(HihiInstruction::Close);
+    all.push(HihiInstruction::InitializeCanonical(
+        InitializeWithHalving {
+            nonce: 254,
+            halving_epochs: 0,
+        },
+    ));
+    all.push(HihiInstruction::Register);
+    all.push(HihiInstruction::Migrate);
+    all
+}
+
+#[test]
+fn v2_round_trip() {
+    for ix in all_instructions() {
+        let data = ix.pack();
+        assert!(data[0] >= V2_TAG_OFFSET);
+        assert_eq!(HihiInstruction::unpack(&data).unwrap(), ix);
+    }
+}
+
+#[test]
+fn legacy_round_trip() {
+    for (tag, ix) in legacy_instructions().into_iter().enumerate() {
+        let data = ix.pack_legacy().unwrap();
+        assert_eq!(data[0], tag as u8);
+        assert_eq!(HihiInstruction::unpack(&data).unwrap(), ix);
+    }
+}
+
+#[test]
+fn v2_rejects_bad_lengths() {
+    for ix in all_instructions() {
+        let data = ix.pack();
+        let mut long = data.clone();
+        long.push(0);
+        assert!(HihiInstruction::unpack(&long).is_err());
+        if data.len() > 1 {
+            assert!(HihiInstruction::unpack(&data[..data.len() - 1]).is_err());
+        }
+    }
+}
+
+#[test]
+fn short_input_is_an_error() {
+    assert!(HihiInstruction::unpack(&[]).is_err());
+    assert!(HihiInstruction::unpack(&[0]).is_err());
+    assert!(HihiInstruction::unpack(&[1, 0, 0]).is_err());
+    assert!(HihiInstruction::unpack(&[3; 20]).is_err());
+    assert!(HihiInstruction::unpack(&[255]).is_err());
+}
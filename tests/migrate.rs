@@ -0,0 +1,179 @@
+mod common;
+
+use common::{instance, Account};
+use solana_program::{
+    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_program,
+};
+use Hihi::{
+    error::HihiError,
+    processor::Processor,
+    state::{
+        HihiState, BASE_SPACE, HEAP_COUNT, LB_BYTES, LEGACY_STATE_SPACE, LEGACY_VERSION, MAX_COUNT,
+        RESERVED_BYTES, STATE_LAYOUT, STATE_SPACE, STATE_VERSION, WORK_BYTES,
+    },
+};
+
+fn legacy_instance() -> HihiState {
+    let mut state = instance();
+    state.is_initialized = true;
+    state.nonce = 254;
+    state.current_slot = 77;
+    state.difficulty = 5;
+    state.lamports = 1_000;
+    state.breach_price = 150_000_000;
+    state.breach_count = 42;
+    state.work_cached = 3;
+    state.token_doubles = 2;
+    state.admin_one_id = Pubkey::new_unique();
+    state.admin_two_id = Pubkey::new_unique();
+    state.withdraw_id = Pubkey::new_unique();
+    state.limit_break = vec![9; LB_BYTES];
+    state
+}
+
+/// `state` with `puzzles` heap puzzles as the first release wrote it.
+fn legacy_data(state: &HihiState, puzzles: usize) -> Vec<u8> {
+    let mut packed = vec![0; STATE_SPACE];
+    state.pack_into_slice(&mut packed);
+    let mut data = packed[..BASE_SPACE].to_vec();
+    data[0] = LEGACY_VERSION;
+    data.push(puzzles as u8);
+    data.extend_from_slice(&((puzzles * WORK_BYTES) as u32).to_le_bytes());
+    for i in 0..puzzles {
+        data.extend_from_slice(&[i as u8; WORK_BYTES]);
+    }
+    data.resize(LEGACY_STATE_SPACE, 0);
+    data
+}
+
+#[test]
+fn instances_keep_the_first_release_size() {
+    assert_eq!(STATE_SPACE, LEGACY_STATE_SPACE);
+    assert_eq!(LEGACY_STATE_SPACE, 6022);
+    assert_eq!(RESERVED_BYTES, 28);
+    let size: usize = STATE_LAYOUT.iter().map(|(_, _, bytes)| bytes).sum();
+    assert_eq!(size, STATE_SPACE);
+
+    let mut packed = vec![0; STATE_SPACE];
+    legacy_instance().pack_into_slice(&mut packed);
+    assert_eq!(packed[0], STATE_VERSION);
+}
+
+#[test]
+fn legacy_instances_must_migrate() {
+    let state = legacy_instance();
+    let data = legacy_data(&state, 90);
+    assert_eq!(
+        HihiState::unpack_unchecked(&data),
+        Err(HihiError::LegacyState.into())
+    );
+
+    let legacy = HihiState::unpack_legacy(&data).unwrap();
+    assert_eq!(legacy.work.len(), 90);
+    assert_eq!(legacy.work[89], vec![89; WORK_BYTES]);
+    assert_eq!(
+        HihiState {
+            work: Vec::new(),
+            ..legacy
+        },
+        state
+    );
+
+    assert!(HihiState::unpack_legacy(&data[..LEGACY_STATE_SPACE - 1]).is_err());
+    let mut data = data;
+    data[BASE_SPACE] = MAX_COUNT as u8 + 1;
+    assert_eq!(
+        HihiState::unpack_legacy(&data),
+        Err(HihiError::InvalidWorkCount.into())
+    );
+}
+
+struct Setup {
+    program_id: Pubkey,
+    instance: Account,
+    payer: Account,
+    system: Account,
+    page: Account,
+    state: HihiState,
+}
+
+fn setup(puzzles: usize) -> Setup {
+    let program_id = Pubkey::new_unique();
+    let state = legacy_instance();
+    let mut instance = Account::new(Pubkey::new_unique(), false, program_id);
+    instance.data = legacy_data(&state, puzzles);
+    let (page_id, _) = Processor::page_id(&program_id, &instance.key, 0);
+    Setup {
+        program_id,
+        instance,
+        payer: Account::new(Pubkey::new_unique(), true, system_program::id()),
+        system: Account::new(system_program::id(), false, Pubkey::default()),
+        page: Account::new(page_id, false, system_program::id()),
+        state,
+    }
+}
+
+fn migrate(setup: &mut Setup, page: bool) -> Result<(), ProgramError> {
+    let data = Hihi::instruction::migrate(
+        &setup.program_id,
+        &setup.instance.key,
+        &setup.payer.key,
+        None,
+    )
+    .unwrap()
+    .data;
+    let mut placeholder = Account::new(setup.program_id, false, Pubkey::default());
+    let page = if page {
+        setup.page.info()
+    } else {
+        placeholder.info()
+    };
+    let accounts = [
+        setup.instance.info(),
+        setup.payer.info(),
+        setup.system.info(),
+        page,
+    ];
+    Processor::process(&setup.program_id, &accounts, &data)
+}
+
+#[test]
+fn migrate_rewrites_the_instance_in_place() {
+    let mut setup = setup(HEAP_COUNT);
+    migrate(&mut setup, false).unwrap();
+    assert_eq!(setup.instance.data.len(), LEGACY_STATE_SPACE);
+    assert_eq!(setup.instance.data[0], STATE_VERSION);
+
+    let state = HihiState::unpack(&setup.instance.data).unwrap();
+    assert_eq!(state.work.len(), HEAP_COUNT);
+    assert_eq!(state.page_count, 0);
+    assert_eq!(
+        HihiState {
+            work: Vec::new(),
+            ..state
+        },
+        setup.state
+    );
+
+    //a second run finds nothing to migrate.
+    assert_eq!(
+        migrate(&mut setup, false),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn overflowing_heaps_need_the_first_page() {
+    let mut setup = setup(HEAP_COUNT + 1);
+    assert_eq!(
+        migrate(&mut setup, false),
+        Err(HihiError::InvalidPageAccount.into())
+    );
+
+    setup.page.key = Pubkey::new_unique();
+    assert_eq!(
+        migrate(&mut setup, true),
+        Err(HihiError::InvalidPageAccount.into())
+    );
+    assert_eq!(setup.instance.data[0], LEGACY_VERSION);
+}
@@ -0,0 +1,263 @@
+mod common;
+
+use common::instance;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use Hihi::{
+    error::HihiError,
+    instruction,
+    processor::{
+        breaches_cost, buy_breaches, calculate_price, check_decay, check_price_curve,
+        current_price, curve_price, decayed_price, surge_price, token_breach_price, update_window,
+        Processor, BREACH_WINDOW, CURVE_EXPONENT, CURVE_SCALE, MAX_BREACHES, MAX_CURVE_EXPONENT,
+        START_PRICE,
+    },
+};
+
+fn fitted(count: i32) -> u64 {
+    curve_price(count, START_PRICE, CURVE_SCALE, CURVE_EXPONENT)
+}
+
+#[test]
+fn fitted_curve_matches_the_tier_anchors() {
+    assert_eq!(fitted(0), START_PRICE);
+    for (count, anchor) in [
+        (1000, 1323796464u64),
+        (10000, 9416424207),
+        (100000, 67051171537),
+    ]
+    .iter()
+    {
+        assert_eq!(calculate_price(*count, START_PRICE), *anchor);
+        let error = (fitted(*count) as f64 - *anchor as f64).abs() / *anchor as f64;
+        assert!(error < 0.02, "{} off by {}", count, error);
+    }
+}
+
+#[test]
+fn fitted_curve_is_strictly_increasing() {
+    let mut last = fitted(-5);
+    assert_eq!(last, START_PRICE);
+    for count in 1..=200_000 {
+        let price = fitted(count);
+        assert!(price > last, "{} priced {} after {}", count, price, last);
+        last = price;
+    }
+}
+
+#[test]
+fn fixed_point_tracks_the_float_curve() {
+    for &(scale, exponent) in [(86, 865_245), (1, 1_000_000), (5000, 2_500_000), (3, 1)].iter() {
+        for count in [0, 1, 2, 99, 1234, 65_536, 1_000_000, i32::MAX].iter() {
+            let expected = START_PRICE as f64
+                * (1.0 + *count as f64 / scale as f64).powf(exponent as f64 / 1e6);
+            let price = curve_price(*count, START_PRICE, scale, exponent);
+            if expected >= u64::MAX as f64 {
+                assert_eq!(price, u64::MAX);
+            } else {
+                assert!((price as f64 - expected).abs() / expected < 1e-6);
+            }
+        }
+    }
+}
+
+#[test]
+fn steep_curves_saturate() {
+    assert_eq!(
+        curve_price(i32::MAX, u64::MAX, 1, MAX_CURVE_EXPONENT),
+        u64::MAX
+    );
+    assert_eq!(
+        curve_price(i32::MAX, START_PRICE, 1, MAX_CURVE_EXPONENT),
+        u64::MAX
+    );
+}
+
+#[test]
+fn curve_is_chosen_by_the_instance() {
+    let mut instance = instance();
+    instance.breach_count = 5000;
+    assert_eq!(current_price(&instance), calculate_price(5000, START_PRICE));
+    instance.price_scale = CURVE_SCALE;
+    instance.price_exponent = CURVE_EXPONENT;
+    assert_eq!(current_price(&instance), fitted(5000));
+
+    assert!(check_price_curve(0, 0).is_ok());
+    assert!(check_price_curve(CURVE_SCALE, CURVE_EXPONENT).is_ok());
+    assert!(check_price_curve(CURVE_SCALE, 0).is_err());
+    assert!(check_price_curve(CURVE_SCALE, MAX_CURVE_EXPONENT + 1).is_err());
+}
+
+#[test]
+fn surge_raises_each_breach_of_the_window() {
+    let mut instance = instance();
+    instance.breach_price = 1000;
+    assert_eq!(surge_price(&instance, 0, 5), 1000);
+    assert_eq!(breaches_cost(&instance, 0, 3), 3000);
+
+    instance.surge_bps = 500;
+    instance.breach_count_this_window = 2;
+    assert_eq!(surge_price(&instance, 0, 0), 1100);
+    assert_eq!(surge_price(&instance, 0, 1), 1150);
+    assert_eq!(breaches_cost(&instance, 0, 3), 1100 + 1150 + 1200);
+
+    instance.breach_price = u64::MAX;
+    assert_eq!(surge_price(&instance, 0, 0), u64::MAX);
+    assert_eq!(breaches_cost(&instance, 0, 3), u64::MAX);
+}
+
+#[test]
+fn surge_buys_fewer_breaches_and_keeps_the_rest() {
+    let mut instance = instance();
+    instance.breach_price = 1000;
+    instance.surge_bps = 1000;
+    //1000 + 1100 + 1200 fit, 1300 does not.
+    assert_eq!(buy_breaches(&mut instance, 0, 3500), 3);
+    assert_eq!(instance.breach_remain, 200);
+    //the window counter moves when the breaches are added.
+    instance.breach_count_this_window = 3;
+    assert_eq!(buy_breaches(&mut instance, 0, 1100), 1);
+    assert_eq!(instance.breach_remain, 0);
+}
+
+#[test]
+fn surge_resets_with_the_window() {
+    let mut instance = instance();
+    instance.surge_bps = 100;
+    instance.breach_count = 20;
+    instance.breach_count_this_window = 20;
+    instance.breach_price = calculate_price(0, START_PRICE);
+    assert!(surge_price(&instance, 0, 0) > instance.breach_price);
+
+    let mut clock = solana_program::clock::Clock::default();
+    clock.slot = BREACH_WINDOW as u64;
+    update_window(&mut instance, &clock);
+    assert_eq!(instance.breach_count_this_window, 0);
+    assert_eq!(
+        surge_price(&instance, 0, 0),
+        calculate_price(20, START_PRICE)
+    );
+}
+
+#[test]
+fn quiet_instances_decay_to_the_floor() {
+    let mut instance = instance();
+    instance.breach_price = 1_000_000;
+    instance.last_breach_slot = 100;
+    assert_eq!(decayed_price(&instance, 10_000), 1_000_000);
+
+    instance.decay_bps = 10;
+    instance.decay_floor_bps = 2500;
+    assert_eq!(decayed_price(&instance, 50), 1_000_000);
+    assert_eq!(decayed_price(&instance, 100), 1_000_000);
+    assert_eq!(decayed_price(&instance, 200), 900_000);
+    assert_eq!(decayed_price(&instance, 850), 250_000);
+    assert_eq!(decayed_price(&instance, u64::MAX), 250_000);
+    //surge applies on top of the decayed price.
+    instance.surge_bps = 1000;
+    instance.breach_count_this_window = 1;
+    assert_eq!(surge_price(&instance, 200, 0), 990_000);
+}
+
+#[test]
+fn decayed_breaches_are_cheaper() {
+    let mut instance = instance();
+    instance.breach_price = 1_000_000;
+    instance.decay_bps = 50;
+    instance.decay_floor_bps = 5000;
+    assert_eq!(breaches_cost(&instance, 100, 2), 1_000_000);
+    assert_eq!(buy_breaches(&mut instance, 100, 1_000_000), 2);
+    assert_eq!(instance.breach_remain, 0);
+
+    assert!(check_decay(0, 0).is_ok());
+    assert!(check_decay(50, 5000).is_ok());
+    assert!(check_decay(50, 0).is_err());
+    assert!(check_decay(10_001, 5000).is_err());
+    assert!(check_decay(50, 10_001).is_err());
+}
+
+#[test]
+fn decayed_remainder_stays_within_max_breaches() {
+    let mut instance = instance();
+    instance.breach_price = 1_000_000_000;
+    instance.breach_remain = 900_000_000;
+    instance.decay_bps = 100;
+    instance.decay_floor_bps = 5000;
+    //idle long enough to sit on the floor, half price.
+    let slot = 1000;
+    let lamports = breaches_cost(&instance, slot, MAX_BREACHES);
+    assert_eq!(lamports, 5_000_000_000);
+    assert_eq!(buy_breaches(&mut instance, slot, lamports), MAX_BREACHES);
+    assert_eq!(instance.breach_remain, 900_000_000);
+}
+
+#[test]
+fn surge_remainder_into_a_new_window_stays_within_max_breaches() {
+    let mut instance = instance();
+    instance.breach_price = 1000;
+    instance.surge_bps = 1000;
+    instance.breach_count_this_window = 5;
+    instance.breach_remain = 5000;
+    //the window rolls over, the remainder now buys at the cheapest surge prices.
+    let mut clock = solana_program::clock::Clock::default();
+    clock.slot = BREACH_WINDOW as u64;
+    update_window(&mut instance, &clock);
+    instance.breach_price = 1000;
+    assert_eq!(instance.breach_count_this_window, 0);
+
+    let lamports = breaches_cost(&instance, clock.slot, MAX_BREACHES);
+    assert_eq!(lamports, 14_500);
+    assert_eq!(
+        buy_breaches(&mut instance, clock.slot, lamports),
+        MAX_BREACHES
+    );
+    assert_eq!(instance.breach_remain, 5000);
+}
+
+#[test]
+fn token_breaches_burn_the_multiplied_cost() {
+    let mut instance = instance();
+    instance.breach_price = 1000;
+    assert_eq!(
+        token_breach_price(breaches_cost(&instance, 0, 3)),
+        Ok(3_000_000)
+    );
+    instance.surge_bps = 1000;
+    assert_eq!(
+        token_breach_price(breaches_cost(&instance, 0, 3)),
+        Ok((1000 + 1100 + 1200) * 1000)
+    );
+    assert_eq!(
+        token_breach_price(u64::MAX),
+        Err(HihiError::TokenPriceOverflow)
+    );
+}
+
+#[test]
+fn token_breaches_are_bounded() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let process = |breaches| {
+        let ix = instruction::breach_with_tokens(
+            &program_id,
+            &key,
+            &key,
+            &key,
+            &key,
+            &key,
+            &[],
+            breaches,
+        )
+        .unwrap();
+        Processor::process(&program_id, &[], &ix.data)
+    };
+    assert_eq!(process(0), Err(HihiError::BreachTooSmall.into()));
+    assert_eq!(
+        process(MAX_BREACHES + 1),
+        Err(HihiError::BreachTooLarge.into())
+    );
+    //in bounds it goes on to the accounts.
+    assert_eq!(
+        process(MAX_BREACHES),
+        Err(ProgramError::NotEnoughAccountKeys)
+    );
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, pubkey::Pubkey, sysvar,
+};
+use Hihi::{
+    error::HihiError,
+    processor::{
+        batch_puzzle, check_claim, check_claim_with_nonce, create_hash_puzzles, nonce_claim_hash,
+        open_batch_puzzles, puzzle_seed, recent_slot_hash, NONCE_EXTRA_BYTES,
+    },
+    state::{HihiState, PuzzleBatch, WORK_BYTES},
+};
+
+fn instance(token_doubles: u64) -> HihiState {
+    let mut instance = common::instance();
+    instance.is_initialized = true;
+    instance.token_doubles = token_doubles;
+    instance.nonce = 255;
+    instance.current_slot = 1;
+    instance.current_epoch = 2;
+    instance.difficulty = 4;
+    instance.breach_price = 150000000;
+    instance.breach_count = 42;
+    instance
+}
+
+fn clock() -> Clock {
+    Clock {
+        slot: 1000,
+        epoch: 3,
+        unix_timestamp: 1630000000,
+        ..Clock::default()
+    }
+}
+
+#[test]
+fn batch_puzzles_match_stored_puzzles() {
+    let instance_id = Pubkey::new_unique();
+    for doubles in [0, 3, 20].iter() {
+        let instance = instance(*doubles);
+        let (stored, left) = create_hash_puzzles(
+            &clock(),
+            10,
+            &instance,
+            &instance_id,
+            &[5; 32],
+            150000000,
+            100,
+            4,
+        );
+        let doubled = doubles - left;
+        let batch = PuzzleBatch {
+            seed: puzzle_seed(&clock(), &instance, &instance_id, &[5; 32], 150000000),
+            count: 10,
+            reward: 100,
+            doubled: doubled as u8,
+            difficulty: 4,
+            claimed: 0,
+        };
+        //stored puzzles are made from index count - 1 down to 0.
+        for (i, puzzle) in stored.chunks_exact(WORK_BYTES).enumerate() {
+            assert_eq!(batch_puzzle(&batch, 9 - i as u8), puzzle);
+        }
+    }
+}
+
+#[test]
+fn claimed_puzzles_are_not_open() {
+    let mut batch = PuzzleBatch {
+        seed: [7; 32],
+        count: 5,
+        reward: 50,
+        doubled: 2,
+        difficulty: 3,
+        claimed: 0,
+    };
+    assert_eq!(batch.reward_of(4), 100);
+    assert_eq!(batch.reward_of(2), 50);
+    batch.set_claimed(1);
+    batch.set_claimed(3);
+    assert_eq!(batch.open_count(), 3);
+    let open: Vec<u8> = open_batch_puzzles(&batch).iter().map(|p| p.0).collect();
+    assert_eq!(open, vec![0, 2, 4]);
+}
+
+#[test]
+fn recent_slot_hash_reads_the_newest_entry() {
+    let mut data = 2u64.to_le_bytes().to_vec();
+    data.extend_from_slice(&99u64.to_le_bytes());
+    data.extend_from_slice(&[3; 32]);
+    data.extend_from_slice(&98u64.to_le_bytes());
+    data.extend_from_slice(&[4; 32]);
+    let mut lamports = 0;
+    let key = sysvar::slot_hashes::id();
+    let owner = sysvar::id();
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    assert_eq!(recent_slot_hash(&info).unwrap(), [3; 32]);
+
+    let mut lamports = 0;
+    let mut data = 2u64.to_le_bytes().to_vec();
+    data.extend_from_slice(&[0; 80]);
+    let other = Pubkey::new_unique();
+    let info = AccountInfo::new(
+        &other,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    assert!(recent_slot_hash(&info).is_err());
+}
+
+#[test]
+fn nonce_solves_a_puzzle_with_fixed_keys() {
+    let (puzzles, _) = create_hash_puzzles(
+        &clock(),
+        1,
+        &instance(0),
+        &Pubkey::new_from_array([1; 32]),
+        &[3; 32],
+        150000000,
+        1,
+        1,
+    );
+    let work = &puzzles[..WORK_BYTES];
+    let (sha, magic) = (&work[1..33], &work[34..35]);
+    let wallet = Pubkey::new_from_array([2; 32]);
+    let pool = Pubkey::new_from_array([3; 32]);
+
+    let mut solved = magic.to_vec();
+    solved.extend_from_slice(&[0; NONCE_EXTRA_BYTES]);
+    let nonce = (0..)
+        .find(|nonce| nonce_claim_hash(sha, &wallet, &pool, *nonce).starts_with(&solved))
+        .unwrap();
+    assert!(check_claim_with_nonce(&wallet, &pool, work, nonce).is_ok());
+    assert!(check_claim_with_nonce(&pool, &wallet, work, nonce).is_err());
+    assert!(check_claim(&wallet, &pool, work).is_err());
+}
+
+#[test]
+fn nonce_claims_need_extra_zero_bytes() {
+    let mut work = vec![1; 36];
+    work[33] = 2;
+    let (sha, magic) = (&work[1..33], &work[34..36]);
+    let wallet = Pubkey::new_from_array([2; 32]);
+    let pool = Pubkey::new_from_array([3; 32]);
+
+    //matching the magic alone is what a keypair claim needs, not a nonce claim.
+    let nonce = (0..)
+        .find(|nonce| {
+            let hash = nonce_claim_hash(sha, &wallet, &pool, *nonce);
+            hash.starts_with(magic) && hash[magic.len()] != 0
+        })
+        .unwrap();
+    assert_eq!(
+        check_claim_with_nonce(&wallet, &pool, &work, nonce),
+        Err(HihiError::IncorrectClaimSolution.into())
+    );
+
+    //a magic that fills the whole hash leaves no room for the extra bytes.
+    let mut work = vec![0; 34 + 32];
+    work[33] = 32;
+    assert_eq!(
+        check_claim_with_nonce(&wallet, &pool, &work, 0),
+        Err(HihiError::IncorrectClaimSolution.into())
+    );
+}
+
+#[test]
+fn malformed_puzzles_have_an_invalid_magic_length() {
+    let wallet = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let invalid = Err(ProgramError::from(HihiError::InvalidMagicLength));
+
+    //too short to hold the magic length byte.
+    for len in [0, 1, 33].iter() {
+        let work = vec![0; *len];
+        assert_eq!(check_claim(&wallet, &pool, &work), invalid);
+        assert_eq!(check_claim_with_nonce(&wallet, &pool, &work, 0), invalid);
+    }
+
+    //the magic length points past the end.
+    let mut work = vec![0; 36];
+    work[33] = 3;
+    assert_eq!(check_claim(&wallet, &pool, &work), invalid);
+    assert_eq!(check_claim_with_nonce(&wallet, &pool, &work, 0), invalid);
+    work[33] = 2;
+    assert_ne!(check_claim(&wallet, &pool, &work), invalid);
+    work[33] = 0;
+    assert_eq!(check_claim(&wallet, &pool, &work[..34]), Ok(()));
+}
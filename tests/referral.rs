@@ -0,0 +1,199 @@
+mod common;
+
+use common::{instance, Account};
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+use Hihi::{
+    accounts,
+    error::HihiError,
+    processor::{referral_tokens, Processor, MAX_REFERRAL_SHARE},
+    state::{HihiState, MinerState, ReferrerState, REFERRER_LAYOUT, REFERRER_SPACE},
+};
+
+struct Setup {
+    program_id: Pubkey,
+    state: HihiState,
+    instance: Account,
+    token_program: Account,
+    mint: Account,
+    authority: Account,
+    referrer: Account,
+    referrer_token: Account,
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey) -> Account {
+    let mut account = Account::new(Pubkey::new_unique(), false, spl_token::id());
+    account.data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        },
+        &mut account.data,
+    )
+    .unwrap();
+    account
+}
+
+fn setup() -> Setup {
+    let program_id = Pubkey::new_unique();
+    let mut state = instance();
+    state.is_initialized = true;
+    let instance = Account::new(Pubkey::new_unique(), false, program_id);
+    let wallet_id = Pubkey::new_unique();
+
+    let (referrer_id, bump) = Processor::referrer_id(&program_id, &instance.key, &wallet_id);
+    let mut referrer = Account::new(referrer_id, false, program_id);
+    referrer.data = vec![0; REFERRER_SPACE];
+    ReferrerState::pack(
+        ReferrerState {
+            is_initialized: true,
+            instance_id: instance.key,
+            wallet_id,
+            bump,
+            referred_breaches: 3,
+            tokens_earned: 0,
+        },
+        &mut referrer.data,
+    )
+    .unwrap();
+
+    Setup {
+        program_id,
+        instance,
+        token_program: Account::new(spl_token::id(), false, Pubkey::default()),
+        mint: Account::new(state.token_mint_id, false, spl_token::id()),
+        authority: Account::new(Pubkey::new_unique(), false, Pubkey::default()),
+        referrer,
+        referrer_token: token_account(&state.token_mint_id, &wallet_id),
+        state,
+    }
+}
+
+/// Check the referral account the way `BreachAccounts` does, then pay it.
+fn pay(setup: &mut Setup, payer_id: &Pubkey) -> Result<(), ProgramError> {
+    let instance = setup.instance.info();
+    let referrer_info = setup.referrer.info();
+    let referrer = accounts::referrer(&setup.program_id, instance.key, &referrer_info)?;
+    Processor::pay_referrer(
+        &mut setup.state,
+        &instance,
+        &setup.token_program.info(),
+        &setup.mint.info(),
+        &setup.authority.info(),
+        payer_id,
+        &referrer_info,
+        referrer,
+        &setup.referrer_token.info(),
+        2,
+        1_000_000,
+    )
+}
+
+#[test]
+fn referrer_pack_round_trip() {
+    let referrer = ReferrerState {
+        is_initialized: true,
+        instance_id: Pubkey::new_unique(),
+        wallet_id: Pubkey::new_unique(),
+        bump: 254,
+        referred_breaches: u64::MAX,
+        tokens_earned: 12345,
+    };
+    let mut data = [0; REFERRER_SPACE];
+    ReferrerState::pack(referrer.clone(), &mut data).unwrap();
+    assert_eq!(ReferrerState::unpack(&data).unwrap(), referrer);
+
+    let size: usize = REFERRER_LAYOUT.iter().map(|(_, _, bytes)| bytes).sum();
+    assert_eq!(size, REFERRER_SPACE);
+}
+
+#[test]
+fn referral_share_is_in_basis_points() {
+    assert_eq!(referral_tokens(0, 1_000_000), 0);
+    assert_eq!(referral_tokens(250, 1_000_000), 25_000);
+    assert_eq!(referral_tokens(1, 9_999), 0);
+    assert_eq!(referral_tokens(MAX_REFERRAL_SHARE, 1_000_000), 1_000_000);
+    assert_eq!(referral_tokens(5000, u64::MAX), u64::MAX / 2);
+}
+
+#[test]
+fn referrer_can_not_refer_themselves() {
+    let mut setup = setup();
+    let wallet_id = ReferrerState::unpack(&setup.referrer.data)
+        .unwrap()
+        .wallet_id;
+    assert_eq!(
+        pay(&mut setup, &wallet_id),
+        Err(HihiError::SelfReferral.into())
+    );
+}
+
+#[test]
+fn referral_tokens_go_to_the_referrer_wallet() {
+    let mut setup = setup();
+    setup.referrer_token = token_account(&setup.state.token_mint_id, &Pubkey::new_unique());
+    assert_eq!(
+        pay(&mut setup, &Pubkey::new_unique()),
+        Err(HihiError::InvalidReferrerAccount.into())
+    );
+
+    let mut setup = self::setup();
+    setup.referrer.owner = Pubkey::new_unique();
+    assert_eq!(
+        pay(&mut setup, &Pubkey::new_unique()),
+        Err(HihiError::InvalidReferrerAccount.into())
+    );
+
+    let mut setup = self::setup();
+    setup.instance.key = Pubkey::new_unique();
+    assert_eq!(
+        pay(&mut setup, &Pubkey::new_unique()),
+        Err(HihiError::InvalidReferrerAccount.into())
+    );
+}
+
+#[test]
+fn referrer_must_be_its_program_address() {
+    let mut setup = setup();
+    setup.referrer.key = Pubkey::new_unique();
+    assert_eq!(
+        pay(&mut setup, &Pubkey::new_unique()),
+        Err(HihiError::InvalidReferrerAccount.into())
+    );
+
+    //the referrer's miner account has the same first fields, but is not a referral account.
+    let mut setup = self::setup();
+    let referrer = ReferrerState::unpack(&setup.referrer.data).unwrap();
+    let (miner_id, bump) =
+        Processor::miner_id(&setup.program_id, &setup.instance.key, &referrer.wallet_id);
+    let mut data = vec![0; MinerState::LEN];
+    MinerState::pack(
+        MinerState {
+            is_initialized: true,
+            instance_id: setup.instance.key,
+            wallet_id: referrer.wallet_id,
+            bump,
+            ..MinerState::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    setup.referrer.key = miner_id;
+    setup.referrer.data = data[..REFERRER_SPACE].to_vec();
+    assert_eq!(
+        pay(&mut setup, &Pubkey::new_unique()),
+        Err(HihiError::InvalidAccountType.into())
+    );
+}
+
+#[test]
+fn referrer_totals_count_the_breaches() {
+    //no share, so nothing is minted and no token program is needed.
+    let mut setup = setup();
+    pay(&mut setup, &Pubkey::new_unique()).unwrap();
+    let referrer = ReferrerState::unpack(&setup.referrer.data).unwrap();
+    assert_eq!(referrer.referred_breaches, 5);
+    assert_eq!(referrer.tokens_earned, 0);
+}
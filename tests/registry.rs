@@ -0,0 +1,120 @@
+use solana_program::{
+    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_instruction,
+};
+use Hihi::{
+    error::HihiError,
+    processor::{create_program_account_instructions, Processor},
+    state::{RegistryState, MAX_INSTANCES, REGISTRY_LAYOUT, REGISTRY_SPACE},
+};
+
+#[test]
+fn registry_pack_round_trip() {
+    let mut registry = RegistryState {
+        is_initialized: true,
+        bump: 254,
+        instances: Vec::new(),
+    };
+    registry.add(&Pubkey::new_unique()).unwrap();
+    registry.add(&Pubkey::new_unique()).unwrap();
+
+    let mut data = [0; REGISTRY_SPACE];
+    RegistryState::pack(registry.clone(), &mut data).unwrap();
+    assert_eq!(RegistryState::unpack(&data).unwrap(), registry);
+
+    let size: usize = REGISTRY_LAYOUT.iter().map(|(_, _, bytes)| bytes).sum();
+    assert_eq!(size, REGISTRY_SPACE);
+}
+
+#[test]
+fn registry_rejects_duplicates_and_overflow() {
+    let mut registry = RegistryState::default();
+    let instance = Pubkey::new_unique();
+    registry.add(&instance).unwrap();
+    assert_eq!(
+        registry.add(&instance),
+        Err(ProgramError::from(HihiError::AlreadyRegistered))
+    );
+
+    for _ in 1..MAX_INSTANCES {
+        registry.add(&Pubkey::new_unique()).unwrap();
+    }
+    assert_eq!(
+        registry.add(&Pubkey::new_unique()),
+        Err(ProgramError::from(HihiError::RegistryFull))
+    );
+}
+
+#[test]
+fn registry_forgets_removed_instances() {
+    let mut registry = RegistryState::default();
+    let ids = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    for id in ids.iter() {
+        registry.add(id).unwrap();
+    }
+    assert!(registry.remove(&ids[1]));
+    assert_eq!(registry.instances, vec![ids[0], ids[2]]);
+    assert!(!registry.remove(&ids[1]));
+    //a removed instance can register again.
+    registry.add(&ids[1]).unwrap();
+}
+
+#[test]
+fn canonical_instance_is_unique_per_mint() {
+    let program_id = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (instance, _) = Processor::instance_id(&program_id, &mint);
+    assert_eq!(Processor::instance_id(&program_id, &mint).0, instance);
+    assert_ne!(
+        Processor::instance_id(&program_id, &Pubkey::new_unique()).0,
+        instance
+    );
+    assert_ne!(Processor::registry_id(&program_id).0, instance);
+}
+
+#[test]
+fn registry_address_can_be_pre_funded() {
+    let program_id = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let (registry, _) = Processor::registry_id(&program_id);
+    let space = REGISTRY_SPACE;
+    let minimum = 5_000_000;
+
+    assert_eq!(
+        create_program_account_instructions(&program_id, &payer, &registry, 0, minimum, space),
+        vec![system_instruction::create_account(
+            &payer,
+            &registry,
+            minimum,
+            space as u64,
+            &program_id
+        )]
+    );
+
+    //anyone can send lamports to the address first, the payer covers the rest.
+    assert_eq!(
+        create_program_account_instructions(&program_id, &payer, &registry, 1, minimum, space),
+        vec![
+            system_instruction::transfer(&payer, &registry, minimum - 1),
+            system_instruction::allocate(&registry, space as u64),
+            system_instruction::assign(&registry, &program_id),
+        ]
+    );
+    assert_eq!(
+        create_program_account_instructions(
+            &program_id,
+            &payer,
+            &registry,
+            minimum + 1,
+            minimum,
+            space
+        ),
+        vec![
+            system_instruction::allocate(&registry, space as u64),
+            system_instruction::assign(&registry, &program_id),
+        ]
+    );
+}
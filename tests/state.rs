@@ -0,0 +1,461 @@
+use proptest::prelude::*;
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+use Hihi::{
+    error::HihiError,
+    processor::check_max_supply,
+    state::{
+        HihiState, MinerState, PageState, PuzzleBatch, ReferrerState, VestingState, BATCH_BYTES, LB_BYTES,
+        HEAP_COUNT, LEGACY_STATE_SPACE, MAX_BATCHES, MAX_COUNT, MINER_LAYOUT, MINER_SPACE,
+        RESERVED_BYTES, STATE_SPACE, VESTING_COUNT_BYTES, WORK_BYTES,
+    },
+};
+
+fn pubkey() -> impl Strategy<Value = Pubkey> {
+    any::<[u8; 32]>().prop_map(Pubkey::new_from_array)
+}
+
+fn batches() -> impl Strategy<Value = Vec<PuzzleBatch>> {
+    let batch = (
+        any::<[u8; 32]>(),
+        any::<u8>(),
+        any::<u8>(),
+        any::<u8>(),
+        any::<u8>(),
+        any::<u16>(),
+    )
+        .prop_map(
+            |(seed, count, reward, doubled, difficulty, claimed)| PuzzleBatch {
+                seed,
+                count,
+                reward,
+                doubled,
+                difficulty,
+                claimed,
+            },
+        );
+    prop::collection::vec(batch, 0..=MAX_BATCHES)
+}
+
+fn work(max: usize) -> impl Strategy<Value = Vec<Vec<u8>>> {
+    prop::collection::vec(prop::collection::vec(any::<u8>(), WORK_BYTES), 0..=max)
+}
+
+prop_compose! {
+    fn initialized_state()(
+        keys in prop::array::uniform4(pubkey()),
+        nonce in any::<u8>(),
+        current_slot in any::<u64>(),
+        current_epoch in any::<u64>(),
+        difficulty in any::<u8>(),
+        lamports in any::<u64>(),
+        breach_price in any::<u64>(),
+        breach_remain in any::<u64>(),
+        breach_count in any::<i32>(),
+        breach_count_this_window in any::<u32>(),
+        limit_count in any::<u32>(),
+        limit_breaks_this_epoch in any::<u32>(),
+        token_doubles in any::<u64>(),
+        work_cached in any::<u64>(),
+        referral_share in any::<u16>(),
+        vesting_epochs in any::<u16>(),
+        page_count in any::<u32>(),
+        batches in batches(),
+        max_supply in any::<u64>(),
+        minted in any::<u64>(),
+        halving_epochs in any::<u64>(),
+        start_epoch in any::<u64>(),
+        price_scale in any::<u32>(),
+        price_exponent in any::<u32>(),
+        surge_bps in any::<u16>(),
+        last_breach_slot in any::<u64>(),
+        decay_bps in any::<u16>(),
+        decay_floor_bps in any::<u16>(),
+        vesting_count in any::<u32>(),
+        limit_break in prop::collection::vec(any::<u8>(), LB_BYTES),
+        work in work(HEAP_COUNT),
+    ) -> HihiState {
+        HihiState {
+            is_initialized: true,
+            token_mint_id: keys[0],
+            token_doubles,
+            nonce,
+            current_slot,
+            current_epoch,
+            difficulty,
+            lamports,
+            breach_price,
+            breach_remain,
+            breach_count,
+            breach_count_this_window,
+            limit_count,
+            limit_breaks_this_epoch,
+            admin_one_id: keys[1],
+            admin_two_id: keys[2],
+            withdraw_id: keys[3],
+            limit_break,
+            work_cached,
+            referral_share,
+            vesting_epochs,
+            page_count,
+            batches,
+            max_supply,
+            minted,
+            halving_epochs,
+            start_epoch,
+            price_scale,
+            price_exponent,
+            surge_bps,
+            last_breach_slot,
+            decay_bps,
+            decay_floor_bps,
+            vesting_count,
+            work,
+        }
+    }
+}
+
+prop_compose! {
+    fn uninitialized_state()(keys in prop::array::uniform4(pubkey())) -> HihiState {
+        HihiState {
+            is_initialized: false,
+            token_mint_id: keys[0],
+            token_doubles: 0,
+            nonce: 0,
+            current_slot: 0,
+            current_epoch: 0,
+            difficulty: 0,
+            lamports: 0,
+            breach_price: 0,
+            breach_remain: 0,
+            breach_count: 0,
+            breach_count_this_window: 0,
+            limit_count: 0,
+            limit_breaks_this_epoch: 0,
+            admin_one_id: keys[1],
+            admin_two_id: keys[2],
+            withdraw_id: keys[3],
+            limit_break: Vec::new(),
+            work_cached: 0,
+            referral_share: 0,
+            vesting_epochs: 0,
+            page_count: 0,
+            batches: Vec::new(),
+            max_supply: 0,
+            minted: 0,
+            halving_epochs: 0,
+            start_epoch: 0,
+            price_scale: 0,
+            price_exponent: 0,
+            surge_bps: 0,
+            last_breach_slot: 0,
+            decay_bps: 0,
+            decay_floor_bps: 0,
+            vesting_count: 0,
+            work: Vec::new(),
+        }
+    }
+}
+
+fn round_trip(state: &HihiState) -> HihiState {
+    let mut packed = vec![0; STATE_SPACE];
+    state.pack_into_slice(&mut packed);
+    HihiState::unpack_from_slice(&packed).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn initialized_round_trip(state in initialized_state()) {
+        prop_assert_eq!(round_trip(&state), state);
+    }
+
+    #[test]
+    fn uninitialized_round_trip(state in uninitialized_state()) {
+        prop_assert_eq!(round_trip(&state), state);
+    }
+
+    #[test]
+    fn repack_is_identical(state in initialized_state()) {
+        let mut first = vec![0; STATE_SPACE];
+        state.pack_into_slice(&mut first);
+        let mut second = vec![0; STATE_SPACE];
+        HihiState::unpack_from_slice(&first).unwrap().pack_into_slice(&mut second);
+        prop_assert_eq!(first, second);
+    }
+
+    #[test]
+    fn add_work_respects_free_space(mut state in initialized_state(), extra in 0..=HEAP_COUNT) {
+        let free = state.get_work_free_space();
+        let result = state.add_work(&vec![1; extra * WORK_BYTES]);
+        prop_assert_eq!(result.is_ok(), extra as i32 <= free);
+    }
+}
+
+#[test]
+fn full_heap_round_trip() {
+    //a heap of `HEAP_COUNT` puzzles, the `MAX_COUNT` heap of the first release is read by
+    //`HihiState::unpack_legacy`, see tests/migrate.rs.
+    let mut state = round_trip(&initialized_state_with_work(HEAP_COUNT));
+    assert_eq!(state.work.len(), HEAP_COUNT);
+    assert_eq!(state.get_work_free_space(), 0);
+    assert!(state.add_work(&[1; WORK_BYTES]).is_err());
+    state.remove_work(0).unwrap();
+    assert_eq!(state.get_work_free_space(), 1);
+}
+
+#[test]
+fn instances_keep_the_first_release_size() {
+    //batches took the room of `MAX_COUNT - HEAP_COUNT` heap puzzles.
+    assert!(HEAP_COUNT < MAX_COUNT);
+    assert_eq!(STATE_SPACE, LEGACY_STATE_SPACE);
+}
+
+#[test]
+fn work_count_above_max_is_rejected() {
+    let mut packed = vec![0; STATE_SPACE];
+    initialized_state_with_work(0).pack_into_slice(&mut packed);
+    let count_offset = STATE_SPACE - RESERVED_BYTES - VESTING_COUNT_BYTES - HEAP_COUNT * WORK_BYTES - 4 - 1;
+    packed[count_offset] = HEAP_COUNT as u8 + 1;
+    assert!(HihiState::unpack_from_slice(&packed).is_err());
+}
+
+#[test]
+fn batch_count_above_max_is_rejected() {
+    let mut packed = vec![0; STATE_SPACE];
+    initialized_state_with_work(0).pack_into_slice(&mut packed);
+    let batch_offset = STATE_SPACE - RESERVED_BYTES - VESTING_COUNT_BYTES - HEAP_COUNT * WORK_BYTES - 4 - 1 - MAX_BATCHES * BATCH_BYTES - 1;
+    packed[batch_offset] = MAX_BATCHES as u8 + 1;
+    assert!(HihiState::unpack_from_slice(&packed).is_err());
+}
+
+#[test]
+fn short_input_is_rejected() {
+    assert!(HihiState::unpack_from_slice(&[1; STATE_SPACE - 1]).is_err());
+}
+
+#[test]
+fn page_fills_to_max_count() {
+    let mut page = PageState::default();
+    page.add_work(&[1; WORK_BYTES * (MAX_COUNT - 1)]).unwrap();
+    assert_eq!(page.get_work_free_space(), 1);
+    assert!(page.add_work(&[2; WORK_BYTES * 2]).is_err());
+    page.add_work(&[2; WORK_BYTES]).unwrap();
+    assert_eq!(page.get_work_free_space(), 0);
+    page.remove_work(0).unwrap();
+    assert_eq!(page.work[0], vec![2; WORK_BYTES]);
+}
+
+#[test]
+fn vesting_releases_linearly() {
+    let mut vesting = VestingState::default();
+    vesting.grant(1000, 10, 4);
+    assert_eq!(vesting.claimable(10), 0);
+    assert_eq!(vesting.release(11), 250);
+    assert_eq!(vesting.release(11), 0);
+    assert_eq!(vesting.release(13), 500);
+    assert_eq!(vesting.release(20), 250);
+    assert_eq!(vesting.claimable(30), 0);
+}
+
+#[test]
+fn vesting_grant_keeps_vested_claimable() {
+    let mut vesting = VestingState::default();
+    vesting.grant(1000, 10, 4);
+    assert_eq!(vesting.release(11), 250);
+    vesting.grant(250, 12, 2);
+    //250 vested at 12 but unclaimed, 500 left of the first grant plus 250 new vest again.
+    assert_eq!(vesting.claimable(12), 250);
+    assert_eq!(vesting.release(13), 250 + 375);
+    assert_eq!(vesting.release(14), 375);
+}
+
+#[test]
+fn supply_is_uncapped_by_default() {
+    let mut state = initialized_state_with_work(0);
+    assert_eq!(state.take_supply(u64::MAX / 2), u64::MAX / 2);
+    assert_eq!(state.remaining_supply(), u64::MAX);
+    assert!(!state.is_supply_exhausted());
+}
+
+#[test]
+fn last_reward_is_cut_to_the_remaining_supply() {
+    let mut state = initialized_state_with_work(0);
+    state.max_supply = 1000;
+    assert_eq!(state.take_supply(600), 600);
+    assert_eq!(state.take_supply(600), 400);
+    assert!(state.is_supply_exhausted());
+    assert_eq!(state.take_supply(1), 0);
+    assert_eq!(state.minted, 1000);
+}
+
+#[test]
+fn max_supply_can_only_be_lowered_to_the_minted_supply() {
+    let mut state = initialized_state_with_work(0);
+    state.minted = 500;
+    assert!(check_max_supply(&state, 0).is_ok());
+    assert!(check_max_supply(&state, 499).is_err());
+    assert!(check_max_supply(&state, 2000).is_ok());
+    state.max_supply = 2000;
+    assert!(check_max_supply(&state, 2000).is_ok());
+    assert!(check_max_supply(&state, 2001).is_err());
+    assert!(check_max_supply(&state, 0).is_err());
+    assert!(check_max_supply(&state, 500).is_ok());
+}
+
+proptest! {
+    #[test]
+    fn page_round_trip(instance_id in pubkey(), index in any::<u32>(), bump in any::<u8>(), work in work(MAX_COUNT)) {
+        let page = PageState {
+            is_initialized: true,
+            instance_id,
+            index,
+            bump,
+            work,
+        };
+        let mut packed = vec![0; PageState::LEN];
+        page.pack_into_slice(&mut packed);
+        prop_assert_eq!(PageState::unpack_from_slice(&packed).unwrap(), page);
+    }
+
+    #[test]
+    fn vesting_pays_out_every_grant(grants in prop::collection::vec((0..u32::MAX as u64, 0..10u64, 0..10u64), 1..10)) {
+        let mut vesting = VestingState::default();
+        let mut epoch = 0;
+        let mut granted = 0;
+        let mut released = 0;
+        for (amount, wait, epochs) in grants {
+            epoch += wait;
+            released += vesting.release(epoch);
+            vesting.grant(amount, epoch, epochs);
+            granted += amount;
+        }
+        released += vesting.release(epoch + 10);
+        prop_assert_eq!(released, granted);
+    }
+
+    #[test]
+    fn vesting_round_trip(total in any::<u64>(), released in any::<u64>(), unlocked in any::<u64>(), start_epoch in any::<u64>(), end_epoch in any::<u64>(), keys in prop::array::uniform2(pubkey())) {
+        let vesting = VestingState {
+            is_initialized: true,
+            instance_id: keys[0],
+            wallet_id: keys[1],
+            bump: 254,
+            total,
+            released,
+            unlocked,
+            start_epoch,
+            end_epoch,
+        };
+        let mut packed = vec![0; VestingState::LEN];
+        vesting.pack_into_slice(&mut packed);
+        prop_assert_eq!(VestingState::unpack_from_slice(&packed).unwrap(), vesting);
+    }
+}
+
+proptest! {
+    #[test]
+    fn miner_round_trip(keys in prop::array::uniform2(pubkey()), bump in any::<u8>(), puzzles_solved in any::<u64>(), tokens_earned in any::<u64>(), limit_breaks in any::<u32>(), first_slot in any::<u64>(), last_slot in any::<u64>(), highest_difficulty in any::<u8>()) {
+        let miner = MinerState {
+            is_initialized: true,
+            instance_id: keys[0],
+            wallet_id: keys[1],
+            bump,
+            puzzles_solved,
+            tokens_earned,
+            limit_breaks,
+            first_slot,
+            last_slot,
+            highest_difficulty,
+        };
+        let mut packed = vec![0; MinerState::LEN];
+        miner.pack_into_slice(&mut packed);
+        prop_assert_eq!(MinerState::unpack_from_slice(&packed).unwrap(), miner);
+    }
+}
+
+#[test]
+fn miner_layout_matches_its_space() {
+    let size: usize = MINER_LAYOUT.iter().map(|(_, _, bytes)| bytes).sum();
+    assert_eq!(size, MINER_SPACE);
+    assert!(MinerState::unpack_from_slice(&[0; MINER_SPACE - 1]).is_err());
+    assert!(MinerState::unpack_from_slice(&[2; MINER_SPACE]).is_err());
+}
+
+#[test]
+fn side_accounts_only_unpack_as_their_own_type() {
+    //a vesting account is long enough to read as a miner, its tag gives it away.
+    let vesting = VestingState {
+        is_initialized: true,
+        instance_id: Pubkey::new_unique(),
+        wallet_id: Pubkey::new_unique(),
+        ..VestingState::default()
+    };
+    let mut packed = vec![0; PageState::LEN];
+    vesting.pack_into_slice(&mut packed);
+    assert_eq!(MinerState::unpack_from_slice(&packed), Err(HihiError::InvalidAccountType.into()));
+    assert_eq!(ReferrerState::unpack_from_slice(&packed), Err(HihiError::InvalidAccountType.into()));
+    assert_eq!(PageState::unpack_from_slice(&packed), Err(HihiError::InvalidAccountType.into()));
+
+    let miner = MinerState {
+        is_initialized: true,
+        ..MinerState::default()
+    };
+    miner.pack_into_slice(&mut packed);
+    assert_eq!(VestingState::unpack_from_slice(&packed), Err(HihiError::InvalidAccountType.into()));
+}
+
+#[test]
+fn miner_records_claims_and_limit_breaks() {
+    let mut miner = MinerState::default();
+    miner.record(100, 50, 3, false);
+    miner.record(120, 25, 2, false);
+    miner.record(150, 500, 6, true);
+    assert_eq!(miner.puzzles_solved, 2);
+    assert_eq!(miner.limit_breaks, 1);
+    assert_eq!(miner.tokens_earned, 575);
+    assert_eq!(miner.first_slot, 100);
+    assert_eq!(miner.last_slot, 150);
+    assert_eq!(miner.highest_difficulty, 6);
+    miner.record(160, 0, 1, false);
+    assert_eq!(miner.highest_difficulty, 6);
+    assert_eq!(miner.first_slot, 100);
+}
+
+fn initialized_state_with_work(count: usize) -> HihiState {
+    HihiState {
+        is_initialized: true,
+        token_mint_id: Pubkey::new_unique(),
+        token_doubles: 3,
+        nonce: 255,
+        current_slot: 1,
+        current_epoch: 2,
+        difficulty: 2,
+        lamports: 4,
+        breach_price: 150000000,
+        breach_remain: 5,
+        breach_count: 6,
+        breach_count_this_window: 7,
+        limit_count: 8,
+        limit_breaks_this_epoch: 9,
+        admin_one_id: Pubkey::new_unique(),
+        admin_two_id: Pubkey::new_unique(),
+        withdraw_id: Pubkey::new_unique(),
+        limit_break: vec![200; LB_BYTES],
+        work_cached: 10,
+        referral_share: 11,
+        vesting_epochs: 3,
+        page_count: 12,
+        batches: Vec::new(),
+        max_supply: 0,
+        minted: 0,
+        halving_epochs: 0,
+        start_epoch: 0,
+        price_scale: 0,
+        price_exponent: 0,
+        surge_bps: 0,
+        last_breach_slot: 0,
+        decay_bps: 0,
+        decay_floor_bps: 0,
+        vesting_count: 0,
+        work: vec![vec![100; WORK_BYTES]; count],
+    }
+}